@@ -2,7 +2,7 @@
 //!
 //! See: https://doc.rust-lang.org/reference/conditional-compilation.html#conditional-compilation
 
-use std::slice::Iter as SliceIter;
+use std::{fmt, slice::Iter as SliceIter};
 
 use ra_syntax::SmolStr;
 use tt::{Leaf, Subtree, TokenTree};
@@ -35,6 +35,30 @@ impl CfgExpr {
     }
 }
 
+impl fmt::Display for CfgExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CfgExpr::Invalid => write!(f, "<invalid>"),
+            CfgExpr::Atom(name) => write!(f, "{}", name),
+            CfgExpr::KeyValue { key, value } => write!(f, "{} = \"{}\"", key, value),
+            CfgExpr::All(preds) => write_list(f, "all", preds),
+            CfgExpr::Any(preds) => write_list(f, "any", preds),
+            CfgExpr::Not(pred) => write!(f, "not({})", pred),
+        }
+    }
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, name: &str, preds: &[CfgExpr]) -> fmt::Result {
+    write!(f, "{}(", name)?;
+    for (i, pred) in preds.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", pred)?;
+    }
+    write!(f, ")")
+}
+
 pub fn parse_cfg(tt: &Subtree) -> CfgExpr {
     next_cfg_expr(&mut tt.token_trees.iter()).unwrap_or(CfgExpr::Invalid)
 }
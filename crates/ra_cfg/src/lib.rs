@@ -33,6 +33,28 @@ impl CfgOptions {
         })
     }
 
+    /// Explains why `cfg` evaluates to false, like `feature = "foo" is not enabled`.
+    /// Returns `None` if it is enabled or can't be evaluated.
+    pub fn explain_disabled(&self, cfg: &CfgExpr) -> Option<String> {
+        if self.check(cfg) != Some(false) {
+            return None;
+        }
+        let res = match cfg {
+            CfgExpr::Invalid => return None,
+            CfgExpr::Atom(_) | CfgExpr::KeyValue { .. } => format!("{} is not enabled", cfg),
+            CfgExpr::All(preds) => {
+                return preds.iter().find_map(|pred| self.explain_disabled(pred));
+            }
+            CfgExpr::Any(preds) => {
+                let reasons: Vec<_> =
+                    preds.iter().filter_map(|pred| self.explain_disabled(pred)).collect();
+                reasons.join(" and ")
+            }
+            CfgExpr::Not(pred) => format!("{} is enabled", pred),
+        };
+        Some(res)
+    }
+
     pub fn is_cfg_enabled(&self, attr: &tt::Subtree) -> Option<bool> {
         self.check(&parse_cfg(attr))
     }
@@ -53,4 +75,47 @@ impl CfgOptions {
     pub fn insert_features(&mut self, iter: impl IntoIterator<Item = SmolStr>) {
         iter.into_iter().for_each(|feat| self.insert_key_value("feature".into(), feat));
     }
+
+    /// The enabled options as `cfg` expressions, in no particular order.
+    pub fn enabled(&self) -> impl Iterator<Item = CfgExpr> + '_ {
+        let atoms = self.atoms.iter().map(|it| CfgExpr::Atom(it.clone()));
+        let key_values = self
+            .key_values
+            .iter()
+            .map(|(key, value)| CfgExpr::KeyValue { key: key.clone(), value: value.clone() });
+        atoms.chain(key_values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_disabled() {
+        let mut options = CfgOptions::default();
+        options.insert_atom("unix".into());
+        options.insert_features(vec!["bar".into()]);
+
+        let feature = |name: &str| CfgExpr::KeyValue { key: "feature".into(), value: name.into() };
+        let atom = |name: &str| CfgExpr::Atom(name.into());
+
+        assert_eq!(options.explain_disabled(&atom("unix")), None);
+        assert_eq!(
+            options.explain_disabled(&feature("foo")).as_deref(),
+            Some(r#"feature = "foo" is not enabled"#)
+        );
+        assert_eq!(
+            options.explain_disabled(&CfgExpr::All(vec![feature("bar"), atom("windows")])),
+            Some("windows is not enabled".to_string())
+        );
+        assert_eq!(
+            options.explain_disabled(&CfgExpr::Any(vec![atom("windows"), atom("macos")])),
+            Some("windows is not enabled and macos is not enabled".to_string())
+        );
+        assert_eq!(
+            options.explain_disabled(&CfgExpr::Not(Box::new(atom("unix")))),
+            Some("unix is enabled".to_string())
+        );
+    }
 }
@@ -6,7 +6,8 @@ pub mod fixture;
 use std::{panic, sync::Arc};
 
 use ra_prof::profile;
-use ra_syntax::{ast, Parse, SourceFile, TextRange, TextSize};
+use ra_syntax::{ast, AstNode, Parse, SourceFile, TextRange, TextSize};
+use ra_text_edit::AtomTextEdit;
 
 pub use crate::{
     cancellation::Canceled,
@@ -98,6 +99,16 @@ pub trait FileLoader {
         extern_id: ExternSourceId,
         relative_path: &RelativePath,
     ) -> Option<FileId>;
+
+    /// Takes the syntax tree the file had before its text was last changed
+    /// incrementally, together with the edits made since. `parse` uses it to
+    /// reparse only the edited parts of the file.
+    fn take_pending_reparse(
+        &self,
+        _file_id: FileId,
+    ) -> Option<(Parse<ast::SourceFile>, Vec<AtomTextEdit>)> {
+        None
+    }
 }
 
 /// Database which stores all significant input facts: source code and project
@@ -116,9 +127,35 @@ pub trait SourceDatabase: CheckCanceled + FileLoader + std::fmt::Debug {
 fn parse_query(db: &impl SourceDatabase, file_id: FileId) -> Parse<ast::SourceFile> {
     let _p = profile("parse_query");
     let text = db.file_text(file_id);
+    // The pending reparse is not tracked by salsa, but that's fine: it is only
+    // used if it yields the same tree as parsing `text` from scratch.
+    if let Some((parse, edits)) = db.take_pending_reparse(file_id) {
+        if let Some(parse) = reparse(parse, &edits, &text) {
+            return parse;
+        }
+    }
     SourceFile::parse(&*text)
 }
 
+/// Applies `edits` to `parse`. Returns `None` if they don't produce `text`,
+/// e.g. because they were computed against another version of the file.
+fn reparse(
+    mut parse: Parse<ast::SourceFile>,
+    edits: &[AtomTextEdit],
+    text: &str,
+) -> Option<Parse<ast::SourceFile>> {
+    for edit in edits {
+        if !parse.tree().syntax().text_range().contains_range(edit.delete) {
+            return None;
+        }
+        parse = parse.reparse(edit);
+    }
+    if parse.tree().syntax().text() != text {
+        return None;
+    }
+    Some(parse)
+}
+
 /// We don't want to give HIR knowledge of source roots, hence we extract these
 /// methods into a separate DB.
 #[salsa::query_group(SourceDatabaseExtStorage)]
@@ -6,9 +6,10 @@ use either::Either;
 use hir_def::{
     adt::StructKind,
     adt::VariantData,
+    body::pretty::print_body,
     builtin_type::BuiltinType,
     docs::Documentation,
-    expr::{BindingAnnotation, Pat, PatId},
+    expr::{BindingAnnotation, ExprId, Pat, PatId},
     per_ns::PerNs,
     resolver::{HasResolver, Resolver},
     type_ref::{Mutability, TypeRef},
@@ -22,8 +23,9 @@ use hir_expand::{
     MacroDefId,
 };
 use hir_ty::{
-    autoderef, display::HirFormatter, expr::ExprValidator, method_resolution, ApplicationTy,
-    Canonical, InEnvironment, Substs, TraitEnvironment, Ty, TyDefId, TypeCtor,
+    autoderef, display::HirFormatter, expr::ExprValidator, layout::Layout, method_resolution,
+    ApplicationTy, Canonical, GenericPredicate, InEnvironment, Substs, TraitEnvironment, Ty,
+    TyDefId, TypeCtor, TypeWalk,
 };
 use ra_db::{CrateId, CrateName, Edition, FileId};
 use ra_prof::profile;
@@ -329,6 +331,30 @@ impl Field {
     pub fn parent_def(&self, _db: &dyn HirDatabase) -> VariantDef {
         self.parent
     }
+
+    /// The layout of the type of this field, as in `signature_ty`.
+    pub fn layout(&self, db: &dyn HirDatabase) -> Option<Layout> {
+        let generic_def_id: GenericDefId = match self.parent {
+            VariantDef::Struct(it) => it.id.into(),
+            VariantDef::Union(it) => it.id.into(),
+            VariantDef::EnumVariant(it) => it.parent.id.into(),
+        };
+        let substs = Substs::type_params(db, generic_def_id);
+        hir_ty::layout::layout_of_field(db, self.parent.into(), self.id, &substs)
+    }
+
+    /// The offset of this field from the start of its struct or union.
+    pub fn offset(&self, db: &dyn HirDatabase) -> Option<u64> {
+        let adt: Adt = match self.parent {
+            VariantDef::Struct(it) => it.into(),
+            VariantDef::Union(it) => it.into(),
+            VariantDef::EnumVariant(_) => return None,
+        };
+        let layout = adt.ty(db).layout(db)?;
+        let fields = self.parent.variant_data(db);
+        let index = fields.fields().iter().position(|(id, _)| id == self.id)?;
+        layout.field_offsets.get(index).copied()
+    }
 }
 
 impl HasVisibility for Field {
@@ -588,6 +614,13 @@ impl DefWithBody {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    Shared,
+    Exclusive,
+    Owned,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Function {
     pub(crate) id: FunctionId,
@@ -610,10 +643,40 @@ impl Function {
         db.function_data(self.id).has_self_param
     }
 
+    /// How the method takes `self`, `None` for associated functions.
+    pub fn self_param_access(self, db: &dyn HirDatabase) -> Option<Access> {
+        let data = db.function_data(self.id);
+        if !data.has_self_param {
+            return None;
+        }
+        let access = match &data.params[0] {
+            TypeRef::Reference(_, Mutability::Shared) => Access::Shared,
+            TypeRef::Reference(_, Mutability::Mut) => Access::Exclusive,
+            _ => Access::Owned,
+        };
+        Some(access)
+    }
+
+    pub fn is_unsafe(self, db: &dyn HirDatabase) -> bool {
+        db.function_data(self.id).is_unsafe
+    }
+
     pub fn params(self, db: &dyn HirDatabase) -> Vec<TypeRef> {
         db.function_data(self.id).params.clone()
     }
 
+    /// The types of the parameters, followed by the return type.
+    pub fn signature_types(self, db: &dyn HirDatabase) -> Vec<Type> {
+        let krate = self.module(db).id.krate;
+        let substs = Substs::type_params(db, self.id);
+        let sig = db.callable_item_signature(self.id.into()).subst(&substs);
+        sig.params()
+            .iter()
+            .chain(std::iter::once(sig.ret()))
+            .map(|ty| Type::new(db, krate, self.id, ty.clone()))
+            .collect()
+    }
+
     pub fn diagnostics(self, db: &dyn HirDatabase, sink: &mut DiagnosticSink) {
         let _p = profile("Function::diagnostics");
         let infer = db.infer(self.id.into());
@@ -621,6 +684,32 @@ impl Function {
         let mut validator = ExprValidator::new(self.id, infer, sink);
         validator.validate_body(db);
     }
+
+    /// A dump of the lowered body, annotated with the inferred types, method
+    /// resolutions and adjustments. Meant for debugging type inference.
+    pub fn debug_hir(self, db: &dyn HirDatabase) -> String {
+        let body = db.body(self.id.into());
+        let infer = db.infer(self.id.into());
+        let annotate_expr = |expr: ExprId| {
+            let mut res = format!(": {}", infer[expr].display(db));
+            if let Some(ty) = infer.adjusted_type_of_expr(expr) {
+                res += &format!(" (adjusted to {})", ty.display(db));
+            }
+            if let Some(mismatch) = infer.type_mismatch_for_expr(expr) {
+                res += &format!(" (expected {})", mismatch.expected.display(db));
+            }
+            let resolved = infer
+                .method_resolution(expr)
+                .map(AssocItemId::FunctionId)
+                .or_else(|| infer.assoc_resolutions_for_expr(expr));
+            if let Some(item) = resolved {
+                res += &format!(" (resolved to {})", AssocItem::from(item).debug_path(db));
+            }
+            res
+        };
+        let annotate_pat = |pat: PatId| format!(": {}", infer[pat].display(db));
+        print_body(&body, &annotate_expr, &annotate_pat)
+    }
 }
 
 impl HasVisibility for Function {
@@ -675,6 +764,10 @@ impl Static {
     pub fn name(self, db: &dyn HirDatabase) -> Option<Name> {
         db.static_data(self.id).name.clone()
     }
+
+    pub fn is_mut(self, db: &dyn HirDatabase) -> bool {
+        db.static_data(self.id).mutable
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -698,6 +791,12 @@ impl Trait {
     pub fn is_auto(self, db: &dyn HirDatabase) -> bool {
         db.trait_data(self.id).auto
     }
+
+    /// The traits listed as bounds on `Self`, like `A` and `B` in
+    /// `trait T: A where Self: B {}`.
+    pub fn direct_super_traits(self, db: &dyn HirDatabase) -> Vec<Trait> {
+        hir_ty::direct_super_traits(db.upcast(), self.id).into_iter().map(Trait::from).collect()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -760,6 +859,14 @@ impl MacroDef {
         self.source(db).value.name().map(|it| it.as_name())
     }
 
+    /// The index of the `macro_rules!` arm which the arguments `arg` of a call
+    /// to this macro match. If the call is still being typed and matches no arm,
+    /// this is the arm which comes closest.
+    pub fn matching_rule(self, db: &dyn HirDatabase, arg: &ast::TokenTree) -> Option<usize> {
+        let expander = db.macro_def(self.id)?;
+        expander.0.matching_rule(arg)
+    }
+
     /// Indicate it is a proc-macro
     pub fn is_proc_macro(&self) -> bool {
         match self.id.kind {
@@ -817,6 +924,13 @@ where
 }
 
 impl AssocItem {
+    pub fn name(self, db: &dyn HirDatabase) -> Option<Name> {
+        match self {
+            AssocItem::Function(it) => Some(it.name(db)),
+            AssocItem::Const(it) => it.name(db),
+            AssocItem::TypeAlias(it) => Some(it.name(db)),
+        }
+    }
     pub fn module(self, db: &dyn HirDatabase) -> Module {
         match self {
             AssocItem::Function(f) => f.module(db),
@@ -836,6 +950,17 @@ impl AssocItem {
             AssocContainerId::ContainerId(_) => panic!("invalid AssocItem"),
         }
     }
+
+    /// The path of the item through its trait or impl, like `<Vec<T>>::len`.
+    pub fn debug_path(self, db: &dyn HirDatabase) -> String {
+        let name = self.name(db).map_or_else(|| "_".to_string(), |it| it.to_string());
+        match self.container(db) {
+            AssocItemContainer::Trait(it) => format!("{}::{}", it.name(db), name),
+            AssocItemContainer::ImplDef(it) => {
+                format!("<{}>::{}", it.target_ty(db).display(db), name)
+            }
+        }
+    }
 }
 
 impl HasVisibility for AssocItem {
@@ -975,6 +1100,11 @@ impl ImplDef {
         db.impl_data(self.id).target_trait.clone()
     }
 
+    /// The trait this impl implements, if it resolves.
+    pub fn target_trait_def(&self, db: &dyn HirDatabase) -> Option<Trait> {
+        db.impl_trait(self.id).map(|it| it.value.trait_.into())
+    }
+
     pub fn target_type(&self, db: &dyn HirDatabase) -> TypeRef {
         db.impl_data(self.id).target_type.clone()
     }
@@ -1081,10 +1211,37 @@ impl Type {
         )
     }
 
+    pub fn is_raw_ptr(&self) -> bool {
+        matches!(self.ty.value, Ty::Apply(ApplicationTy { ctor: TypeCtor::RawPtr(..), .. }))
+    }
+
+    pub fn is_unit(&self) -> bool {
+        matches!(
+            self.ty.value,
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Tuple { cardinality: 0 }, .. })
+        )
+    }
+
     pub fn is_unknown(&self) -> bool {
         matches!(self.ty.value, Ty::Unknown)
     }
 
+    /// The type behind a reference, together with the mutability of the
+    /// reference.
+    pub fn as_reference(&self) -> Option<(Type, Mutability)> {
+        match &self.ty.value {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Ref(mutability), parameters }) => {
+                Some((self.derived(parameters.as_single().clone()), *mutability))
+            }
+            _ => None,
+        }
+    }
+
+    /// How many times `self` has to be dereferenced to become `target`.
+    pub fn deref_steps_to(&self, db: &dyn HirDatabase, target: &Type) -> Option<usize> {
+        self.autoderef(db).position(|it| it.ty.value == target.ty.value)
+    }
+
     /// Checks that particular type `ty` implements `std::future::Future`.
     /// This function is used in `.await` syntax completion.
     pub fn impls_future(&self, db: &dyn HirDatabase) -> bool {
@@ -1107,6 +1264,15 @@ impl Type {
         )
     }
 
+    pub fn is_copy(&self, db: &dyn HirDatabase) -> bool {
+        let lang_item = db.lang_item(self.krate, "copy".into());
+        let copy_trait = match lang_item.and_then(|it| it.as_trait()) {
+            Some(it) => it,
+            None => return false,
+        };
+        self.impls_trait(db, copy_trait.into(), &[])
+    }
+
     pub fn impls_trait(&self, db: &dyn HirDatabase, trait_: Trait, args: &[Type]) -> bool {
         let trait_ref = hir_ty::TraitRef {
             trait_: trait_.id,
@@ -1132,6 +1298,10 @@ impl Type {
         Some(self.ty.value.as_callable()?.0)
     }
 
+    pub fn layout(&self, db: &dyn HirDatabase) -> Option<Layout> {
+        hir_ty::layout::layout_of(db, &self.ty.value)
+    }
+
     pub fn is_closure(&self) -> bool {
         matches!(&self.ty.value, Ty::Apply(ApplicationTy { ctor: TypeCtor::Closure { .. }, .. }))
     }
@@ -1283,6 +1453,35 @@ impl Type {
         Some(adt.into())
     }
 
+    /// The structs, enums, unions and traits this type is built from, like
+    /// `Vec` and `Display` in `Vec<Box<dyn Display>>`.
+    pub fn referenced_defs(&self, _db: &dyn HirDatabase) -> Vec<ModuleDef> {
+        let mut res = Vec::new();
+        self.ty.value.walk(&mut |ty| {
+            let defs: Vec<ModuleDef> = match ty {
+                Ty::Apply(ApplicationTy { ctor: TypeCtor::Adt(adt), .. }) => {
+                    vec![Adt::from(*adt).into()]
+                }
+                Ty::Dyn(predicates) | Ty::Opaque(predicates) => predicates
+                    .iter()
+                    .filter_map(|it| match it {
+                        GenericPredicate::Implemented(trait_ref) => {
+                            Some(Trait::from(trait_ref.trait_).into())
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for def in defs {
+                if !res.contains(&def) {
+                    res.push(def);
+                }
+            }
+        });
+        res
+    }
+
     // FIXME: provide required accessors such that it becomes implementable from outside.
     pub fn is_equal_for_find_impls(&self, other: &Type) -> bool {
         match (&self.ty.value, &other.ty.value) {
@@ -2,10 +2,11 @@
 
 use either::Either;
 use hir_def::{
-    nameres::ModuleSource,
+    nameres::{ModuleOrigin, ModuleSource},
     src::{HasChildSource, HasSource as _},
     Lookup, VariantId,
 };
+use ra_db::FileId;
 use ra_syntax::ast;
 
 use crate::{
@@ -29,6 +30,18 @@ impl Module {
         def_map[self.id.local_id].definition_source(db.upcast())
     }
 
+    /// Returns the file which contains the items of this module. Unlike
+    /// `definition_source`, this doesn't parse the file.
+    pub fn definition_file(self, db: &dyn HirDatabase) -> FileId {
+        let def_map = db.crate_def_map(self.id.krate);
+        match def_map[self.id.local_id].origin {
+            ModuleOrigin::CrateRoot { definition } | ModuleOrigin::File { definition, .. } => {
+                definition
+            }
+            ModuleOrigin::Inline { definition } => definition.file_id.original_file(db.upcast()),
+        }
+    }
+
     /// Returns a node which declares this module, either a `mod foo;` or a `mod foo {}`.
     /// `None` for the crate root.
     pub fn declaration_source(self, db: &dyn HirDatabase) -> Option<InFile<ast::Module>> {
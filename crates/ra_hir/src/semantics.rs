@@ -5,6 +5,8 @@ mod source_to_def;
 use std::{cell::RefCell, fmt, iter::successors};
 
 use hir_def::{
+    body::Body,
+    expr::ExprId,
     resolver::{self, HasResolver, Resolver},
     AsMacroCall, TraitId,
 };
@@ -27,6 +29,16 @@ use crate::{
     Name, Origin, Path, ScopeDef, Trait, Type, TypeParam,
 };
 
+/// Where a token of a macro expansion comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacroTokenOrigin {
+    /// The token was passed to the macro.
+    Call,
+    /// The token is part of the macro definition, like the body of a
+    /// `macro_rules!` rule.
+    Definition,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PathResolution {
     /// An item
@@ -73,6 +85,48 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         Some(node)
     }
 
+    /// Expands the `#[derive]`s of `item`, one node per derive.
+    pub fn expand_derives(&self, item: &ast::ModuleItem) -> Vec<SyntaxNode> {
+        let item = self.find_file(item.syntax().clone()).with_value(item);
+        let sa = self.analyze2(item.map(|it| it.syntax()), None);
+        hir_def::derive_macro_calls(self.db, item, |path| {
+            sa.resolver.resolve_path_as_macro(self.db, &path)
+        })
+        .into_iter()
+        .filter_map(|call| {
+            let file_id = call.as_file();
+            let node = self.db.parse_or_expand(file_id)?;
+            self.cache(node.clone(), file_id);
+            Some(node)
+        })
+        .collect()
+    }
+
+    /// Maps a token of a macro expansion back to the token it was produced
+    /// from, through all levels of expansion. Returns `None` for tokens which
+    /// aren't part of an expansion, or which the macro made up.
+    pub fn macro_token_origin(&self, token: &SyntaxToken) -> Option<(FileRange, MacroTokenOrigin)> {
+        let mut token = self.find_file(token.parent()).with_value(token.clone());
+        let mut origin = None;
+        while let Some(info) = token.file_id.expansion_info(self.db) {
+            let (up, step) = info.map_token_up(token.as_ref())?;
+            if step == Origin::Def {
+                origin = Some(MacroTokenOrigin::Definition);
+            }
+            origin = origin.or(Some(MacroTokenOrigin::Call));
+            token = up;
+        }
+        // Eager macros have no expansion info yet.
+        if token.file_id.call_node(self.db).is_some() {
+            return None;
+        }
+        let range = FileRange {
+            file_id: token.file_id.original_file(self.db),
+            range: token.value.text_range(),
+        };
+        Some((range, origin?))
+    }
+
     pub fn expand_hypothetical(
         &self,
         actual_macro_call: &ast::MacroCall,
@@ -225,6 +279,42 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
             .unwrap_or_default()
     }
 
+    /// The `return`s, `?`s and tail expressions through which the function or
+    /// closure with the body `body` returns.
+    pub fn exit_points(&self, body: &ast::Expr) -> Vec<ast::Expr> {
+        self.related_exprs(body, |it, expr| it.exit_points(expr))
+    }
+
+    /// The `break`s and `continue`s of the loop `loop_expr`.
+    pub fn loop_exits(&self, loop_expr: &ast::Expr) -> Vec<ast::Expr> {
+        self.related_exprs(loop_expr, |it, expr| it.loop_exits(expr))
+    }
+
+    /// The loop a `break` or `continue` expression leaves.
+    pub fn break_target(&self, expr: &ast::Expr) -> Option<ast::Expr> {
+        self.related_exprs(expr, |it, expr| it.break_target(expr).into_iter().collect()).pop()
+    }
+
+    /// The `.await`s of the async function or closure with the body `body`.
+    pub fn await_points(&self, body: &ast::Expr) -> Vec<ast::Expr> {
+        self.related_exprs(body, |it, expr| it.await_points(expr))
+    }
+
+    fn related_exprs(
+        &self,
+        expr: &ast::Expr,
+        f: impl FnOnce(&Body, ExprId) -> Vec<ExprId>,
+    ) -> Vec<ast::Expr> {
+        let exprs = self.analyze(expr.syntax()).related_exprs(self.db, expr, f).unwrap_or_default();
+        exprs
+            .into_iter()
+            .map(|it| {
+                self.cache(find_root(it.value.syntax()), it.file_id);
+                it.value
+            })
+            .collect()
+    }
+
     pub fn to_def<T: ToDef>(&self, src: &T) -> Option<T::Def> {
         let src = self.find_file(src.syntax().clone()).with_value(src).cloned();
         T::to_def(self, src)
@@ -270,6 +270,28 @@ impl SourceAnalyzer {
         Some(res)
     }
 
+    /// Maps `expr` into the body, calls `f` with it and maps the resulting
+    /// expressions back to the source. Desugared expressions are skipped.
+    pub(crate) fn related_exprs(
+        &self,
+        db: &dyn HirDatabase,
+        expr: &ast::Expr,
+        f: impl FnOnce(&Body, ExprId) -> Vec<ExprId>,
+    ) -> Option<Vec<InFile<ast::Expr>>> {
+        let body = self.body.as_ref()?;
+        let source_map = self.body_source_map.as_ref()?;
+        let expr_id = self.expr_id(db, expr)?;
+        let res = f(body, expr_id)
+            .into_iter()
+            .filter_map(|it| {
+                let src = source_map.expr_syntax(it).ok()?;
+                let root = db.parse_or_expand(src.file_id)?;
+                Some(src.map(|ptr| ptr.to_node(&root)))
+            })
+            .collect();
+        Some(res)
+    }
+
     fn missing_fields(
         &self,
         db: &dyn HirDatabase,
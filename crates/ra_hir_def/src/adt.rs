@@ -10,11 +10,12 @@ use hir_expand::{
 use ra_arena::{map::ArenaMap, Arena};
 use ra_prof::profile;
 use ra_syntax::ast::{self, NameOwner, TypeAscriptionOwner, VisibilityOwner};
+use tt::{Leaf, Subtree, TokenTree};
 
 use crate::{
-    body::CfgExpander, db::DefDatabase, src::HasChildSource, src::HasSource, trace::Trace,
-    type_ref::TypeRef, visibility::RawVisibility, EnumId, HasModule, LocalEnumVariantId,
-    LocalFieldId, Lookup, ModuleId, StructId, UnionId, VariantId,
+    attr::Attrs, body::CfgExpander, builtin_type::BuiltinInt, db::DefDatabase, src::HasChildSource,
+    src::HasSource, trace::Trace, type_ref::TypeRef, visibility::RawVisibility, EnumId, HasModule,
+    LocalEnumVariantId, LocalFieldId, Lookup, ModuleId, StructId, UnionId, VariantId,
 };
 
 /// Note that we use `StructData` for unions as well!
@@ -22,12 +23,89 @@ use crate::{
 pub struct StructData {
     pub name: Name,
     pub variant_data: Arc<VariantData>,
+    pub repr: ReprOptions,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EnumData {
     pub name: Name,
     pub variants: Arena<EnumVariantData>,
+    pub repr: ReprOptions,
+}
+
+/// The attributes which affect the memory layout of an ADT, mostly `#[repr]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReprOptions {
+    /// `repr(C)`: fields are laid out in declaration order.
+    pub c: bool,
+    pub transparent: bool,
+    /// `repr(packed)` or `repr(packed(N))`, the maximum alignment of fields.
+    pub packed: Option<u64>,
+    /// `repr(align(N))`.
+    pub align: Option<u64>,
+    /// `repr(u8)` and friends, the type of the discriminant of an enum.
+    pub int: Option<BuiltinInt>,
+    /// Set if there are options we don't understand, like `repr(simd)`.
+    pub unknown: bool,
+    /// `#[rustc_layout_scalar_valid_range_start(N)]`, which marks the values
+    /// below `N` as invalid, as `NonNull` does for null.
+    pub valid_range_start: Option<u64>,
+}
+
+impl ReprOptions {
+    fn from_attrs(attrs: &Attrs) -> ReprOptions {
+        let mut res = ReprOptions::default();
+        for subtree in attrs.by_key("repr").tt_values() {
+            let mut tts = subtree.token_trees.iter().peekable();
+            while let Some(tt) = tts.next() {
+                let option = match tt {
+                    TokenTree::Leaf(Leaf::Ident(it)) => it.text.as_str(),
+                    // The commas between the options.
+                    _ => continue,
+                };
+                let arg = match tts.peek() {
+                    Some(TokenTree::Subtree(it)) => {
+                        tts.next();
+                        Some(it)
+                    }
+                    _ => None,
+                };
+                match option {
+                    "C" => res.c = true,
+                    "transparent" => res.transparent = true,
+                    "packed" => match arg.map_or(Some(1), power_of_two_arg) {
+                        Some(pack) => res.packed = Some(pack),
+                        None => res.unknown = true,
+                    },
+                    "align" => match arg.and_then(power_of_two_arg) {
+                        Some(align) => res.align = Some(align),
+                        None => res.unknown = true,
+                    },
+                    _ => match BuiltinInt::from_suffix(option) {
+                        Some(int) => res.int = Some(int),
+                        None => res.unknown = true,
+                    },
+                }
+            }
+        }
+        res.valid_range_start =
+            attrs.by_key("rustc_layout_scalar_valid_range_start").tt_values().find_map(int_arg);
+        res
+    }
+}
+
+/// Reads `8` from the `(8)` in `align(8)`.
+fn int_arg(subtree: &Subtree) -> Option<u64> {
+    match subtree.token_trees.as_slice() {
+        [TokenTree::Leaf(Leaf::Literal(lit))] => lit.text.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Reads the argument of `packed(N)` and `align(N)`, which rustc requires to
+/// be a power of two.
+fn power_of_two_arg(subtree: &Subtree) -> Option<u64> {
+    int_arg(subtree).filter(|it| it.is_power_of_two())
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -59,7 +137,8 @@ impl StructData {
         let variant_data =
             VariantData::new(db, src.map(|s| s.kind()), id.lookup(db).container.module(db));
         let variant_data = Arc::new(variant_data);
-        Arc::new(StructData { name, variant_data })
+        let repr = ReprOptions::from_attrs(&db.attrs(id.into()));
+        Arc::new(StructData { name, variant_data, repr })
     }
     pub(crate) fn union_data_query(db: &dyn DefDatabase, id: UnionId) -> Arc<StructData> {
         let src = id.lookup(db).source(db);
@@ -74,7 +153,8 @@ impl StructData {
             id.lookup(db).container.module(db),
         );
         let variant_data = Arc::new(variant_data);
-        Arc::new(StructData { name, variant_data })
+        let repr = ReprOptions::from_attrs(&db.attrs(id.into()));
+        Arc::new(StructData { name, variant_data, repr })
     }
 }
 
@@ -85,7 +165,8 @@ impl EnumData {
         let name = src.value.name().map_or_else(Name::missing, |n| n.as_name());
         let mut trace = Trace::new_for_arena();
         lower_enum(db, &mut trace, &src, e.lookup(db).container.module(db));
-        Arc::new(EnumData { name, variants: trace.into_arena() })
+        let repr = ReprOptions::from_attrs(&db.attrs(e.into()));
+        Arc::new(EnumData { name, variants: trace.into_arena(), repr })
     }
 
     pub fn variant(&self, name: &Name) -> Option<LocalEnumVariantId> {
@@ -5,7 +5,7 @@ use std::{ops, sync::Arc};
 use either::Either;
 use hir_expand::{hygiene::Hygiene, AstId, InFile};
 use mbe::ast_to_token_tree;
-use ra_cfg::CfgOptions;
+use ra_cfg::{parse_cfg, CfgExpr, CfgOptions};
 use ra_syntax::{
     ast::{self, AstNode, AttrsOwner},
     SmolStr,
@@ -140,6 +140,16 @@ impl Attr {
     }
 }
 
+/// Parses the condition of `attr`, if it is a `#[cfg(...)]` attribute.
+pub fn parse_cfg_attr(attr: &ast::Attr) -> Option<CfgExpr> {
+    let (name, tt) = attr.as_simple_call()?;
+    if name != "cfg" {
+        return None;
+    }
+    let (tt, _) = ast_to_token_tree(&tt)?;
+    Some(parse_cfg(&tt))
+}
+
 pub struct AttrQuery<'a> {
     attrs: &'a Attrs,
     key: &'static str,
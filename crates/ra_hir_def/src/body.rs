@@ -1,6 +1,8 @@
 //! Defines `Body`: a lowered representation of bodies of functions, statics and
 //! consts.
+mod control_flow;
 mod lower;
+pub mod pretty;
 pub mod scope;
 
 use std::{mem, ops::Index, sync::Arc};
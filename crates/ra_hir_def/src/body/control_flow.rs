@@ -0,0 +1,157 @@
+//! Finds the expressions which transfer control out of a function, a closure
+//! or a loop.
+
+use hir_expand::name::Name;
+
+use crate::{
+    body::Body,
+    expr::{Expr, ExprId},
+};
+
+impl Body {
+    /// The expressions through which the function, closure or async block with
+    /// the body `body_expr` returns: `return`s, `?`s and the tail expressions.
+    /// When a `loop` is in tail position, its `break`s are exit points as well.
+    ///
+    /// Closures and async blocks nested in the body have their own exit points
+    /// and are skipped.
+    pub fn exit_points(&self, body_expr: ExprId) -> Vec<ExprId> {
+        let mut res = Vec::new();
+        self.walk_exprs(body_expr, &mut |id, expr| match expr {
+            Expr::Return { .. } | Expr::Try { .. } => {
+                res.push(id);
+                true
+            }
+            Expr::Lambda { .. } | Expr::Async { .. } => id == body_expr,
+            _ => true,
+        });
+        self.collect_tail_exprs(body_expr, &mut res);
+        res
+    }
+
+    /// The `break`s and `continue`s which target the loop `loop_expr`. Inside
+    /// nested loops, only those labeled with the label of `loop_expr` count.
+    pub fn loop_exits(&self, loop_expr: ExprId) -> Vec<ExprId> {
+        let (body, label) = match &self[loop_expr] {
+            Expr::Loop { body, label }
+            | Expr::While { body, label, .. }
+            | Expr::For { body, label, .. } => (*body, label.as_ref()),
+            _ => return Vec::new(),
+        };
+        let mut res = Vec::new();
+        self.collect_loop_exits(body, label, 0, &mut res);
+        res
+    }
+
+    /// The loop which the `break` or `continue` expression `expr` targets.
+    pub fn break_target(&self, expr: ExprId) -> Option<ExprId> {
+        match &self[expr] {
+            Expr::Break { .. } | Expr::Continue { .. } => (),
+            _ => return None,
+        }
+        self.exprs
+            .iter()
+            .map(|(id, _)| id)
+            .find(|&id| is_loop(&self[id]) && self.loop_exits(id).contains(&expr))
+    }
+
+    /// The `.await`s of the async function, closure or block with the body
+    /// `body_expr`, without those in nested closures and async blocks.
+    pub fn await_points(&self, body_expr: ExprId) -> Vec<ExprId> {
+        let mut res = Vec::new();
+        self.walk_exprs(body_expr, &mut |id, expr| match expr {
+            Expr::Await { .. } => {
+                res.push(id);
+                true
+            }
+            Expr::Lambda { .. } | Expr::Async { .. } => id == body_expr,
+            _ => true,
+        });
+        res
+    }
+
+    /// Calls `f` for `expr` and its descendants, skipping the descendants of
+    /// those for which `f` returns `false`.
+    fn walk_exprs(&self, expr: ExprId, f: &mut dyn FnMut(ExprId, &Expr) -> bool) {
+        if f(expr, &self[expr]) {
+            self[expr].walk_child_exprs(|child| self.walk_exprs(child, f));
+        }
+    }
+
+    fn collect_tail_exprs(&self, expr: ExprId, acc: &mut Vec<ExprId>) {
+        match &self[expr] {
+            Expr::Block { tail, .. } => {
+                if let Some(tail) = tail {
+                    self.collect_tail_exprs(*tail, acc);
+                }
+            }
+            Expr::If { then_branch, else_branch, .. } => {
+                self.collect_tail_exprs(*then_branch, acc);
+                if let Some(else_branch) = else_branch {
+                    self.collect_tail_exprs(*else_branch, acc);
+                }
+            }
+            Expr::Match { arms, .. } => {
+                for arm in arms {
+                    self.collect_tail_exprs(arm.expr, acc);
+                }
+            }
+            Expr::Loop { .. } => {
+                let breaks = self.loop_exits(expr).into_iter().filter(|&it| match &self[it] {
+                    Expr::Break { .. } => true,
+                    _ => false,
+                });
+                acc.extend(breaks);
+            }
+            Expr::Missing | Expr::Return { .. } | Expr::Continue { .. } | Expr::Break { .. } => {}
+            _ => {
+                if !acc.contains(&expr) {
+                    acc.push(expr);
+                }
+            }
+        }
+    }
+
+    fn collect_loop_exits(
+        &self,
+        expr: ExprId,
+        label: Option<&Name>,
+        nested_loops: usize,
+        acc: &mut Vec<ExprId>,
+    ) {
+        let expr_data = &self[expr];
+        let nested_loops = match expr_data {
+            Expr::Break { label: target, .. } | Expr::Continue { label: target } => {
+                let targets_loop = match target {
+                    Some(target) => Some(target) == label,
+                    None => nested_loops == 0,
+                };
+                if targets_loop {
+                    acc.push(expr);
+                }
+                nested_loops
+            }
+            // `break` can't leave a closure.
+            Expr::Lambda { .. } => return,
+            Expr::Loop { label: inner, .. }
+            | Expr::While { label: inner, .. }
+            | Expr::For { label: inner, .. } => {
+                // The inner loop shadows our label.
+                if inner.is_some() && inner.as_ref() == label {
+                    return;
+                }
+                nested_loops + 1
+            }
+            _ => nested_loops,
+        };
+        expr_data
+            .walk_child_exprs(|child| self.collect_loop_exits(child, label, nested_loops, acc));
+    }
+}
+
+fn is_loop(expr: &Expr) -> bool {
+    match expr {
+        Expr::Loop { .. } | Expr::While { .. } | Expr::For { .. } => true,
+        _ => false,
+    }
+}
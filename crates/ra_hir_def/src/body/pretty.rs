@@ -0,0 +1,453 @@
+//! A tree-shaped dump of a lowered `Body`, for debugging. Each expression and
+//! pattern is printed on its own line, followed by an annotation supplied by
+//! the caller, like its inferred type.
+
+use hir_expand::name::Name;
+use ra_syntax::ast::RangeOp;
+use stdx::SepBy;
+
+use crate::{
+    body::Body,
+    builtin_type::BuiltinType,
+    expr::{
+        ArithOp, Array, BinaryOp, BindingAnnotation, CmpOp, Expr, ExprId, Literal, LogicOp,
+        Ordering, Pat, PatId, Statement, UnaryOp,
+    },
+    path::{GenericArg, GenericArgs, Path, PathKind},
+    type_ref::{TypeBound, TypeRef},
+};
+
+pub fn print_body(
+    body: &Body,
+    annotate_expr: &dyn Fn(ExprId) -> String,
+    annotate_pat: &dyn Fn(PatId) -> String,
+) -> String {
+    let mut p = Printer { body, annotate_expr, annotate_pat, buf: String::new(), indent: 0 };
+    for &param in &body.params {
+        p.pat("param", param);
+    }
+    p.expr("body", body.body_expr);
+    p.buf
+}
+
+struct Printer<'a> {
+    body: &'a Body,
+    annotate_expr: &'a dyn Fn(ExprId) -> String,
+    annotate_pat: &'a dyn Fn(PatId) -> String,
+    buf: String,
+    indent: usize,
+}
+
+impl Printer<'_> {
+    fn line(&mut self, role: &str, label: &str, annotation: &str) {
+        for _ in 0..self.indent {
+            self.buf.push_str("  ");
+        }
+        if !role.is_empty() {
+            self.buf.push_str(role);
+            self.buf.push_str(": ");
+        }
+        self.buf.push_str(label);
+        self.buf.push_str(annotation);
+        self.buf.push('\n');
+    }
+
+    fn expr(&mut self, role: &str, id: ExprId) {
+        let body = self.body;
+        let expr = &body[id];
+        let label = match expr {
+            Expr::Missing => "Missing".to_string(),
+            Expr::Path(path) => format!("Path `{}`", print_path(path)),
+            Expr::If { .. } => "If".to_string(),
+            Expr::Block { .. } => "Block".to_string(),
+            Expr::Loop { label, .. } => with_label("Loop", label.as_ref()),
+            Expr::While { label, .. } => with_label("While", label.as_ref()),
+            Expr::For { label, .. } => with_label("For", label.as_ref()),
+            Expr::Call { .. } => "Call".to_string(),
+            Expr::MethodCall { method_name, generic_args, .. } => match generic_args {
+                Some(args) => format!("MethodCall `{}::{}`", method_name, print_generic_args(args)),
+                None => format!("MethodCall `{}`", method_name),
+            },
+            Expr::Match { .. } => "Match".to_string(),
+            Expr::Continue { label } => with_label("Continue", label.as_ref()),
+            Expr::Break { label, .. } => with_label("Break", label.as_ref()),
+            Expr::Return { .. } => "Return".to_string(),
+            Expr::RecordLit { path, .. } => with_path("RecordLit", path.as_ref()),
+            Expr::Field { name, .. } => format!("Field `{}`", name),
+            Expr::Await { .. } => "Await".to_string(),
+            Expr::Try { .. } => "Try".to_string(),
+            Expr::TryBlock { .. } => "TryBlock".to_string(),
+            Expr::Async { .. } => "Async".to_string(),
+            Expr::Cast { type_ref, .. } => format!("Cast `{}`", print_type_ref(type_ref)),
+            Expr::Ref { mutability, .. } => format!("Ref `&{}`", mutability.as_keyword_for_ref()),
+            Expr::Box { .. } => "Box".to_string(),
+            Expr::UnaryOp { op, .. } => format!("UnaryOp `{}`", unary_op_text(*op)),
+            Expr::BinaryOp { op, .. } => match op {
+                Some(op) => format!("BinaryOp `{}`", binary_op_text(*op)),
+                None => "BinaryOp".to_string(),
+            },
+            Expr::Range { range_type, .. } => match range_type {
+                RangeOp::Exclusive => "Range `..`".to_string(),
+                RangeOp::Inclusive => "Range `..=`".to_string(),
+            },
+            Expr::Index { .. } => "Index".to_string(),
+            Expr::Lambda { ret_type, .. } => match ret_type {
+                Some(ty) => format!("Closure `-> {}`", print_type_ref(ty)),
+                None => "Closure".to_string(),
+            },
+            Expr::Tuple { .. } => "Tuple".to_string(),
+            Expr::Array(_) => "Array".to_string(),
+            Expr::Literal(lit) => format!("Literal {}", literal_text(lit)),
+        };
+        let annotation = (self.annotate_expr)(id);
+        self.line(role, &label, &annotation);
+
+        self.indent += 1;
+        match expr {
+            Expr::Missing
+            | Expr::Path(_)
+            | Expr::Continue { .. }
+            | Expr::Literal(_)
+            | Expr::Break { expr: None, .. }
+            | Expr::Return { expr: None } => {}
+            Expr::If { condition, then_branch, else_branch } => {
+                self.expr("condition", *condition);
+                self.expr("then", *then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.expr("else", *else_branch);
+                }
+            }
+            Expr::Block { statements, tail } => {
+                for stmt in statements {
+                    match stmt {
+                        Statement::Let { pat, type_ref, initializer } => {
+                            let label = match type_ref {
+                                Some(ty) => format!("Let `{}`", print_type_ref(ty)),
+                                None => "Let".to_string(),
+                            };
+                            self.line("", &label, "");
+                            self.indent += 1;
+                            self.pat("pat", *pat);
+                            if let Some(init) = initializer {
+                                self.expr("init", *init);
+                            }
+                            self.indent -= 1;
+                        }
+                        Statement::Expr(expr) => self.expr("", *expr),
+                    }
+                }
+                if let Some(tail) = tail {
+                    self.expr("tail", *tail);
+                }
+            }
+            Expr::Loop { body, .. } | Expr::TryBlock { body } | Expr::Async { body } => {
+                self.expr("body", *body)
+            }
+            Expr::While { condition, body, .. } => {
+                self.expr("condition", *condition);
+                self.expr("body", *body);
+            }
+            Expr::For { iterable, pat, body, .. } => {
+                self.pat("pat", *pat);
+                self.expr("iterable", *iterable);
+                self.expr("body", *body);
+            }
+            Expr::Call { callee, args } => {
+                self.expr("callee", *callee);
+                for &arg in args {
+                    self.expr("arg", arg);
+                }
+            }
+            Expr::MethodCall { receiver, args, .. } => {
+                self.expr("receiver", *receiver);
+                for &arg in args {
+                    self.expr("arg", arg);
+                }
+            }
+            Expr::Match { expr, arms } => {
+                self.expr("scrutinee", *expr);
+                for arm in arms {
+                    self.line("", "MatchArm", "");
+                    self.indent += 1;
+                    self.pat("pat", arm.pat);
+                    if let Some(guard) = arm.guard {
+                        self.expr("guard", guard);
+                    }
+                    self.expr("expr", arm.expr);
+                    self.indent -= 1;
+                }
+            }
+            Expr::Break { expr: Some(expr), .. } | Expr::Return { expr: Some(expr) } => {
+                self.expr("expr", *expr)
+            }
+            Expr::RecordLit { fields, spread, .. } => {
+                for field in fields {
+                    self.expr(&field.name.to_string(), field.expr);
+                }
+                if let Some(spread) = spread {
+                    self.expr("spread", *spread);
+                }
+            }
+            Expr::Field { expr, .. }
+            | Expr::Await { expr }
+            | Expr::Try { expr }
+            | Expr::Cast { expr, .. }
+            | Expr::Ref { expr, .. }
+            | Expr::Box { expr }
+            | Expr::UnaryOp { expr, .. } => self.expr("expr", *expr),
+            Expr::BinaryOp { lhs, rhs, .. } => {
+                self.expr("lhs", *lhs);
+                self.expr("rhs", *rhs);
+            }
+            Expr::Range { lhs, rhs, .. } => {
+                if let Some(lhs) = lhs {
+                    self.expr("lhs", *lhs);
+                }
+                if let Some(rhs) = rhs {
+                    self.expr("rhs", *rhs);
+                }
+            }
+            Expr::Index { base, index } => {
+                self.expr("base", *base);
+                self.expr("index", *index);
+            }
+            Expr::Lambda { args, arg_types, body, .. } => {
+                for (&arg, ty) in args.iter().zip(arg_types) {
+                    match ty {
+                        Some(ty) => self.pat(&format!("param `{}`", print_type_ref(ty)), arg),
+                        None => self.pat("param", arg),
+                    }
+                }
+                self.expr("body", *body);
+            }
+            Expr::Tuple { exprs } | Expr::Array(Array::ElementList(exprs)) => {
+                for &expr in exprs {
+                    self.expr("", expr);
+                }
+            }
+            Expr::Array(Array::Repeat { initializer, repeat }) => {
+                self.expr("initializer", *initializer);
+                self.expr("repeat", *repeat);
+            }
+        }
+        self.indent -= 1;
+    }
+
+    fn pat(&mut self, role: &str, id: PatId) {
+        let body = self.body;
+        let pat = &body[id];
+        let label = match pat {
+            Pat::Missing => "Missing".to_string(),
+            Pat::Wild => "Wild".to_string(),
+            Pat::Tuple { .. } => "Tuple".to_string(),
+            Pat::Or(_) => "Or".to_string(),
+            Pat::Record { path, .. } => with_path("Record", path.as_ref()),
+            Pat::TupleStruct { path, .. } => with_path("TupleStruct", path.as_ref()),
+            Pat::Range { .. } => "Range".to_string(),
+            Pat::Slice { .. } => "Slice".to_string(),
+            Pat::Path(path) => format!("Path `{}`", print_path(path)),
+            Pat::Lit(_) => "Lit".to_string(),
+            Pat::Bind { mode, name, .. } => {
+                let mode = match mode {
+                    BindingAnnotation::Unannotated => "",
+                    BindingAnnotation::Mutable => "mut ",
+                    BindingAnnotation::Ref => "ref ",
+                    BindingAnnotation::RefMut => "ref mut ",
+                };
+                format!("Bind `{}{}`", mode, name)
+            }
+            Pat::Ref { mutability, .. } => format!("Ref `&{}`", mutability.as_keyword_for_ref()),
+        };
+        let annotation = (self.annotate_pat)(id);
+        self.line(role, &label, &annotation);
+
+        self.indent += 1;
+        match pat {
+            Pat::Missing | Pat::Wild | Pat::Path(_) | Pat::Bind { subpat: None, .. } => {}
+            Pat::Tuple { args, ellipsis } | Pat::TupleStruct { args, ellipsis, .. } => {
+                for (i, &arg) in args.iter().enumerate() {
+                    if Some(i) == *ellipsis {
+                        self.line("", "..", "");
+                    }
+                    self.pat("", arg);
+                }
+                if Some(args.len()) == *ellipsis {
+                    self.line("", "..", "");
+                }
+            }
+            Pat::Or(pats) => {
+                for &pat in pats {
+                    self.pat("", pat);
+                }
+            }
+            Pat::Record { args, ellipsis, .. } => {
+                for field in args {
+                    self.pat(&field.name.to_string(), field.pat);
+                }
+                if *ellipsis {
+                    self.line("", "..", "");
+                }
+            }
+            Pat::Range { start, end } => {
+                self.expr("start", *start);
+                self.expr("end", *end);
+            }
+            Pat::Slice { prefix, slice, suffix } => {
+                for &pat in prefix {
+                    self.pat("", pat);
+                }
+                if let Some(slice) = slice {
+                    self.pat("rest", *slice);
+                }
+                for &pat in suffix {
+                    self.pat("", pat);
+                }
+            }
+            Pat::Lit(expr) => self.expr("", *expr),
+            Pat::Bind { subpat: Some(pat), .. } | Pat::Ref { pat, .. } => self.pat("", *pat),
+        }
+        self.indent -= 1;
+    }
+}
+
+fn with_label(kind: &str, label: Option<&Name>) -> String {
+    match label {
+        Some(label) => format!("{} `{}`", kind, label),
+        None => kind.to_string(),
+    }
+}
+
+fn with_path(kind: &str, path: Option<&Path>) -> String {
+    match path {
+        Some(path) => format!("{} `{}`", kind, print_path(path)),
+        None => kind.to_string(),
+    }
+}
+
+fn literal_text(lit: &Literal) -> String {
+    // Only the kind and suffix of most literals survive lowering.
+    match lit {
+        Literal::String(_) => "string".to_string(),
+        Literal::ByteString(_) => "byte string".to_string(),
+        Literal::Char(_) => "char".to_string(),
+        Literal::Bool(it) => format!("`{}`", it),
+        Literal::Int(_, Some(ty)) => format!("int `{}`", BuiltinType::Int(*ty)),
+        Literal::Int(_, None) => "int".to_string(),
+        Literal::Float(_, Some(ty)) => format!("float `{}`", BuiltinType::Float(*ty)),
+        Literal::Float(_, None) => "float".to_string(),
+    }
+}
+
+fn unary_op_text(op: UnaryOp) -> &'static str {
+    match op {
+        UnaryOp::Deref => "*",
+        UnaryOp::Not => "!",
+        UnaryOp::Neg => "-",
+    }
+}
+
+fn binary_op_text(op: BinaryOp) -> String {
+    match op {
+        BinaryOp::LogicOp(LogicOp::And) => "&&".to_string(),
+        BinaryOp::LogicOp(LogicOp::Or) => "||".to_string(),
+        BinaryOp::ArithOp(op) => arith_op_text(op).to_string(),
+        BinaryOp::CmpOp(CmpOp::Eq { negated }) => (if negated { "!=" } else { "==" }).to_string(),
+        BinaryOp::CmpOp(CmpOp::Ord { ordering, strict }) => {
+            let op = match ordering {
+                Ordering::Less => "<",
+                Ordering::Greater => ">",
+            };
+            if strict {
+                op.to_string()
+            } else {
+                format!("{}=", op)
+            }
+        }
+        BinaryOp::Assignment { op } => format!("{}=", op.map_or("", arith_op_text)),
+    }
+}
+
+fn arith_op_text(op: ArithOp) -> &'static str {
+    match op {
+        ArithOp::Add => "+",
+        ArithOp::Mul => "*",
+        ArithOp::Sub => "-",
+        ArithOp::Div => "/",
+        ArithOp::Rem => "%",
+        ArithOp::Shl => "<<",
+        ArithOp::Shr => ">>",
+        ArithOp::BitXor => "^",
+        ArithOp::BitOr => "|",
+        ArithOp::BitAnd => "&",
+    }
+}
+
+fn print_path(path: &Path) -> String {
+    let mut res = String::new();
+    if let Some(anchor) = path.type_anchor() {
+        res.push_str(&format!("<{}>", print_type_ref(anchor)));
+    }
+    let prefix = match path.kind() {
+        PathKind::Plain => String::new(),
+        PathKind::Super(0) => "self::".to_string(),
+        PathKind::Super(n) => "super::".repeat(*n as usize),
+        PathKind::Crate => "crate::".to_string(),
+        PathKind::Abs => "::".to_string(),
+        PathKind::DollarCrate(_) => "$crate::".to_string(),
+    };
+    res.push_str(&prefix);
+    let segments = path.segments().iter().map(|segment| match segment.args_and_bindings {
+        Some(args) => format!("{}{}", segment.name, print_generic_args(args)),
+        None => segment.name.to_string(),
+    });
+    res.push_str(&segments.sep_by("::").to_string());
+    res
+}
+
+fn print_generic_args(args: &GenericArgs) -> String {
+    let args = args.args.iter().skip(if args.has_self_type { 1 } else { 0 });
+    args.map(|GenericArg::Type(ty)| print_type_ref(ty))
+        .sep_by(", ")
+        .surround_with("<", ">")
+        .to_string()
+}
+
+fn print_type_ref(type_ref: &TypeRef) -> String {
+    match type_ref {
+        TypeRef::Never => "!".to_string(),
+        TypeRef::Placeholder => "_".to_string(),
+        TypeRef::Tuple(fields) if fields.len() == 1 => format!("({},)", print_type_ref(&fields[0])),
+        TypeRef::Tuple(fields) => {
+            fields.iter().map(print_type_ref).sep_by(", ").surround_with("(", ")").to_string()
+        }
+        TypeRef::Path(path) => print_path(path),
+        TypeRef::RawPtr(inner, mutability) => {
+            format!("*{}{}", mutability.as_keyword_for_ptr(), print_type_ref(inner))
+        }
+        TypeRef::Reference(inner, mutability) => {
+            format!("&{}{}", mutability.as_keyword_for_ref(), print_type_ref(inner))
+        }
+        TypeRef::Array(inner, Some(len)) => format!("[{}; {}]", print_type_ref(inner), len),
+        TypeRef::Array(inner, None) => format!("[{}; _]", print_type_ref(inner)),
+        TypeRef::Slice(inner) => format!("[{}]", print_type_ref(inner)),
+        TypeRef::Fn(params_and_ret) => {
+            let (ret, params) = params_and_ret.split_last().expect("fn types have a return type");
+            let params = params.iter().map(print_type_ref).sep_by(", ");
+            match ret {
+                TypeRef::Tuple(fields) if fields.is_empty() => format!("fn({})", params),
+                _ => format!("fn({}) -> {}", params, print_type_ref(ret)),
+            }
+        }
+        TypeRef::ImplTrait(bounds) => format!("impl {}", print_bounds(bounds)),
+        TypeRef::DynTrait(bounds) => format!("dyn {}", print_bounds(bounds)),
+        TypeRef::Error => "{error}".to_string(),
+    }
+}
+
+fn print_bounds(bounds: &[TypeBound]) -> String {
+    let bounds = bounds.iter().map(|bound| match bound {
+        TypeBound::Path(path) => print_path(path),
+        TypeBound::Error => "{error}".to_string(),
+    });
+    bounds.sep_by(" + ").to_string()
+}
@@ -33,6 +33,7 @@ pub struct FunctionData {
     /// True if the first param is `self`. This is relevant to decide whether this
     /// can be called as a method.
     pub has_self_param: bool,
+    pub is_unsafe: bool,
     pub visibility: RawVisibility,
 }
 
@@ -87,7 +88,9 @@ impl FunctionData {
         let visibility =
             RawVisibility::from_ast_with_default(db, vis_default, src.map(|s| s.visibility()));
 
-        let sig = FunctionData { name, params, ret_type, has_self_param, visibility, attrs };
+        let is_unsafe = src.value.unsafe_token().is_some();
+        let sig =
+            FunctionData { name, params, ret_type, has_self_param, is_unsafe, visibility, attrs };
         Arc::new(sig)
     }
 }
@@ -259,6 +262,8 @@ pub struct ConstData {
     pub name: Option<Name>,
     pub type_ref: TypeRef,
     pub visibility: RawVisibility,
+    /// `static mut`, always false for consts.
+    pub mutable: bool,
 }
 
 impl ConstData {
@@ -271,7 +276,8 @@ impl ConstData {
 
     pub(crate) fn static_data_query(db: &dyn DefDatabase, konst: StaticId) -> Arc<ConstData> {
         let node = konst.lookup(db).source(db);
-        Arc::new(ConstData::new(db, RawVisibility::private(), node))
+        let mutable = node.value.mut_token().is_some();
+        Arc::new(ConstData { mutable, ..ConstData::new(db, RawVisibility::private(), node) })
     }
 
     fn new<N: NameOwner + TypeAscriptionOwner + VisibilityOwner>(
@@ -283,7 +289,7 @@ impl ConstData {
         let type_ref = TypeRef::from_ast_opt(node.value.ascribed_type());
         let visibility =
             RawVisibility::from_ast_with_default(db, vis_default, node.map(|n| n.visibility()));
-        ConstData { name, type_ref, visibility }
+        ConstData { name, type_ref, visibility, mutable: false }
     }
 }
 
@@ -52,8 +52,9 @@ mod marks;
 use std::hash::Hash;
 
 use hir_expand::{
-    ast_id_map::FileAstId, eager::expand_eager_macro, hygiene::Hygiene, AstId, HirFileId, InFile,
-    MacroCallId, MacroCallKind, MacroDefId, MacroDefKind,
+    ast_id_map::FileAstId, builtin_derive::find_builtin_derive, eager::expand_eager_macro,
+    hygiene::Hygiene, AstId, HirFileId, InFile, MacroCallId, MacroCallKind, MacroDefId,
+    MacroDefKind,
 };
 use ra_arena::Idx;
 use ra_db::{impl_intern_key, salsa, CrateId};
@@ -436,6 +437,35 @@ impl AsMacroCall for InFile<&ast::MacroCall> {
     }
 }
 
+/// The macro calls of the `#[derive]` attributes of `item`. Builtin derives
+/// take precedence over `resolver`, like in name resolution.
+pub fn derive_macro_calls(
+    db: &dyn db::DefDatabase,
+    item: InFile<&ast::ModuleItem>,
+    resolver: impl Fn(path::ModPath) -> Option<MacroDefId>,
+) -> Vec<MacroCallId> {
+    let hygiene = Hygiene::new(db.upcast(), item.file_id);
+    let attrs = attr::Attrs::new(item.value, &hygiene);
+    let ast_id = AstId::new(item.file_id, db.ast_id_map(item.file_id).ast_id(item.value));
+    attrs
+        .by_key("derive")
+        .tt_values()
+        .flat_map(|subtree| subtree.token_trees.iter())
+        .filter_map(|tt| match tt {
+            tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) => Some(ident),
+            _ => None,
+        })
+        .filter_map(|ident| {
+            let path = path::ModPath::from_tt_ident(ident);
+            let ast_id = AstIdWithPath { ast_id, path };
+            ast_id.as_call_id(db, |path| {
+                let builtin = path.as_ident().and_then(find_builtin_derive);
+                builtin.or_else(|| resolver(path))
+            })
+        })
+        .collect()
+}
+
 /// Helper wrapper for `AstId` with `ModPath`
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct AstIdWithPath<T: ast::AstNode> {
@@ -6,7 +6,7 @@ use mbe::{ExpandResult, MacroRules};
 use ra_db::{salsa, SourceDatabase};
 use ra_parser::FragmentKind;
 use ra_prof::profile;
-use ra_syntax::{algo::diff, AstNode, Parse, SyntaxKind::*, SyntaxNode};
+use ra_syntax::{algo::diff, ast, AstNode, Parse, SyntaxKind::*, SyntaxNode};
 
 use crate::{
     ast_id_map::AstIdMap, BuiltinDeriveExpander, BuiltinFnLikeExpander, EagerCallLoc, EagerMacroId,
@@ -55,6 +55,20 @@ impl TokenExpander {
             TokenExpander::ProcMacro(..) => (id, mbe::Origin::Call),
         }
     }
+
+    /// For `macro_rules!`, the index of the rule which the call arguments `arg`
+    /// match, or come closest to matching.
+    pub fn matching_rule(&self, arg: &ast::TokenTree) -> Option<usize> {
+        match self {
+            TokenExpander::MacroRules(it) => {
+                let (tt, _) = mbe::ast_to_token_tree(arg)?;
+                it.matching_rule(&tt)
+            }
+            TokenExpander::Builtin(..)
+            | TokenExpander::BuiltinDerive(..)
+            | TokenExpander::ProcMacro(..) => None,
+        }
+    }
 }
 
 // FIXME: rename to ExpandDatabase
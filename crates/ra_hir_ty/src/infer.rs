@@ -136,6 +136,9 @@ pub struct InferenceResult {
     pub type_of_expr: ArenaMap<ExprId, Ty>,
     pub type_of_pat: ArenaMap<PatId, Ty>,
     pub(super) type_mismatches: ArenaMap<ExprId, TypeMismatch>,
+    /// For each expression whose type was changed by a coercion, or by the
+    /// autoderef and autoref of a method receiver, the type after the change.
+    adjusted_types: FxHashMap<ExprId, Ty>,
 }
 
 impl InferenceResult {
@@ -166,6 +169,9 @@ impl InferenceResult {
     pub fn type_mismatch_for_expr(&self, expr: ExprId) -> Option<&TypeMismatch> {
         self.type_mismatches.get(expr)
     }
+    pub fn adjusted_type_of_expr(&self, expr: ExprId) -> Option<&Ty> {
+        self.adjusted_types.get(&expr)
+    }
     pub fn add_diagnostics(
         &self,
         db: &dyn HirDatabase,
@@ -238,6 +244,12 @@ impl<'a> InferenceContext<'a> {
             let resolved = self.table.resolve_ty_completely(mem::replace(ty, Ty::Unknown));
             *ty = resolved;
         }
+        for ty in result.adjusted_types.values_mut() {
+            let resolved = self.table.resolve_ty_completely(mem::replace(ty, Ty::Unknown));
+            *ty = resolved;
+        }
+        let type_of_expr = &result.type_of_expr;
+        result.adjusted_types.retain(|&expr, ty| type_of_expr.get(expr) != Some(ty));
         result
     }
 
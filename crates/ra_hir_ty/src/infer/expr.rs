@@ -50,6 +50,7 @@ impl<'a> InferenceContext<'a> {
         } else if expected.coercion_target() == &Ty::Unknown {
             ty
         } else {
+            self.result.adjusted_types.insert(expr, expected.ty.clone());
             expected.ty.clone()
         };
 
@@ -78,18 +79,23 @@ impl<'a> InferenceContext<'a> {
                 // FIXME should be std::result::Result<{inner}, _>
                 Ty::Unknown
             }
-            Expr::Loop { body } => {
+            Expr::Async { body } => {
+                let _inner = self.infer_expr(*body, &Expectation::none());
+                // FIXME should be impl Future<Output = {inner}>
+                Ty::Unknown
+            }
+            Expr::Loop { body, .. } => {
                 self.infer_expr(*body, &Expectation::has_type(Ty::unit()));
                 // FIXME handle break with value
                 Ty::simple(TypeCtor::Never)
             }
-            Expr::While { condition, body } => {
+            Expr::While { condition, body, .. } => {
                 // while let is desugared to a match loop, so this is always simple while
                 self.infer_expr(*condition, &Expectation::has_type(Ty::simple(TypeCtor::Bool)));
                 self.infer_expr(*body, &Expectation::has_type(Ty::unit()));
                 Ty::unit()
             }
-            Expr::For { iterable, body, pat } => {
+            Expr::For { iterable, body, pat, .. } => {
                 let iterable_ty = self.infer_expr(*iterable, &Expectation::none());
 
                 let pat_ty =
@@ -185,8 +191,8 @@ impl<'a> InferenceContext<'a> {
                 let resolver = resolver_for_expr(self.db.upcast(), self.owner, tgt_expr);
                 self.infer_path(&resolver, p, tgt_expr.into()).unwrap_or(Ty::Unknown)
             }
-            Expr::Continue => Ty::simple(TypeCtor::Never),
-            Expr::Break { expr } => {
+            Expr::Continue { .. } => Ty::simple(TypeCtor::Never),
+            Expr::Break { expr, .. } => {
                 if let Some(expr) = expr {
                     // FIXME handle break with value
                     self.infer_expr(*expr, &Expectation::none());
@@ -616,6 +622,7 @@ impl<'a> InferenceContext<'a> {
             _ => derefed_receiver_ty,
         };
         self.unify(&expected_receiver_ty, &actual_receiver_ty);
+        self.result.adjusted_types.insert(receiver, actual_receiver_ty);
 
         self.check_call_arguments(args, &param_tys);
         self.normalize_associated_types_in(ret_ty)
@@ -0,0 +1,364 @@
+//! Computes the memory layout of types: their size, alignment and the offsets
+//! of their fields.
+//!
+//! We follow what rustc does for a 64-bit target. rustc doesn't promise a
+//! layout for `repr(Rust)` types, so this is a best effort. When the layout
+//! depends on something we can't see through, like a type parameter or a
+//! `repr` we don't understand, we still compute a layout, but mark it as
+//! approximate. When we can't know the size of a type at all, there's no
+//! layout rather than a wrong one.
+//!
+//! Types don't know the lengths of arrays, so those come from the types of
+//! fields as written, and only if they are integer literals.
+
+use std::cmp::{max, min, Reverse};
+
+use hir_def::{
+    adt::ReprOptions, builtin_type::BuiltinInt, type_ref::TypeRef, AdtId, EnumVariantId,
+    LocalFieldId, VariantId,
+};
+
+use crate::{
+    db::HirDatabase,
+    primitive::{FloatBitness, IntBitness, Uncertain},
+    utils::variant_data,
+    ApplicationTy, Substs, Ty, TypeCtor,
+};
+
+const POINTER_SIZE: u64 = 8;
+
+/// Guards against infinitely sized types, like `struct S { s: S }`.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub size: u64,
+    pub align: u64,
+    /// The offsets of the fields of a struct, union or tuple, in declaration
+    /// order.
+    pub field_offsets: Vec<u64>,
+    /// Whether the real layout might differ.
+    pub approximate: bool,
+    /// Invalid values which an enum can use to store its discriminant, like
+    /// the null of a reference in `Option<&T>`.
+    niche: Option<Niche>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Niche {
+    offset: u64,
+    available: u128,
+}
+
+impl Layout {
+    fn scalar(size: u64) -> Layout {
+        Layout { size, align: size, field_offsets: Vec::new(), approximate: false, niche: None }
+    }
+
+    fn zst() -> Layout {
+        Layout { size: 0, align: 1, field_offsets: Vec::new(), approximate: false, niche: None }
+    }
+
+    /// Stands in for a type we know nothing about.
+    fn unknown() -> Layout {
+        Layout { approximate: true, ..Layout::zst() }
+    }
+
+    fn with_niche(mut self, available: u128) -> Layout {
+        self.niche = Some(Niche { offset: 0, available });
+        self
+    }
+}
+
+pub fn layout_of(db: &dyn HirDatabase, ty: &Ty) -> Option<Layout> {
+    LayoutCx { db, depth: 0 }.layout_of(ty, None)
+}
+
+/// The layout of the type of a field. Unlike `layout_of`, this knows the
+/// lengths of arrays written in the field's type.
+pub fn layout_of_field(
+    db: &dyn HirDatabase,
+    variant: VariantId,
+    field: LocalFieldId,
+    substs: &Substs,
+) -> Option<Layout> {
+    let ty = db.field_types(variant)[field].clone().subst(substs);
+    let data = variant_data(db.upcast(), variant);
+    LayoutCx { db, depth: 0 }.layout_of(&ty, Some(&data.fields()[field].type_ref))
+}
+
+struct LayoutCx<'a> {
+    db: &'a dyn HirDatabase,
+    depth: usize,
+}
+
+/// Enum variants store the tag before their fields.
+#[derive(Clone, Copy)]
+enum StructKind {
+    Plain,
+    Prefixed { tag_size: u64 },
+}
+
+impl LayoutCx<'_> {
+    /// `type_ref` is how `ty` is written, if we know that.
+    fn layout_of(&mut self, ty: &Ty, type_ref: Option<&TypeRef>) -> Option<Layout> {
+        if self.depth >= MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let res = self.layout_of_inner(ty, type_ref);
+        self.depth -= 1;
+        res
+    }
+
+    fn layout_of_inner(&mut self, ty: &Ty, type_ref: Option<&TypeRef>) -> Option<Layout> {
+        let a_ty = match ty {
+            Ty::Apply(it) => it,
+            _ => return None,
+        };
+        let res = match a_ty.ctor {
+            TypeCtor::Bool => Layout::scalar(1).with_niche(254),
+            TypeCtor::Char => Layout::scalar(4).with_niche(u128::from(u32::MAX - 0x10_FFFF)),
+            TypeCtor::Int(Uncertain::Known(int)) => Layout::scalar(int_size(int.bitness)),
+            TypeCtor::Float(Uncertain::Known(float)) => Layout::scalar(match float.bitness {
+                FloatBitness::X32 => 4,
+                FloatBitness::X64 => 8,
+            }),
+            TypeCtor::Never | TypeCtor::FnDef(_) => Layout::zst(),
+            TypeCtor::FnPtr { .. } => Layout::scalar(POINTER_SIZE).with_niche(1),
+            TypeCtor::Ref(_) | TypeCtor::RawPtr(_) => {
+                let mut res = self.pointer_layout(a_ty.parameters.as_single());
+                if let TypeCtor::Ref(_) = a_ty.ctor {
+                    res = res.with_niche(1);
+                }
+                res
+            }
+            TypeCtor::Tuple { .. } => {
+                let field_refs = match type_ref {
+                    Some(TypeRef::Tuple(it)) if it.len() == a_ty.parameters.len() => Some(it),
+                    _ => None,
+                };
+                let fields = a_ty
+                    .parameters
+                    .iter()
+                    .enumerate()
+                    .map(|(i, it)| self.field_layout(it, field_refs.map(|refs| &refs[i])))
+                    .collect::<Option<_>>()?;
+                univariant(fields, &ReprOptions::default(), StructKind::Plain)
+            }
+            TypeCtor::Array => {
+                let (elem_ref, len) = match type_ref {
+                    Some(TypeRef::Array(elem_ref, Some(len))) => (elem_ref, *len),
+                    _ => return None,
+                };
+                let elem = self.field_layout(a_ty.parameters.as_single(), Some(elem_ref))?;
+                Layout {
+                    size: elem.size.checked_mul(len)?,
+                    field_offsets: Vec::new(),
+                    niche: if len > 0 { elem.niche } else { None },
+                    ..elem
+                }
+            }
+            TypeCtor::Adt(adt) => self.adt_layout(adt, &a_ty.parameters)?,
+            TypeCtor::Int(Uncertain::Unknown)
+            | TypeCtor::Float(Uncertain::Unknown)
+            | TypeCtor::Str
+            | TypeCtor::Slice
+            | TypeCtor::AssociatedType(_)
+            | TypeCtor::Closure { .. } => return None,
+        };
+        Some(res)
+    }
+
+    /// The layout of a field. If we can't see through its type, like a type
+    /// parameter, the field gets a placeholder layout. If it's a known type
+    /// without a layout, like an array of unknown length, the fields after it
+    /// can't be placed.
+    fn field_layout(&mut self, ty: &Ty, type_ref: Option<&TypeRef>) -> Option<Layout> {
+        match self.layout_of(ty, type_ref) {
+            Some(it) => Some(it),
+            None if matches!(ty, Ty::Apply(_)) => None,
+            None => Some(Layout::unknown()),
+        }
+    }
+
+    fn pointer_layout(&mut self, pointee: &Ty) -> Layout {
+        let thin = Layout::scalar(POINTER_SIZE);
+        match pointee {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Str, .. })
+            | Ty::Apply(ApplicationTy { ctor: TypeCtor::Slice, .. })
+            | Ty::Dyn(_) => Layout { size: 2 * POINTER_SIZE, ..thin },
+            Ty::Apply(_) => thin,
+            // A type parameter might be `?Sized`.
+            _ => Layout { approximate: true, ..thin },
+        }
+    }
+
+    fn adt_layout(&mut self, adt: AdtId, substs: &Substs) -> Option<Layout> {
+        let db = self.db;
+        match adt {
+            AdtId::StructId(id) => {
+                let data = db.struct_data(id);
+                let fields = self.variant_fields(id.into(), substs)?;
+                let mut res = univariant(fields, &data.repr, StructKind::Plain);
+                if let Some(start) = data.repr.valid_range_start {
+                    res.niche = Some(Niche { offset: 0, available: u128::from(start) });
+                }
+                Some(res)
+            }
+            AdtId::UnionId(id) => {
+                let data = db.union_data(id);
+                let fields = self.variant_fields(id.into(), substs)?;
+                Some(union(fields, &data.repr))
+            }
+            AdtId::EnumId(id) => {
+                let data = db.enum_data(id);
+                let variants = data
+                    .variants
+                    .iter()
+                    .map(|(local_id, _)| {
+                        let variant = EnumVariantId { parent: id, local_id };
+                        self.variant_fields(variant.into(), substs)
+                    })
+                    .collect::<Option<_>>()?;
+                Some(enum_layout(variants, &data.repr))
+            }
+        }
+    }
+
+    fn variant_fields(&mut self, variant: VariantId, substs: &Substs) -> Option<Vec<Layout>> {
+        let field_types = self.db.field_types(variant);
+        let data = variant_data(self.db.upcast(), variant);
+        field_types
+            .iter()
+            .map(|(id, ty)| {
+                let type_ref = &data.fields()[id].type_ref;
+                self.field_layout(&ty.clone().subst(substs), Some(type_ref))
+            })
+            .collect()
+    }
+}
+
+fn univariant(fields: Vec<Layout>, repr: &ReprOptions, kind: StructKind) -> Layout {
+    let pack = repr.packed.unwrap_or(u64::MAX);
+    let field_align = |field: &Layout| min(field.align, pack);
+
+    let mut order: Vec<usize> = (0..fields.len()).collect();
+    if !repr.c && repr.packed.is_none() {
+        // rustc sorts the fields to minimize padding. Enum variants start
+        // after the tag, so for them smaller fields go first.
+        match kind {
+            StructKind::Plain => order.sort_by_key(|&i| Reverse(fields[i].align)),
+            StructKind::Prefixed { .. } => order.sort_by_key(|&i| fields[i].align),
+        }
+    }
+
+    let mut offset = match kind {
+        StructKind::Plain => 0,
+        StructKind::Prefixed { tag_size } => tag_size,
+    };
+    let mut align = match kind {
+        StructKind::Plain => 1,
+        StructKind::Prefixed { tag_size } => tag_size,
+    };
+    let mut field_offsets = vec![0; fields.len()];
+    let mut niche: Option<Niche> = None;
+    let mut approximate = repr.unknown;
+    for i in order {
+        let field = &fields[i];
+        offset = align_to(offset, field_align(field));
+        field_offsets[i] = offset;
+        if let Some(field_niche) = field.niche {
+            if niche.map_or(true, |it| it.available < field_niche.available) {
+                niche = Some(Niche { offset: offset + field_niche.offset, ..field_niche });
+            }
+        }
+        offset += field.size;
+        align = max(align, field_align(field));
+        approximate |= field.approximate;
+    }
+    if let Some(repr_align) = repr.align {
+        align = max(align, repr_align);
+    }
+
+    Layout { size: align_to(offset, align), align, field_offsets, approximate, niche }
+}
+
+fn union(fields: Vec<Layout>, repr: &ReprOptions) -> Layout {
+    let pack = repr.packed.unwrap_or(u64::MAX);
+    let mut size = 0;
+    let mut align = repr.align.unwrap_or(1);
+    let mut approximate = repr.unknown;
+    for field in fields.iter() {
+        size = max(size, field.size);
+        align = max(align, min(field.align, pack));
+        approximate |= field.approximate;
+    }
+    let field_offsets = vec![0; fields.len()];
+    Layout { size: align_to(size, align), align, field_offsets, approximate, niche: None }
+}
+
+fn enum_layout(variants: Vec<Vec<Layout>>, repr: &ReprOptions) -> Layout {
+    if variants.is_empty() {
+        return Layout::zst();
+    }
+    let approximate = repr.unknown || variants.iter().flatten().any(|it| it.approximate);
+
+    if repr.int.is_none() && !repr.c {
+        if variants.len() == 1 {
+            let mut res = univariant(variants[0].clone(), repr, StructKind::Plain);
+            res.field_offsets.clear();
+            return res;
+        }
+
+        // If only one variant has data, the others can be stored as invalid
+        // values of one of its fields, without a tag.
+        let mut dataful = variants.iter().filter(|it| it.iter().any(|field| field.size > 0));
+        if let (Some(fields), None) = (dataful.next(), dataful.next()) {
+            let res = univariant(fields.clone(), repr, StructKind::Plain);
+            let needed = variants.len() as u128 - 1;
+            if let Some(niche) = res.niche.filter(|it| it.available >= needed) {
+                let niche = Some(Niche { available: niche.available - needed, ..niche });
+                return Layout { field_offsets: Vec::new(), approximate, niche, ..res };
+            }
+        }
+    }
+
+    let tag_size = match repr.int {
+        Some(BuiltinInt { bitness, .. }) => int_size(bitness),
+        // The tag of a `repr(C)` enum is a C `int`.
+        None if repr.c => 4,
+        None => match variants.len() {
+            n if n <= 1 << 8 => 1,
+            n if n <= 1 << 16 => 2,
+            _ => 4,
+        },
+    };
+    let mut size = tag_size;
+    let mut align = tag_size;
+    for fields in variants.iter() {
+        let variant = univariant(fields.clone(), repr, StructKind::Prefixed { tag_size });
+        size = max(size, variant.size);
+        align = max(align, variant.align);
+    }
+    let niche = 1u128
+        .checked_shl(8 * tag_size as u32)
+        .map(|values| Niche { offset: 0, available: values - variants.len() as u128 });
+
+    Layout { size: align_to(size, align), align, field_offsets: Vec::new(), approximate, niche }
+}
+
+fn int_size(bitness: IntBitness) -> u64 {
+    match bitness {
+        IntBitness::Xsize => POINTER_SIZE,
+        IntBitness::X8 => 1,
+        IntBitness::X16 => 2,
+        IntBitness::X32 => 4,
+        IntBitness::X64 => 8,
+        IntBitness::X128 => 16,
+    }
+}
+
+fn align_to(offset: u64, align: u64) -> u64 {
+    (offset + align - 1) / align * align
+}
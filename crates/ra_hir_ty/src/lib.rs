@@ -36,6 +36,7 @@ pub mod display;
 pub(crate) mod utils;
 pub mod db;
 pub mod diagnostics;
+pub mod layout;
 pub mod expr;
 
 #[cfg(test)]
@@ -69,6 +70,7 @@ pub use lower::{
     callable_item_sig, ImplTraitLoweringMode, TyDefId, TyLoweringContext, ValueTyDefId,
 };
 pub use traits::{InEnvironment, Obligation, ProjectionPredicate, TraitEnvironment};
+pub use utils::direct_super_traits;
 
 pub use chalk_ir::{BoundVar, DebruijnIndex};
 
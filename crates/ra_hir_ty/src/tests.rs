@@ -6,6 +6,7 @@ mod patterns;
 mod traits;
 mod method_resolution;
 mod macros;
+mod layout;
 
 use std::sync::Arc;
 
@@ -37,6 +38,10 @@ use crate::{
 // update the snapshots.
 
 fn type_at_pos(db: &TestDB, pos: FilePosition) -> String {
+    ty_at_pos(db, pos).display(db).to_string()
+}
+
+fn ty_at_pos(db: &TestDB, pos: FilePosition) -> Ty {
     let file = db.parse(pos.file_id).ok().unwrap();
     let expr = algo::find_node_at_offset::<ast::Expr>(file.syntax(), pos.offset).unwrap();
     let fn_def = expr.syntax().ancestors().find_map(ast::FnDef::cast).unwrap();
@@ -48,8 +53,7 @@ fn type_at_pos(db: &TestDB, pos: FilePosition) -> String {
     let (_body, source_map) = db.body_with_source_map(func.into());
     if let Some(expr_id) = source_map.node_expr(InFile::new(pos.file_id.into(), &expr)) {
         let infer = db.infer(func.into());
-        let ty = &infer[expr_id];
-        return ty.display(db).to_string();
+        return infer[expr_id].clone();
     }
     panic!("Can't find expression")
 }
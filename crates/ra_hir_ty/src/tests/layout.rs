@@ -0,0 +1,224 @@
+use ra_db::fixture::WithFixture;
+
+use super::ty_at_pos;
+use crate::{layout::layout_of, test_db::TestDB};
+
+fn layout_at(ra_fixture: &str) -> String {
+    let (db, pos) = TestDB::with_position(ra_fixture);
+    let ty = ty_at_pos(&db, pos);
+    let layout = match layout_of(&db, &ty) {
+        Some(it) => it,
+        None => return "unknown".to_string(),
+    };
+    let mut res = format!("size = {}, align = {}", layout.size, layout.align);
+    if !layout.field_offsets.is_empty() {
+        res.push_str(&format!(", offsets = {:?}", layout.field_offsets));
+    }
+    if layout.approximate {
+        res.push_str(" (approximate)");
+    }
+    res
+}
+
+#[test]
+fn layout_of_primitives() {
+    assert_eq!(layout_at("fn f(x: u8) { x<|>; }"), "size = 1, align = 1");
+    assert_eq!(layout_at("fn f(x: u128) { x<|>; }"), "size = 16, align = 16");
+    assert_eq!(layout_at("fn f(x: usize) { x<|>; }"), "size = 8, align = 8");
+    assert_eq!(layout_at("fn f(x: char) { x<|>; }"), "size = 4, align = 4");
+    assert_eq!(layout_at("fn f(x: &str) { x<|>; }"), "size = 16, align = 8");
+    assert_eq!(layout_at("fn f(x: &[u8]) { x<|>; }"), "size = 16, align = 8");
+    assert_eq!(layout_at("fn f(x: *const u8) { x<|>; }"), "size = 8, align = 8");
+    assert_eq!(layout_at("fn f(x: (u8, u64)) { x<|>; }"), "size = 16, align = 8, offsets = [8, 0]");
+    assert_eq!(layout_at("fn f(x: [u32; 4]) { x<|>; }"), "unknown");
+}
+
+#[test]
+fn layout_of_structs() {
+    assert_eq!(
+        layout_at(
+            r#"
+struct S { a: u8, b: u32, c: u16 }
+fn f(s: S) { s<|>; }
+"#,
+        ),
+        "size = 8, align = 4, offsets = [6, 0, 4]",
+    );
+    assert_eq!(
+        layout_at(
+            r#"
+#[repr(C)]
+struct S { a: u8, b: u32, c: u16 }
+fn f(s: S) { s<|>; }
+"#,
+        ),
+        "size = 12, align = 4, offsets = [0, 4, 8]",
+    );
+    assert_eq!(
+        layout_at(
+            r#"
+#[repr(packed)]
+struct S { a: u8, b: u32 }
+fn f(s: S) { s<|>; }
+"#,
+        ),
+        "size = 5, align = 1, offsets = [0, 1]",
+    );
+    assert_eq!(
+        layout_at(
+            r#"
+#[repr(align(16))]
+struct S(u8);
+fn f(s: S) { s<|>; }
+"#,
+        ),
+        "size = 16, align = 16, offsets = [0]",
+    );
+    assert_eq!(
+        layout_at(
+            r#"
+union U { a: u8, b: u32 }
+fn f(u: U) { u<|>; }
+"#,
+        ),
+        "size = 4, align = 4, offsets = [0, 0]",
+    );
+}
+
+#[test]
+fn layout_of_struct_with_arrays() {
+    assert_eq!(
+        layout_at(
+            r#"
+struct S { a: u32, b: [u8; 4] }
+fn f(s: S) { s<|>; }
+"#,
+        ),
+        "size = 8, align = 4, offsets = [0, 4]",
+    );
+    assert_eq!(
+        layout_at(
+            r#"
+struct S { a: [[u16; 2]; 0x3], b: (u8, [u32; 2_usize]) }
+fn f(s: S) { s<|>; }
+"#,
+        ),
+        "size = 24, align = 4, offsets = [12, 0]",
+    );
+    // The length of an array is only known if it's a literal.
+    assert_eq!(
+        layout_at(
+            r#"
+const N: usize = 4;
+struct S { a: u32, b: [u8; N] }
+fn f(s: S) { s<|>; }
+"#,
+        ),
+        "unknown",
+    );
+}
+
+#[test]
+fn layout_ignores_invalid_repr_args() {
+    assert_eq!(
+        layout_at(
+            r#"
+#[repr(packed(0))]
+struct S { a: u8, b: u32 }
+fn f(s: S) { s<|>; }
+"#,
+        ),
+        "size = 8, align = 4, offsets = [4, 0] (approximate)",
+    );
+    assert_eq!(
+        layout_at(
+            r#"
+#[repr(align(3))]
+struct S(u8);
+fn f(s: S) { s<|>; }
+"#,
+        ),
+        "size = 1, align = 1, offsets = [0] (approximate)",
+    );
+}
+
+#[test]
+fn layout_of_generic_struct_is_approximate() {
+    assert_eq!(
+        layout_at(
+            r#"
+struct S<T> { a: u32, t: T }
+fn f<T>(s: S<T>) { s<|>; }
+"#,
+        ),
+        "size = 4, align = 4, offsets = [0, 4] (approximate)",
+    );
+    assert_eq!(
+        layout_at(
+            r#"
+struct S<T> { a: u32, t: T }
+fn f(s: S<u64>) { s<|>; }
+"#,
+        ),
+        "size = 16, align = 8, offsets = [8, 0]",
+    );
+}
+
+#[test]
+fn layout_of_enums() {
+    assert_eq!(
+        layout_at(
+            r#"
+enum E { A, B, C }
+fn f(e: E) { e<|>; }
+"#,
+        ),
+        "size = 1, align = 1",
+    );
+    assert_eq!(
+        layout_at(
+            r#"
+#[repr(u32)]
+enum E { A, B, C }
+fn f(e: E) { e<|>; }
+"#,
+        ),
+        "size = 4, align = 4",
+    );
+    assert_eq!(
+        layout_at(
+            r#"
+enum E { A(u8), B(u32, u8) }
+fn f(e: E) { e<|>; }
+"#,
+        ),
+        "size = 8, align = 4",
+    );
+    assert_eq!(
+        layout_at(
+            r#"
+enum Void {}
+fn f(v: Void) { v<|>; }
+"#,
+        ),
+        "size = 0, align = 1",
+    );
+}
+
+#[test]
+fn layout_uses_niches() {
+    let option = r#"
+enum Option<T> { None, Some(T) }
+"#;
+    let check = |ty: &str, expected: &str| {
+        let fixture = format!("{}fn f(x: {}) {{ x<|>; }}", option, ty);
+        assert_eq!(layout_at(&fixture), expected, "{}", ty);
+    };
+    check("Option<&u32>", "size = 8, align = 8");
+    check("Option<fn()>", "size = 8, align = 8");
+    check("Option<bool>", "size = 1, align = 1");
+    check("Option<Option<bool>>", "size = 1, align = 1");
+    check("Option<(u32, &u8)>", "size = 16, align = 8");
+    check("Option<u32>", "size = 8, align = 4");
+    check("Option<*const u8>", "size = 16, align = 8");
+}
@@ -2,15 +2,28 @@
 
 use indexmap::IndexMap;
 
-use hir::Semantics;
-use ra_ide_db::RootDatabase;
+use hir::{AsAssocItem, AssocItem, AssocItemContainer, ImplDef, Semantics};
+use ra_db::SourceDatabaseExt;
+use ra_ide_db::{
+    defs::{classify_name, classify_name_ref, Definition},
+    RootDatabase,
+};
 use ra_syntax::{ast, match_ast, AstNode, TextRange};
 
 use crate::{
-    call_info::FnCallNode, display::ToNav, goto_definition, references, FilePosition,
-    NavigationTarget, RangeInfo,
+    call_info::FnCallNode, display::ToNav, goto_definition, references, references::Reference,
+    FilePosition, NavigationTarget, RangeInfo,
 };
 
+#[derive(Debug, Clone, Default)]
+pub struct CallHierarchyConfig {
+    /// Treats a call to a trait method as a call to each implementation of it
+    /// in the workspace, so that calls through `dyn Trait` and generics can be
+    /// followed. Incoming calls of an implementation then include the callers
+    /// of the trait method.
+    pub include_trait_impls: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct CallItem {
     pub target: NavigationTarget,
@@ -37,7 +50,11 @@ pub(crate) fn call_hierarchy(
     goto_definition::goto_definition(db, position)
 }
 
-pub(crate) fn incoming_calls(db: &RootDatabase, position: FilePosition) -> Option<Vec<CallItem>> {
+pub(crate) fn incoming_calls(
+    db: &RootDatabase,
+    position: FilePosition,
+    config: &CallHierarchyConfig,
+) -> Option<Vec<CallItem>> {
     let sema = Semantics::new(db);
     // 1. Find all refs
     // 2. Loop through refs and determine unique fndef. This will become our `from: CallHierarchyItem,` in the reply.
@@ -45,8 +62,30 @@ pub(crate) fn incoming_calls(db: &RootDatabase, position: FilePosition) -> Optio
     let refs = references::find_all_refs(db, position, None)?;
 
     let mut calls = CallLocations::default();
+    add_callers(&sema, refs.info.references(), &mut calls)?;
+
+    if config.include_trait_impls {
+        if let Some(function) = function_at(&sema, position) {
+            let related = match trait_method(db, function) {
+                Some(trait_method) => vec![trait_method],
+                None => trait_method_impls(db, function),
+            };
+            for function in related {
+                let refs = Definition::ModuleDef(function.into()).find_usages(db, None);
+                add_callers(&sema, &refs, &mut calls)?;
+            }
+        }
+    }
 
-    for reference in refs.info.references() {
+    Some(calls.into_items())
+}
+
+fn add_callers(
+    sema: &Semantics<RootDatabase>,
+    references: &[Reference],
+    calls: &mut CallLocations,
+) -> Option<()> {
+    for reference in references {
         let file_id = reference.file_range.file_id;
         let file = sema.parse(file_id);
         let file = file.syntax();
@@ -70,11 +109,14 @@ pub(crate) fn incoming_calls(db: &RootDatabase, position: FilePosition) -> Optio
             calls.add(&nav, relative_range);
         }
     }
-
-    Some(calls.into_items())
+    Some(())
 }
 
-pub(crate) fn outgoing_calls(db: &RootDatabase, position: FilePosition) -> Option<Vec<CallItem>> {
+pub(crate) fn outgoing_calls(
+    db: &RootDatabase,
+    position: FilePosition,
+    config: &CallHierarchyConfig,
+) -> Option<Vec<CallItem>> {
     let sema = Semantics::new(db);
     let file_id = position.file_id;
     let file = sema.parse(file_id);
@@ -91,38 +133,93 @@ pub(crate) fn outgoing_calls(db: &RootDatabase, position: FilePosition) -> Optio
         .filter_map(|call_node| {
             let name_ref = call_node.name_ref()?;
 
-            if let Some(func_target) = match &call_node {
+            let func_targets = match &call_node {
                 FnCallNode::CallExpr(expr) => {
                     //FIXME: Type::as_callable is broken
                     let callable_def = sema.type_of_expr(&expr.expr()?)?.as_callable()?;
                     match callable_def {
-                        hir::CallableDef::FunctionId(it) => {
-                            let fn_def: hir::Function = it.into();
-                            let nav = fn_def.to_nav(db);
-                            Some(nav)
-                        }
-                        _ => None,
+                        hir::CallableDef::FunctionId(it) => call_targets(db, it.into(), config),
+                        _ => return None,
                     }
                 }
                 FnCallNode::MethodCallExpr(expr) => {
                     let function = sema.resolve_method_call(&expr)?;
-                    Some(function.to_nav(db))
+                    call_targets(db, function, config)
                 }
                 FnCallNode::MacroCallExpr(macro_call) => {
                     let macro_def = sema.resolve_macro_call(&macro_call)?;
-                    Some(macro_def.to_nav(db))
+                    vec![macro_def.to_nav(db)]
                 }
-            } {
-                Some((func_target, name_ref.syntax().text_range()))
-            } else {
-                None
-            }
+            };
+            Some((func_targets, name_ref.syntax().text_range()))
         })
-        .for_each(|(nav, range)| calls.add(&nav, range));
+        .for_each(|(navs, range)| navs.iter().for_each(|nav| calls.add(nav, range)));
 
     Some(calls.into_items())
 }
 
+/// The called function, and if it's a trait method, its implementations.
+fn call_targets(
+    db: &RootDatabase,
+    function: hir::Function,
+    config: &CallHierarchyConfig,
+) -> Vec<NavigationTarget> {
+    let mut res = vec![function.to_nav(db)];
+    if config.include_trait_impls {
+        res.extend(trait_method_impls(db, function).into_iter().map(|it| it.to_nav(db)));
+    }
+    res
+}
+
+fn function_at(sema: &Semantics<RootDatabase>, position: FilePosition) -> Option<hir::Function> {
+    let syntax = sema.parse(position.file_id).syntax().clone();
+    let def = match sema.find_node_at_offset_with_descend::<ast::Name>(&syntax, position.offset) {
+        Some(name) => classify_name(sema, &name)?.definition(),
+        None => {
+            let name_ref =
+                sema.find_node_at_offset_with_descend::<ast::NameRef>(&syntax, position.offset)?;
+            classify_name_ref(sema, &name_ref)?.definition()
+        }
+    };
+    match def {
+        Definition::ModuleDef(hir::ModuleDef::Function(it)) => Some(it),
+        _ => None,
+    }
+}
+
+/// For a method of a trait impl, the method of the trait it implements.
+fn trait_method(db: &RootDatabase, function: hir::Function) -> Option<hir::Function> {
+    let trait_ = match function.as_assoc_item(db)?.container(db) {
+        AssocItemContainer::ImplDef(it) => it.target_trait_def(db)?,
+        AssocItemContainer::Trait(_) => return None,
+    };
+    let name = function.name(db);
+    trait_.items(db).into_iter().find_map(|item| match item {
+        AssocItem::Function(it) if it.name(db) == name => Some(it),
+        _ => None,
+    })
+}
+
+/// For a trait method, its implementations in all crates.
+fn trait_method_impls(db: &RootDatabase, function: hir::Function) -> Vec<hir::Function> {
+    let trait_ = match function.as_assoc_item(db).map(|it| it.container(db)) {
+        Some(AssocItemContainer::Trait(it)) => it,
+        _ => return Vec::new(),
+    };
+    let name = function.name(db);
+    hir::Crate::all(db)
+        .into_iter()
+        .filter(|krate| !db.source_root(db.file_source_root(krate.root_file(db))).is_library)
+        .flat_map(|krate| ImplDef::for_trait(db, krate, trait_))
+        .filter_map(|impl_def| {
+            impl_def.items(db).into_iter().find_map(|item| match item {
+                AssocItem::Function(it) if it.name(db) == name => Some(it),
+                _ => None,
+            })
+        })
+        .collect()
+}
+
 #[derive(Default)]
 struct CallLocations {
     funcs: IndexMap<NavigationTarget, Vec<TextRange>>,
@@ -142,13 +239,29 @@ impl CallLocations {
 mod tests {
     use ra_db::FilePosition;
 
-    use crate::mock_analysis::analysis_and_position;
+    use crate::{mock_analysis::analysis_and_position, CallHierarchyConfig};
 
     fn check_hierarchy(
         fixture: &str,
         expected: &str,
         expected_incoming: &[&str],
         expected_outgoing: &[&str],
+    ) {
+        check_hierarchy_with_config(
+            &CallHierarchyConfig::default(),
+            fixture,
+            expected,
+            expected_incoming,
+            expected_outgoing,
+        )
+    }
+
+    fn check_hierarchy_with_config(
+        config: &CallHierarchyConfig,
+        fixture: &str,
+        expected: &str,
+        expected_incoming: &[&str],
+        expected_outgoing: &[&str],
     ) {
         let (analysis, pos) = analysis_and_position(fixture);
 
@@ -158,14 +271,14 @@ mod tests {
         nav.assert_match(expected);
 
         let item_pos = FilePosition { file_id: nav.file_id(), offset: nav.range().start() };
-        let incoming_calls = analysis.incoming_calls(item_pos).unwrap().unwrap();
+        let incoming_calls = analysis.incoming_calls(item_pos, config).unwrap().unwrap();
         assert_eq!(incoming_calls.len(), expected_incoming.len());
 
         for call in 0..incoming_calls.len() {
             incoming_calls[call].assert_match(expected_incoming[call]);
         }
 
-        let outgoing_calls = analysis.outgoing_calls(item_pos).unwrap().unwrap();
+        let outgoing_calls = analysis.outgoing_calls(item_pos, config).unwrap().unwrap();
         assert_eq!(outgoing_calls.len(), expected_outgoing.len());
 
         for call in 0..outgoing_calls.len() {
@@ -304,6 +417,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_call_hierarchy_outgoing_through_trait_impls() {
+        check_hierarchy_with_config(
+            &CallHierarchyConfig { include_trait_impls: true },
+            r#"
+            //- /lib.rs
+            trait Handler {
+                fn handle(&self);
+            }
+            struct A;
+            impl Handler for A {
+                fn handle(&self) {}
+            }
+            fn disp<|>atch(h: &dyn Handler) {
+                h.handle();
+            }
+            "#,
+            "dispatch FN_DEF FileId(1) 97..145 100..108",
+            &[],
+            &[
+                "handle FN_DEF FileId(1) 20..37 23..29 : [134..140]",
+                "handle FN_DEF FileId(1) 75..94 78..84 : [134..140]",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_incoming_through_trait_impls() {
+        let fixture = r#"
+            //- /lib.rs
+            trait Handler {
+                fn handle(&self);
+            }
+            struct A;
+            impl Handler for A {
+                fn han<|>dle(&self) {}
+            }
+            fn dispatch(h: &dyn Handler) {
+                h.handle();
+            }
+            "#;
+        check_hierarchy(fixture, "handle FN_DEF FileId(1) 75..94 78..84", &[], &[]);
+        check_hierarchy_with_config(
+            &CallHierarchyConfig { include_trait_impls: true },
+            fixture,
+            "handle FN_DEF FileId(1) 75..94 78..84",
+            &["dispatch FN_DEF FileId(1) 97..145 100..108 : [134..140]"],
+            &[],
+        );
+    }
+
     #[test]
     fn test_call_hierarchy_incoming_outgoing() {
         check_hierarchy(
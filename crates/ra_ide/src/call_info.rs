@@ -3,7 +3,7 @@ use hir::Semantics;
 use ra_ide_db::RootDatabase;
 use ra_syntax::{
     ast::{self, ArgListOwner},
-    match_ast, AstNode, SyntaxNode, SyntaxToken,
+    match_ast, AstNode, SyntaxNode, SyntaxToken, T,
 };
 use test_utils::tested_by;
 
@@ -15,8 +15,10 @@ pub(crate) fn call_info(db: &RootDatabase, position: FilePosition) -> Option<Cal
     let file = sema.parse(position.file_id);
     let file = file.syntax();
     let token = file.token_at_offset(position.offset).next()?;
-    let token = sema.descend_into_macros(token);
-    call_info_for_token(&sema, token)
+    let expanded = sema.descend_into_macros(token.clone());
+    // If the token is in the arguments of a macro call, but not in any call
+    // inside of its expansion, show the arms of the macro instead.
+    call_info_for_token(&sema, expanded).or_else(|| call_info_for_token(&sema, token))
 }
 
 #[derive(Debug)]
@@ -37,6 +39,26 @@ impl ActiveParameter {
 }
 
 fn call_info_for_token(sema: &Semantics<RootDatabase>, token: SyntaxToken) -> Option<CallInfo> {
+    // `{` triggers signature help for record literals only, a block inside of
+    // the arguments shouldn't show the call.
+    if token.kind() == T!['{'] && !ast::RecordFieldList::can_cast(token.parent().kind()) {
+        return None;
+    }
+
+    // Generic argument lists and record literals get signature help too, if
+    // they are closer to the cursor than any call.
+    for node in token.parent().ancestors() {
+        if let Some(arg_list) = ast::TypeArgList::cast(node.clone()) {
+            return call_info_for_generic_args(sema, arg_list, &token);
+        }
+        if let Some(field_list) = ast::RecordFieldList::cast(node.clone()) {
+            return call_info_for_record_literal(sema, field_list, &token);
+        }
+        if FnCallNode::with_node_exact(&node).is_some() {
+            break;
+        }
+    }
+
     // Find the calling expression and it's NameRef
     let calling_node = FnCallNode::with_node(&token.parent())?;
 
@@ -63,7 +85,12 @@ fn call_info_for_token(sema: &Semantics<RootDatabase>, token: SyntaxToken) -> Op
         }
         FnCallNode::MacroCallExpr(macro_call) => {
             let macro_def = sema.resolve_macro_call(&macro_call)?;
-            (CallInfo::with_macro(sema.db, macro_def)?, false)
+            let mut call_info = CallInfo::with_macro(sema.db, macro_def)?;
+            // The parameters of a macro are its arms, the active one is the
+            // arm the arguments match.
+            call_info.active_parameter =
+                macro_call.token_tree().and_then(|arg| macro_def.matching_rule(sema.db, &arg));
+            return Some(call_info);
         }
     };
 
@@ -111,6 +138,82 @@ fn call_info_for_token(sema: &Semantics<RootDatabase>, token: SyntaxToken) -> Op
     Some(call_info)
 }
 
+fn call_info_for_generic_args(
+    sema: &Semantics<RootDatabase>,
+    arg_list: ast::TypeArgList,
+    token: &SyntaxToken,
+) -> Option<CallInfo> {
+    let parent = arg_list.syntax().parent()?;
+    let def: hir::GenericDef = match ast::MethodCallExpr::cast(parent.clone()) {
+        Some(method_call) => sema.resolve_method_call(&method_call)?.into(),
+        None => {
+            let path = ast::PathSegment::cast(parent)?.parent_path();
+            match sema.resolve_path(&path)? {
+                hir::PathResolution::Def(hir::ModuleDef::Function(it)) => it.into(),
+                hir::PathResolution::Def(hir::ModuleDef::Adt(it)) => it.into(),
+                hir::PathResolution::Def(hir::ModuleDef::Trait(it)) => it.into(),
+                hir::PathResolution::Def(hir::ModuleDef::TypeAlias(it)) => it.into(),
+                _ => return None,
+            }
+        }
+    };
+    let mut signature = FunctionSignature::from_generic_def(sema.db, def)?;
+
+    // Lifetime arguments can be left out, then the first argument is a type.
+    if arg_list.lifetime_args().next().is_none() {
+        signature.parameters.retain(|param| !param.starts_with('\''));
+    }
+    let active_parameter = arg_list
+        .generic_args()
+        .take_while(|arg| arg.syntax().text_range().end() < token.text_range().start())
+        .count();
+
+    Some(CallInfo { signature, active_parameter: Some(active_parameter) })
+}
+
+/// Shows the fields which are still missing from the record literal, plus the
+/// one at the cursor, which is the active parameter.
+fn call_info_for_record_literal(
+    sema: &Semantics<RootDatabase>,
+    field_list: ast::RecordFieldList,
+    token: &SyntaxToken,
+) -> Option<CallInfo> {
+    let literal = field_list.syntax().parent().and_then(ast::RecordLit::cast)?;
+    let path = literal.path()?;
+
+    let current_field = token
+        .parent()
+        .ancestors()
+        .take_while(|it| it != field_list.syntax())
+        .find_map(ast::RecordField::cast)
+        .and_then(|field| sema.resolve_record_field(&field))
+        .map(|(field, _local)| field);
+    let missing_fields = sema.record_literal_missing_fields(&literal);
+    let variant = current_field.or_else(|| missing_fields.first().map(|(field, _)| *field))?;
+    let variant = variant.parent_def(sema.db);
+
+    let fields: Vec<(hir::Field, hir::Type)> = variant
+        .fields(sema.db)
+        .into_iter()
+        .filter_map(|field| {
+            if Some(field) == current_field {
+                return Some((field, field.signature_ty(sema.db)));
+            }
+            missing_fields.iter().find(|(it, _)| *it == field).cloned()
+        })
+        .collect();
+    let active_parameter =
+        current_field.and_then(|current| fields.iter().position(|(it, _)| *it == current));
+    let signature = FunctionSignature::from_record_literal(
+        sema.db,
+        variant,
+        path.syntax().text().to_string(),
+        fields,
+    );
+
+    Some(CallInfo { signature, active_parameter })
+}
+
 #[derive(Debug)]
 pub(crate) enum FnCallNode {
     CallExpr(ast::CallExpr),
@@ -664,6 +767,103 @@ fn f() {
         assert_eq!(info.doc().map(|it| it.into()), Some("empty macro".to_string()));
     }
 
+    #[test]
+    fn fn_signature_for_macro_arms() {
+        let info = call_info(
+            r#"
+macro_rules! foo {
+    ($e:expr) => { $e };
+    ($a:expr, $b:expr) => { $a + $b };
+}
+
+fn f() {
+    foo!(1, <|>);
+}
+        "#,
+        );
+
+        assert_eq!(info.parameters(), ["foo!($e:expr)", "foo!($a:expr, $b:expr)"]);
+        assert_eq!(info.label(), "foo!($e:expr)\nfoo!($a:expr, $b:expr)");
+        assert_eq!(info.active_parameter, Some(1));
+    }
+
+    #[test]
+    fn generic_args_of_fn() {
+        let info = call_info(
+            r#"
+/// Does foo
+fn foo<T: Copy, U>(x: T, y: U) {}
+fn bar() { foo::<u32, <|>>(0, 1); }
+"#,
+        );
+
+        assert_eq!(info.label(), "fn foo<T: Copy, U>");
+        assert_eq!(info.parameters(), ["T: Copy", "U"]);
+        assert_eq!(info.doc().map(|it| it.into()), Some("Does foo".to_string()));
+        assert_eq!(info.active_parameter, Some(1));
+    }
+
+    #[test]
+    fn generic_args_skip_omitted_lifetimes() {
+        let info = call_info(
+            r#"
+struct S<'a, T>(&'a T);
+fn bar() { let s: S<<|>>; }
+"#,
+        );
+
+        assert_eq!(info.parameters(), ["T"]);
+        assert_eq!(info.active_parameter, Some(0));
+    }
+
+    #[test]
+    fn record_literal_missing_fields() {
+        let info = call_info(
+            r#"
+struct Foo { a: u32, b: i32, c: bool }
+fn bar() { let foo = Foo { a: 0, <|> }; }
+"#,
+        );
+
+        assert_eq!(info.label(), "Foo { b: i32, c: bool }");
+        assert_eq!(info.active_parameter, None);
+    }
+
+    #[test]
+    fn record_literal_open_brace() {
+        let info = call_info(
+            r#"
+struct Foo { a: u32, b: i32 }
+fn bar() { let foo = Foo {<|>}; }
+"#,
+        );
+
+        assert_eq!(info.label(), "Foo { a: u32, b: i32 }");
+    }
+
+    #[test]
+    fn block_open_brace_has_no_call_info() {
+        no_call_info(
+            r#"
+fn foo(f: u32) -> u32 { f }
+fn bar() { foo({<|>}); }
+"#,
+        );
+    }
+
+    #[test]
+    fn record_literal_current_field() {
+        let info = call_info(
+            r#"
+struct Foo { a: u32, b: i32, c: bool }
+fn bar() { let foo = Foo { a: 0, c: tr<|>ue }; }
+"#,
+        );
+
+        assert_eq!(info.parameters(), ["b: i32", "c: bool"]);
+        assert_eq!(info.active_parameter, Some(1));
+    }
+
     #[test]
     fn fn_signature_for_call_in_macro() {
         let info = call_info(
@@ -20,6 +20,7 @@ mod complete_trait_impl;
 #[cfg(test)]
 mod test_utils;
 
+use ra_db::FileId;
 use ra_ide_db::RootDatabase;
 
 use crate::{
@@ -81,5 +82,97 @@ pub(crate) fn completions(
     complete_trait_impl::complete_trait_impl(&mut acc, &ctx);
     complete_attribute::complete_attribute(&mut acc, &ctx);
 
+    if !config.resolve_lazily {
+        acc.resolve(db);
+    }
     Some(acc)
 }
+
+/// Computes the documentation and the detail of a completion item, which
+/// `completions` left out because of `CompletionConfig::resolve_lazily`. The
+/// item refers to its definition, so the completion list isn't recomputed.
+pub(crate) fn resolve_completion(db: &RootDatabase, mut item: CompletionItem) -> CompletionItem {
+    item.resolve(db);
+    item
+}
+
+/// The file whose changes invalidate the unresolved completion item.
+pub(crate) fn completion_resolve_file(db: &RootDatabase, item: &CompletionItem) -> Option<FileId> {
+    item.resolve_file(db)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        completion::CompletionConfig,
+        mock_analysis::{single_file_with_position, MockAnalysis},
+    };
+
+    #[test]
+    fn documentation_and_detail_are_resolved_lazily() {
+        let (analysis, position) = single_file_with_position(
+            r"
+            /// Does the foo.
+            fn foo(x: u32) -> u32 { x }
+            /// Does the bar.
+            const BAR: u32 = 92;
+            /// A baz.
+            struct Baz;
+            fn main() { <|> }
+            ",
+        );
+        let config = CompletionConfig { resolve_lazily: true, ..Default::default() };
+        let items = analysis.completions(position, &config).unwrap().unwrap();
+        let item = |label: &str| items.iter().find(|it| it.lookup() == label).unwrap().clone();
+
+        let foo = item("foo");
+        assert!(foo.documentation().is_none());
+        assert!(foo.detail().is_none());
+        assert!(foo.needs_resolve());
+        let foo = analysis.resolve_completion(foo).unwrap();
+        assert_eq!(foo.documentation().unwrap().as_str(), "Does the foo.");
+        assert_eq!(foo.detail(), Some("fn foo(x: u32) -> u32"));
+        assert!(!foo.needs_resolve());
+
+        let bar = analysis.resolve_completion(item("BAR")).unwrap();
+        assert_eq!(bar.documentation().unwrap().as_str(), "Does the bar.");
+        assert_eq!(bar.detail(), Some("const BAR: u32"));
+
+        let baz = analysis.resolve_completion(item("Baz")).unwrap();
+        assert_eq!(baz.documentation().unwrap().as_str(), "A baz.");
+        assert_eq!(baz.detail(), None);
+
+        let items = analysis.completions(position, &CompletionConfig::default()).unwrap().unwrap();
+        let foo = items.iter().find(|it| it.lookup() == "foo").unwrap();
+        assert_eq!(foo.documentation().unwrap().as_str(), "Does the foo.");
+        assert_eq!(foo.detail(), Some("fn foo(x: u32) -> u32"));
+        assert!(!foo.needs_resolve());
+    }
+
+    #[test]
+    fn resolve_file_is_the_defining_file() {
+        let (mock, position) = MockAnalysis::with_files_and_position(
+            r"
+            //- /lib.rs
+            mod foo;
+            mod bar { pub fn bar() {} }
+            use foo::foo;
+            use bar::bar;
+            fn main() { <|> }
+            //- /foo.rs
+            pub fn foo() {}
+            ",
+        );
+        let analysis = mock.analysis();
+        let config = CompletionConfig { resolve_lazily: true, ..Default::default() };
+        let items = analysis.completions(position, &config).unwrap().unwrap();
+        let file_of = |label: &str| {
+            let item = items.iter().find(|it| it.lookup() == label).unwrap();
+            analysis.completion_resolve_file(item).unwrap()
+        };
+
+        assert_eq!(file_of("foo"), Some(mock.id_of("/foo.rs")));
+        assert_eq!(file_of("bar"), Some(mock.id_of("/lib.rs")));
+        assert_eq!(file_of("main"), Some(mock.id_of("/lib.rs")));
+    }
+}
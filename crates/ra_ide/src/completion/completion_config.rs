@@ -10,6 +10,10 @@ pub struct CompletionConfig {
     pub add_call_parenthesis: bool,
     pub add_call_argument_snippets: bool,
     pub snippet_cap: Option<SnippetCap>,
+    /// Don't compute documentation and signatures up front: they are expensive
+    /// for large completion lists and the client shows them for a single item
+    /// anyway. See `Analysis::resolve_completion`.
+    pub resolve_lazily: bool,
 }
 
 impl CompletionConfig {
@@ -30,6 +34,7 @@ impl Default for CompletionConfig {
             add_call_parenthesis: true,
             add_call_argument_snippets: true,
             snippet_cap: Some(SnippetCap { _private: () }),
+            resolve_lazily: false,
         }
     }
 }
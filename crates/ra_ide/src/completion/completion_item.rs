@@ -2,14 +2,17 @@
 
 use std::fmt;
 
-use super::completion_config::SnippetCap;
-use hir::Documentation;
+use super::{completion_config::SnippetCap, presentation};
+use hir::{AttrDef, Docs, Documentation};
+use ra_db::FileId;
+use ra_ide_db::RootDatabase;
 use ra_syntax::TextRange;
 use ra_text_edit::TextEdit;
 
 /// `CompletionItem` describes a single completion variant in the editor pop-up.
 /// It is basically a POD with various properties. To construct a
 /// `CompletionItem`, use `new` method and the `Builder` struct.
+#[derive(Clone)]
 pub struct CompletionItem {
     /// Used only internally in tests, to check only specific kind of
     /// completion (postfix, keyword, reference, etc).
@@ -45,6 +48,9 @@ pub struct CompletionItem {
     /// Additional info to show in the UI pop up.
     detail: Option<String>,
     documentation: Option<Documentation>,
+    /// The definition `documentation` and `detail` are computed from, if they
+    /// weren't computed eagerly. See `CompletionConfig::resolve_lazily`.
+    resolve_source: Option<AttrDef>,
 
     /// Whether this item is marked as deprecated
     deprecated: bool,
@@ -159,6 +165,7 @@ impl CompletionItem {
             insert_text_format: InsertTextFormat::PlainText,
             detail: None,
             documentation: None,
+            resolve_source: None,
             lookup: None,
             kind: None,
             text_edit: None,
@@ -191,6 +198,39 @@ impl CompletionItem {
     pub fn documentation(&self) -> Option<Documentation> {
         self.documentation.clone()
     }
+    /// Whether the doc-comment or the detail can be computed later with
+    /// `Analysis::resolve_completion`.
+    pub fn needs_resolve(&self) -> bool {
+        self.resolve_source.is_some()
+    }
+    pub(crate) fn resolve(&mut self, db: &RootDatabase) {
+        if let Some(def) = self.resolve_source.take() {
+            if self.documentation.is_none() {
+                self.documentation = def.docs(db);
+            }
+            if self.detail.is_none() {
+                self.detail = presentation::detail(db, def);
+            }
+        }
+    }
+    /// The file which defines the item to resolve. Changing it might make the
+    /// definition disappear, other changes leave the item resolvable.
+    pub(crate) fn resolve_file(&self, db: &RootDatabase) -> Option<FileId> {
+        let module = match self.resolve_source? {
+            // The `mod` item is in the parent module.
+            AttrDef::Module(it) => it.parent(db).unwrap_or(it),
+            AttrDef::Field(it) => it.parent_def(db).module(db),
+            AttrDef::Adt(it) => it.module(db),
+            AttrDef::Function(it) => it.module(db),
+            AttrDef::EnumVariant(it) => it.module(db),
+            AttrDef::Static(it) => it.module(db),
+            AttrDef::Const(it) => it.module(db),
+            AttrDef::Trait(it) => it.module(db),
+            AttrDef::TypeAlias(it) => it.module(db),
+            AttrDef::MacroDef(it) => it.module(db)?,
+        };
+        Some(module.definition_file(db))
+    }
     /// What string is used for filtering.
     pub fn lookup(&self) -> &str {
         self.lookup.as_deref().unwrap_or(&self.label)
@@ -223,6 +263,7 @@ pub(crate) struct Builder {
     insert_text_format: InsertTextFormat,
     detail: Option<String>,
     documentation: Option<Documentation>,
+    resolve_source: Option<AttrDef>,
     lookup: Option<String>,
     kind: Option<CompletionItemKind>,
     text_edit: Option<TextEdit>,
@@ -253,6 +294,7 @@ impl Builder {
             text_edit,
             detail: self.detail,
             documentation: self.documentation,
+            resolve_source: self.resolve_source,
             lookup: self.lookup,
             kind: self.kind,
             completion_kind: self.completion_kind,
@@ -309,6 +351,14 @@ impl Builder {
         self.documentation = docs.map(Into::into);
         self
     }
+    /// Records where the documentation, and the detail if it isn't set, come
+    /// from, without computing them. Depending on the config, they are filled
+    /// in either when completion finishes, or when the client asks to resolve
+    /// this item.
+    pub(crate) fn resolve_source(mut self, def: impl Into<AttrDef>) -> Builder {
+        self.resolve_source = Some(def.into());
+        self
+    }
     pub(crate) fn set_deprecated(mut self, deprecated: bool) -> Builder {
         self.deprecated = Some(deprecated);
         self
@@ -346,6 +396,9 @@ impl Completions {
     {
         items.into_iter().for_each(|item| self.add(item.into()))
     }
+    pub(crate) fn resolve(&mut self, db: &RootDatabase) {
+        self.buf.iter_mut().for_each(|item| item.resolve(db))
+    }
 }
 
 impl Into<Vec<CompletionItem>> for Completions {
@@ -10,7 +10,7 @@ use crate::{
         completion_item::Builder, CompletionContext, CompletionItem, CompletionItemKind,
         CompletionKind, Completions,
     },
-    display::{const_label, macro_label, type_label, FunctionSignature},
+    display::{const_label, function_label, macro_label, param_names, type_label},
     CompletionScore, RootDatabase,
 };
 
@@ -23,7 +23,7 @@ impl Completions {
             CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.to_string())
                 .kind(CompletionItemKind::Field)
                 .detail(ty.clone())
-                .set_documentation(field.docs(ctx.db))
+                .resolve_source(field)
                 .set_deprecated(is_deprecated);
 
         if let Some(score) = compute_score(ctx, &ty, &name.to_string()) {
@@ -87,14 +87,14 @@ impl Completions {
             }
         };
 
-        let docs = match resolution {
-            ScopeDef::ModuleDef(Module(it)) => it.docs(ctx.db),
-            ScopeDef::ModuleDef(Adt(it)) => it.docs(ctx.db),
-            ScopeDef::ModuleDef(EnumVariant(it)) => it.docs(ctx.db),
-            ScopeDef::ModuleDef(Const(it)) => it.docs(ctx.db),
-            ScopeDef::ModuleDef(Static(it)) => it.docs(ctx.db),
-            ScopeDef::ModuleDef(Trait(it)) => it.docs(ctx.db),
-            ScopeDef::ModuleDef(TypeAlias(it)) => it.docs(ctx.db),
+        let docs_source: Option<hir::AttrDef> = match resolution {
+            ScopeDef::ModuleDef(Module(it)) => Some((*it).into()),
+            ScopeDef::ModuleDef(Adt(it)) => Some((*it).into()),
+            ScopeDef::ModuleDef(EnumVariant(it)) => Some((*it).into()),
+            ScopeDef::ModuleDef(Const(it)) => Some((*it).into()),
+            ScopeDef::ModuleDef(Static(it)) => Some((*it).into()),
+            ScopeDef::ModuleDef(Trait(it)) => Some((*it).into()),
+            ScopeDef::ModuleDef(TypeAlias(it)) => Some((*it).into()),
             _ => None,
         };
 
@@ -125,7 +125,10 @@ impl Completions {
             }
         }
 
-        completion_item.kind(kind).set_documentation(docs).add_to(self)
+        if let Some(def) = docs_source {
+            completion_item = completion_item.resolve_source(def);
+        }
+        completion_item.kind(kind).add_to(self)
     }
 
     pub(crate) fn add_macro(
@@ -148,6 +151,7 @@ impl Completions {
         let ast_node = macro_.source(ctx.db).value;
         let detail = macro_label(&ast_node);
 
+        // Docs are needed right away to guess the braces.
         let docs = macro_.docs(ctx.db);
 
         let mut builder = CompletionItem::new(
@@ -189,7 +193,6 @@ impl Completions {
 
         let name = local_name.unwrap_or_else(|| func.name(ctx.db).to_string());
         let ast_node = func.source(ctx.db).value;
-        let function_signature = FunctionSignature::from(&ast_node);
 
         let mut builder =
             CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.clone())
@@ -198,16 +201,14 @@ impl Completions {
                 } else {
                     CompletionItemKind::Function
                 })
-                .set_documentation(func.docs(ctx.db))
-                .set_deprecated(is_deprecated(func, ctx.db))
-                .detail(function_signature.to_string());
+                .resolve_source(func)
+                .set_deprecated(is_deprecated(func, ctx.db));
+        if !ctx.config.resolve_lazily {
+            builder = builder.detail(function_label(&ast_node));
+        }
 
-        let params = function_signature
-            .parameter_names
-            .iter()
-            .skip(if function_signature.has_self_param { 1 } else { 0 })
-            .cloned()
-            .collect();
+        let params =
+            param_names(&ast_node).into_iter().skip(if has_self_param { 1 } else { 0 }).collect();
 
         builder = builder.add_call_parens(ctx, name, Params::Named(params));
 
@@ -220,14 +221,18 @@ impl Completions {
             Some(name) => name,
             _ => return,
         };
-        let detail = const_label(&ast_node);
-
-        CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.text().to_string())
-            .kind(CompletionItemKind::Const)
-            .set_documentation(constant.docs(ctx.db))
-            .set_deprecated(is_deprecated(constant, ctx.db))
-            .detail(detail)
-            .add_to(self);
+        let mut builder = CompletionItem::new(
+            CompletionKind::Reference,
+            ctx.source_range(),
+            name.text().to_string(),
+        )
+        .kind(CompletionItemKind::Const)
+        .resolve_source(constant)
+        .set_deprecated(is_deprecated(constant, ctx.db));
+        if !ctx.config.resolve_lazily {
+            builder = builder.detail(const_label(&ast_node));
+        }
+        builder.add_to(self);
     }
 
     pub(crate) fn add_type_alias(&mut self, ctx: &CompletionContext, type_alias: hir::TypeAlias) {
@@ -236,14 +241,18 @@ impl Completions {
             Some(name) => name,
             _ => return,
         };
-        let detail = type_label(&type_def);
-
-        CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.text().to_string())
-            .kind(CompletionItemKind::TypeAlias)
-            .set_documentation(type_alias.docs(ctx.db))
-            .set_deprecated(is_deprecated(type_alias, ctx.db))
-            .detail(detail)
-            .add_to(self);
+        let mut builder = CompletionItem::new(
+            CompletionKind::Reference,
+            ctx.source_range(),
+            name.text().to_string(),
+        )
+        .kind(CompletionItemKind::TypeAlias)
+        .resolve_source(type_alias)
+        .set_deprecated(is_deprecated(type_alias, ctx.db));
+        if !ctx.config.resolve_lazily {
+            builder = builder.detail(type_label(&type_def));
+        }
+        builder.add_to(self);
     }
 
     pub(crate) fn add_enum_variant(
@@ -274,7 +283,7 @@ impl Completions {
         let mut res =
             CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.clone())
                 .kind(CompletionItemKind::EnumVariant)
-                .set_documentation(variant.docs(ctx.db))
+                .resolve_source(variant)
                 .set_deprecated(is_deprecated)
                 .detail(detail);
 
@@ -287,6 +296,18 @@ impl Completions {
     }
 }
 
+/// Computes the detail of an item which was left out with
+/// `CompletionConfig::resolve_lazily`.
+pub(crate) fn detail(db: &RootDatabase, def: hir::AttrDef) -> Option<String> {
+    let res = match def {
+        hir::AttrDef::Function(it) => function_label(&it.source(db).value),
+        hir::AttrDef::Const(it) => const_label(&it.source(db).value),
+        hir::AttrDef::TypeAlias(it) => type_label(&it.source(db).value),
+        _ => return None,
+    };
+    Some(res)
+}
+
 pub(crate) fn compute_score(
     ctx: &CompletionContext,
     // FIXME: this definitely should be a `Type`
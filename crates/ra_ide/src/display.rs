@@ -64,6 +64,35 @@ pub(crate) fn where_predicates<N: TypeParamsOwner>(node: &N) -> Vec<String> {
     res
 }
 
+/// The names of the parameters, including `self`, which are empty for patterns
+/// without a name.
+pub(crate) fn param_names(node: &ast::FnDef) -> Vec<String> {
+    let mut res = vec![];
+    if let Some(param_list) = node.param_list() {
+        if let Some(self_param) = param_list.self_param() {
+            res.push(self_param.syntax().text().to_string())
+        }
+
+        res.extend(
+            param_list
+                .params()
+                .map(|param| {
+                    Some(
+                        param
+                            .pat()?
+                            .syntax()
+                            .descendants()
+                            .find_map(ast::Name::cast)?
+                            .text()
+                            .to_string(),
+                    )
+                })
+                .map(|param| param.unwrap_or_default()),
+        );
+    }
+    res
+}
+
 pub(crate) fn macro_label(node: &ast::MacroCall) -> String {
     let name = node.name().map(|name| name.syntax().text().to_string()).unwrap_or_default();
     let vis = if node.has_atom_attr("macro_export") { "#[macro_export]\n" } else { "" };
@@ -7,10 +7,14 @@ use std::{
 
 use hir::{Docs, Documentation, HasSource, HirDisplay};
 use ra_ide_db::RootDatabase;
-use ra_syntax::ast::{self, AstNode, NameOwner, VisibilityOwner};
+use ra_syntax::{
+    ast::{self, AstNode, NameOwner, TypeParamsOwner, VisibilityOwner},
+    SyntaxKind::{COMMENT, TOKEN_TREE, WHITESPACE},
+    T,
+};
 use stdx::SepBy;
 
-use crate::display::{generic_parameters, where_predicates};
+use crate::display::{generic_parameters, param_names, where_predicates};
 
 #[derive(Debug)]
 pub enum CallableKind {
@@ -18,6 +22,10 @@ pub enum CallableKind {
     StructConstructor,
     VariantConstructor,
     Macro,
+    /// The generic parameters of an item, for signature help in `Vec::<`.
+    GenericParams,
+    /// The fields of a struct or variant, for signature help in `Foo {`.
+    RecordLiteral,
 }
 
 /// Contains information about a function signature
@@ -145,7 +153,15 @@ impl FunctionSignature {
     pub(crate) fn from_macro(db: &RootDatabase, macro_def: hir::MacroDef) -> Option<Self> {
         let node: ast::MacroCall = macro_def.source(db).value;
 
-        let params = vec![];
+        // One parameter per `macro_rules!` arm, so that the matched one can be
+        // highlighted as the active parameter.
+        let params = match (node.name(), node.token_tree()) {
+            (Some(name), Some(rules)) => macro_arms(&rules)
+                .into_iter()
+                .map(|pattern| format!("{}!{}", name.text(), pattern))
+                .collect(),
+            _ => vec![],
+        };
 
         Some(
             FunctionSignature {
@@ -164,6 +180,112 @@ impl FunctionSignature {
             .with_doc_opt(macro_def.docs(db)),
         )
     }
+
+    pub(crate) fn from_generic_def(db: &RootDatabase, def: hir::GenericDef) -> Option<Self> {
+        let (name, generic_parameters, where_predicates) = match def {
+            hir::GenericDef::Function(it) => generics_of("fn", &it.source(db).value),
+            hir::GenericDef::Adt(hir::Adt::Struct(it)) => {
+                generics_of("struct", &it.source(db).value)
+            }
+            hir::GenericDef::Adt(hir::Adt::Enum(it)) => generics_of("enum", &it.source(db).value),
+            hir::GenericDef::Adt(hir::Adt::Union(it)) => generics_of("union", &it.source(db).value),
+            hir::GenericDef::Trait(it) => generics_of("trait", &it.source(db).value),
+            hir::GenericDef::TypeAlias(it) => generics_of("type", &it.source(db).value),
+            hir::GenericDef::ImplDef(_)
+            | hir::GenericDef::EnumVariant(_)
+            | hir::GenericDef::Const(_) => return None,
+        };
+        let doc = match def {
+            hir::GenericDef::Function(it) => it.docs(db),
+            hir::GenericDef::Adt(it) => it.docs(db),
+            hir::GenericDef::Trait(it) => it.docs(db),
+            hir::GenericDef::TypeAlias(it) => it.docs(db),
+            _ => None,
+        };
+
+        Some(
+            FunctionSignature {
+                kind: CallableKind::GenericParams,
+                visibility: None,
+                name: Some(name),
+                ret_type: None,
+                parameters: generic_parameters,
+                parameter_names: vec![],
+                parameter_types: vec![],
+                generic_parameters: vec![],
+                where_predicates,
+                doc: None,
+                has_self_param: false,
+            }
+            .with_doc_opt(doc),
+        )
+    }
+
+    /// `fields` are the fields of `variant` which can still be added to the
+    /// record literal, with their types.
+    pub(crate) fn from_record_literal(
+        db: &RootDatabase,
+        variant: hir::VariantDef,
+        name: String,
+        fields: Vec<(hir::Field, hir::Type)>,
+    ) -> Self {
+        let doc = match variant {
+            hir::VariantDef::Struct(it) => it.docs(db),
+            hir::VariantDef::Union(it) => it.docs(db),
+            hir::VariantDef::EnumVariant(it) => it.docs(db),
+        };
+        let parameter_names: Vec<String> =
+            fields.iter().map(|(field, _)| field.name(db).to_string()).collect();
+        let parameter_types: Vec<String> =
+            fields.iter().map(|(_, ty)| ty.display(db).to_string()).collect();
+        let parameters = parameter_names
+            .iter()
+            .zip(parameter_types.iter())
+            .map(|(name, ty)| format!("{}: {}", name, ty))
+            .collect();
+
+        FunctionSignature {
+            kind: CallableKind::RecordLiteral,
+            visibility: None,
+            name: Some(name),
+            ret_type: None,
+            parameters,
+            parameter_names,
+            parameter_types,
+            generic_parameters: vec![],
+            where_predicates: vec![],
+            doc: None,
+            has_self_param: false,
+        }
+        .with_doc_opt(doc)
+    }
+}
+
+fn generics_of<N: NameOwner + TypeParamsOwner>(
+    keyword: &str,
+    node: &N,
+) -> (String, Vec<String>, Vec<String>) {
+    let name = node.name().map(|it| it.text().to_string()).unwrap_or_default();
+    (format!("{} {}", keyword, name), generic_parameters(node), where_predicates(node))
+}
+
+/// The patterns of the arms in the body of a `macro_rules!`, like `($e:expr)`.
+fn macro_arms(rules: &ast::TokenTree) -> Vec<String> {
+    let mut res = Vec::new();
+    let mut pattern = None;
+    for child in rules.syntax().children_with_tokens() {
+        match child.kind() {
+            TOKEN_TREE => pattern = child.into_node(),
+            T![=>] => {
+                if let Some(pattern) = pattern.take() {
+                    res.push(pattern.text().to_string());
+                }
+            }
+            WHITESPACE | COMMENT => (),
+            _ => pattern = None,
+        }
+    }
+    res
 }
 
 impl From<&'_ ast::FnDef> for FunctionSignature {
@@ -191,33 +313,6 @@ impl From<&'_ ast::FnDef> for FunctionSignature {
             (has_self_param, res, res_types)
         }
 
-        fn param_name_list(node: &ast::FnDef) -> Vec<String> {
-            let mut res = vec![];
-            if let Some(param_list) = node.param_list() {
-                if let Some(self_param) = param_list.self_param() {
-                    res.push(self_param.syntax().text().to_string())
-                }
-
-                res.extend(
-                    param_list
-                        .params()
-                        .map(|param| {
-                            Some(
-                                param
-                                    .pat()?
-                                    .syntax()
-                                    .descendants()
-                                    .find_map(ast::Name::cast)?
-                                    .text()
-                                    .to_string(),
-                            )
-                        })
-                        .map(|param| param.unwrap_or_default()),
-                );
-            }
-            res
-        }
-
         let (has_self_param, parameters, parameter_types) = param_list(node);
 
         FunctionSignature {
@@ -229,7 +324,7 @@ impl From<&'_ ast::FnDef> for FunctionSignature {
                 .and_then(|r| r.type_ref())
                 .map(|n| n.syntax().text().to_string()),
             parameters,
-            parameter_names: param_name_list(node),
+            parameter_names: param_names(node),
             parameter_types,
             generic_parameters: generic_parameters(node),
             where_predicates: where_predicates(node),
@@ -246,12 +341,50 @@ impl Display for FunctionSignature {
             write!(f, "{} ", t)?;
         }
 
-        if let Some(name) = &self.name {
-            match self.kind {
-                CallableKind::Function => write!(f, "fn {}", name)?,
-                CallableKind::StructConstructor => write!(f, "struct {}", name)?,
-                CallableKind::VariantConstructor => write!(f, "{}", name)?,
-                CallableKind::Macro => write!(f, "{}!", name)?,
+        let name = self.name.as_deref();
+        match self.kind {
+            // The parameters are the arms, and already contain the name.
+            CallableKind::Macro if !self.parameters.is_empty() => {
+                return write!(f, "{}", self.parameters.iter().sep_by("\n"));
+            }
+            CallableKind::GenericParams => {
+                write!(f, "{}", name.unwrap_or_default())?;
+                write!(f, "{}", self.parameters.iter().sep_by(", ").surround_with("<", ">"))?;
+                if !self.where_predicates.is_empty() {
+                    write!(f, "\nwhere {}", self.where_predicates.iter().sep_by(",\n      "))?;
+                }
+                return Ok(());
+            }
+            CallableKind::RecordLiteral => {
+                write!(f, "{} ", name.unwrap_or_default())?;
+                if self.parameters.is_empty() {
+                    return write!(f, "{{}}");
+                }
+                return write!(
+                    f,
+                    "{}",
+                    self.parameters.iter().sep_by(", ").surround_with("{ ", " }")
+                );
+            }
+            CallableKind::Function => {
+                if let Some(name) = name {
+                    write!(f, "fn {}", name)?;
+                }
+            }
+            CallableKind::StructConstructor => {
+                if let Some(name) = name {
+                    write!(f, "struct {}", name)?;
+                }
+            }
+            CallableKind::VariantConstructor => {
+                if let Some(name) = name {
+                    write!(f, "{}", name)?;
+                }
+            }
+            CallableKind::Macro => {
+                if let Some(name) = name {
+                    write!(f, "{}!", name)?;
+                }
             }
         }
 
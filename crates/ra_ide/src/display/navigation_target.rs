@@ -336,6 +336,33 @@ impl ToNav for hir::Adt {
     }
 }
 
+impl TryToNav for InFile<ast::UseTree> {
+    fn try_to_nav(&self, db: &RootDatabase) -> Option<NavigationTarget> {
+        let (name, name_node) = match self.value.alias() {
+            Some(alias) => {
+                let name = alias.name()?;
+                (name.text().clone(), name.syntax().clone())
+            }
+            None => {
+                let name_ref = self.value.path()?.segment()?.name_ref()?;
+                (name_ref.text().clone(), name_ref.syntax().clone())
+            }
+        };
+        let focus_range = original_range(db, self.with_value(&name_node)).range;
+        let frange = original_range(db, self.with_value(self.value.syntax()));
+
+        Some(NavigationTarget::from_syntax(
+            frange.file_id,
+            name,
+            Some(focus_range),
+            frange.range,
+            self.value.syntax().kind(),
+            None,
+            None,
+        ))
+    }
+}
+
 impl ToNav for hir::AssocItem {
     fn to_nav(&self, db: &RootDatabase) -> NavigationTarget {
         match self {
@@ -0,0 +1,304 @@
+//! The Rust code blocks of doc comments, which rustdoc compiles as doctests.
+//!
+//! The code of the blocks is extracted into a synthetic file, wrapped into
+//! `fn main` like rustdoc does, so that we can highlight it. Names in it are
+//! resolved the way rustdoc would: the doctest is a separate crate which
+//! depends on the documented one, so only public items reachable through the
+//! crate's name are visible.
+
+use hir::{Adt, AssocItem, HasVisibility, ImplDef, ModuleDef, ScopeDef, Semantics, Visibility};
+use ra_ide_db::{defs::Definition, RootDatabase};
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, AstNode, AstToken, NameOwner},
+    SourceFile, SyntaxNode, SyntaxToken, TextRange, TextSize,
+};
+
+pub(crate) const RUSTDOC_FENCE: &str = "```";
+
+/// Code blocks are Rust unless their info string says otherwise. Blocks
+/// marked `ignore` aren't compiled at all.
+pub(crate) fn is_rust_code_block(info: &str) -> bool {
+    info.split(|c| c == ',' || c == ' ' || c == '\t').all(|attr| match attr.trim() {
+        "" | "rust" | "should_panic" | "no_run" | "compile_fail" | "allow_fail"
+        | "test_harness" | "edition2015" | "edition2018" => true,
+        _ => false,
+    })
+}
+
+#[derive(Debug)]
+pub(crate) struct DocTestCode {
+    pub(crate) text: String,
+    /// The lines of code in `text`, with the offsets they start at in the
+    /// original file.
+    pieces: Vec<(TextRange, TextSize)>,
+}
+
+impl DocTestCode {
+    pub(crate) fn map_range_up(&self, range: TextRange) -> Option<TextRange> {
+        let (piece, original_start) =
+            self.pieces.iter().find(|(piece, _)| piece.contains_range(range))?;
+        Some(range - piece.start() + *original_start)
+    }
+
+    pub(crate) fn map_offset_down(&self, offset: TextSize) -> Option<TextSize> {
+        self.pieces.iter().find_map(|&(piece, original_start)| {
+            let original = TextRange::at(original_start, piece.len());
+            if original.contains_inclusive(offset) {
+                Some(offset - original_start + piece.start())
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Extracts the code of the doctests from the line doc comments which are
+/// direct children of `owner`.
+pub(crate) fn doc_test_code(owner: &SyntaxNode) -> Option<DocTestCode> {
+    let mut text = String::new();
+    let mut pieces = Vec::new();
+    let mut in_code_block = false;
+    let mut is_rust = false;
+
+    let comments = owner
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter_map(ast::Comment::cast)
+        .filter(|it| it.kind().doc.is_some() && it.kind().shape.is_line());
+    for comment in comments {
+        let prefix_len = comment.prefix().len();
+        let mut line = &comment.text()[prefix_len..];
+        let mut start = comment.syntax().text_range().start() + TextSize::of(comment.prefix());
+        if line.starts_with(' ') {
+            line = &line[1..];
+            start += TextSize::of(' ');
+        }
+
+        if line.trim_start().starts_with(RUSTDOC_FENCE) {
+            if in_code_block && is_rust {
+                text.push_str("}\n");
+            } else if !in_code_block {
+                is_rust = is_rust_code_block(&line.trim_start()[RUSTDOC_FENCE.len()..]);
+                if is_rust {
+                    text.push_str("fn main() {\n");
+                }
+            }
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if !(in_code_block && is_rust) {
+            continue;
+        }
+
+        // Lines hidden from the rendered docs are still part of the test.
+        let hidden_len = if line == "#" {
+            1
+        } else if line.starts_with("# ") {
+            2
+        } else {
+            0
+        };
+        let code = &line[hidden_len..];
+        let piece = TextRange::at(TextSize::of(text.as_str()), TextSize::of(code));
+        pieces.push((piece, start + TextSize::of(&line[..hidden_len])));
+        text.push_str(code);
+        text.push('\n');
+    }
+    if in_code_block && is_rust {
+        text.push_str("}\n");
+    }
+
+    if pieces.is_empty() {
+        return None;
+    }
+    Some(DocTestCode { text, pieces })
+}
+
+/// Resolves the name reference at `offset` in the doctest which contains the
+/// doc comment `token`. Returns the range of the reference in the original
+/// file.
+pub(crate) fn resolve_in_doc_test(
+    sema: &Semantics<RootDatabase>,
+    token: &SyntaxToken,
+    offset: TextSize,
+) -> Option<(TextRange, Definition)> {
+    let comment = ast::Comment::cast(token.clone())?;
+    comment.kind().doc?;
+    let owner = token.parent();
+    let code = doc_test_code(&owner)?;
+    let file = SourceFile::parse(&code.text).tree();
+    let name_ref: ast::NameRef = find_node_at_offset(file.syntax(), code.map_offset_down(offset)?)?;
+    let range = code.map_range_up(name_ref.syntax().text_range())?;
+
+    let mut segments = path_segments(&name_ref)?;
+    if let Some(imported) = segments.first().and_then(|first| find_import(&file, first)) {
+        segments.splice(0..1, imported);
+    }
+
+    let krate = sema.scope(&owner).module()?.krate();
+    let crate_name = krate.display_name(sema.db)?.to_string();
+    let (first, rest) = segments.split_first()?;
+    if *first != crate_name {
+        return None;
+    }
+    let mut def = ScopeDef::ModuleDef(ModuleDef::Module(krate.root_module(sema.db)?));
+    for segment in rest {
+        def = resolve_segment(sema.db, krate, def, segment)?;
+    }
+    let def = match def {
+        ScopeDef::ModuleDef(it) => Definition::ModuleDef(it),
+        ScopeDef::MacroDef(it) => Definition::Macro(it),
+        _ => return None,
+    };
+    Some((range, def))
+}
+
+/// The segments of the path up to and including `name_ref`, with the
+/// prefixes of the enclosing `use` trees.
+fn path_segments(name_ref: &ast::NameRef) -> Option<Vec<String>> {
+    let segment = name_ref.syntax().parent().and_then(ast::PathSegment::cast)?;
+    let path = segment.syntax().parent().and_then(ast::Path::cast)?;
+    let mut res = segments_of(&path)?;
+    let use_tree = path
+        .syntax()
+        .ancestors()
+        .take_while(|it| ast::Path::can_cast(it.kind()))
+        .last()
+        .and_then(|it| it.parent())
+        .and_then(ast::UseTree::cast);
+    if let Some(use_tree) = use_tree {
+        for tree in use_tree.syntax().ancestors().skip(1).filter_map(ast::UseTree::cast) {
+            if let Some(prefix) = tree.path() {
+                res.splice(0..0, segments_of(&prefix)?);
+            }
+        }
+    }
+    Some(res)
+}
+
+fn segments_of(path: &ast::Path) -> Option<Vec<String>> {
+    let mut res = Vec::new();
+    let mut path = Some(path.clone());
+    while let Some(it) = path {
+        res.push(it.segment()?.name_ref()?.text().to_string());
+        path = it.qualifier();
+    }
+    res.reverse();
+    Some(res)
+}
+
+/// The full path of the item `use`d as `name` in the doctest, if any.
+fn find_import(file: &SourceFile, name: &str) -> Option<Vec<String>> {
+    file.syntax().descendants().filter_map(ast::UseTree::cast).find_map(|tree| {
+        if tree.use_tree_list().is_some() || tree.star_token().is_some() {
+            return None;
+        }
+        let path = tree.path()?;
+        let imported_as = match tree.alias() {
+            Some(alias) => alias.name()?.text().to_string(),
+            None => path.segment()?.name_ref()?.text().to_string(),
+        };
+        if imported_as != name {
+            return None;
+        }
+        path_segments(&path.segment()?.name_ref()?)
+    })
+}
+
+fn resolve_segment(
+    db: &RootDatabase,
+    krate: hir::Crate,
+    def: ScopeDef,
+    name: &str,
+) -> Option<ScopeDef> {
+    let is_public_assoc = |item: &AssocItem| {
+        item.visibility(db) == Visibility::Public
+            && item.name(db).map_or(false, |it| it.to_string() == name)
+    };
+    let res = match def {
+        ScopeDef::ModuleDef(ModuleDef::Module(module)) => {
+            let (_, def) = module.scope(db, None).into_iter().find(|(it, def)| {
+                let is_public = match def {
+                    ScopeDef::ModuleDef(def) => {
+                        module.visibility_of(db, def) == Some(Visibility::Public)
+                    }
+                    ScopeDef::MacroDef(_) => true,
+                    _ => false,
+                };
+                is_public && it.to_string() == name
+            })?;
+            def
+        }
+        ScopeDef::ModuleDef(ModuleDef::Adt(Adt::Enum(it))) => {
+            let variant = it.variants(db).into_iter().find(|it| it.name(db).to_string() == name);
+            match variant {
+                Some(variant) => ScopeDef::ModuleDef(ModuleDef::EnumVariant(variant)),
+                None => inherent_item(db, krate, Adt::Enum(it), &is_public_assoc)?,
+            }
+        }
+        ScopeDef::ModuleDef(ModuleDef::Adt(adt)) => {
+            inherent_item(db, krate, adt, &is_public_assoc)?
+        }
+        ScopeDef::ModuleDef(ModuleDef::Trait(it)) => {
+            assoc_item_def(it.items(db).into_iter().find(|it| is_public_assoc(it))?)
+        }
+        _ => return None,
+    };
+    Some(res)
+}
+
+fn inherent_item(
+    db: &RootDatabase,
+    krate: hir::Crate,
+    adt: Adt,
+    pred: &dyn Fn(&AssocItem) -> bool,
+) -> Option<ScopeDef> {
+    ImplDef::all_in_crate(db, krate)
+        .into_iter()
+        .filter(|it| it.target_trait(db).is_none() && it.target_ty(db).as_adt() == Some(adt))
+        .flat_map(|it| it.items(db))
+        .find(|it| pred(it))
+        .map(assoc_item_def)
+}
+
+fn assoc_item_def(item: AssocItem) -> ScopeDef {
+    ScopeDef::ModuleDef(match item {
+        AssocItem::Function(it) => ModuleDef::Function(it),
+        AssocItem::Const(it) => ModuleDef::Const(it),
+        AssocItem::TypeAlias(it) => ModuleDef::TypeAlias(it),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_syntax::{AstNode, SourceFile, TextRange, TextSize};
+
+    use super::doc_test_code;
+
+    #[test]
+    fn extracts_doc_test_code() {
+        let text = r#"
+/// Adds one.
+///
+/// ```
+/// # use foo::add_one;
+/// let x = add_one(1);
+/// ```
+///
+/// ```text
+/// not code
+/// ```
+fn add_one(x: i32) -> i32 { x + 1 }
+"#;
+        let file = SourceFile::parse(text).tree();
+        let owner = file.syntax().first_child().unwrap();
+        let code = doc_test_code(&owner).unwrap();
+        assert_eq!(code.text, "fn main() {\nuse foo::add_one;\nlet x = add_one(1);\n}\n");
+
+        let call = TextSize::of("fn main() {\nuse foo::add_one;\nlet x = ");
+        let range = code.map_range_up(TextRange::at(call, TextSize::of("add_one"))).unwrap();
+        assert_eq!(&text[range], "add_one");
+        assert_eq!(code.map_offset_down(range.start()), Some(call));
+    }
+}
@@ -0,0 +1,223 @@
+//! Computes document links: ranges of a file which refer to other files, like
+//! `foo` in `mod foo;` or the path in `include_str!("data.txt")`, and URLs
+//! mentioned in doc comments.
+
+use hir::Semantics;
+use ra_db::{FileId, RelativePathBuf};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    ast::{self, AstNode, AstToken, AttrsOwner, HasQuotes, HasStringValue},
+    match_ast, NodeOrToken, TextRange, TextSize, T,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentLink {
+    pub range: TextRange,
+    pub target: DocumentLinkTarget,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocumentLinkTarget {
+    /// A file known to the analysis, like the one `mod foo;` resolves to.
+    File(FileId),
+    /// A path relative to the directory of `anchor`. `include_str!` and
+    /// friends can refer to files which are not loaded, so we can't always
+    /// produce a `FileId` for them.
+    Path { anchor: FileId, path: RelativePathBuf },
+    /// An URL found in a doc comment.
+    Url(String),
+}
+
+pub(crate) fn document_links(db: &RootDatabase, file_id: FileId) -> Vec<DocumentLink> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(file_id);
+
+    let mut res = Vec::new();
+    for element in source_file.syntax().descendants_with_tokens() {
+        match element {
+            NodeOrToken::Node(node) => {
+                match_ast! {
+                    match node {
+                        ast::Module(it) => { module_links(&sema, it, &mut res); },
+                        ast::MacroCall(it) => { include_link(db, file_id, it, &mut res); },
+                        _ => (),
+                    }
+                }
+            }
+            NodeOrToken::Token(token) => {
+                if let Some(comment) = ast::Comment::cast(token) {
+                    if comment.kind().doc.is_some() {
+                        url_links(&comment, &mut res);
+                    }
+                }
+            }
+        }
+    }
+    res
+}
+
+fn module_links(
+    sema: &Semantics<RootDatabase>,
+    module: ast::Module,
+    acc: &mut Vec<DocumentLink>,
+) -> Option<()> {
+    // Inline modules are already in this file.
+    if module.item_list().is_some() {
+        return None;
+    }
+    let def = sema.to_def(&module)?;
+    let target = def.definition_source(sema.db).file_id.original_file(sema.db);
+    let target = DocumentLinkTarget::File(target);
+
+    acc.push(DocumentLink { range: module.name()?.syntax().text_range(), target: target.clone() });
+
+    let path_attr = module.attrs().find(|it| it.simple_name().as_deref() == Some("path"));
+    if let Some(ast::AttrInput::Literal(lit)) = path_attr.and_then(|it| it.input()) {
+        let range = ast::String::cast(lit.token())?.text_range_between_quotes()?;
+        acc.push(DocumentLink { range, target });
+    }
+    Some(())
+}
+
+fn include_link(
+    db: &RootDatabase,
+    file_id: FileId,
+    macro_call: ast::MacroCall,
+    acc: &mut Vec<DocumentLink>,
+) -> Option<()> {
+    let name_ref = macro_call.path()?.segment()?.name_ref()?;
+    if !matches!(name_ref.text().as_str(), "include" | "include_str" | "include_bytes") {
+        return None;
+    }
+    let string = macro_call
+        .token_tree()?
+        .syntax()
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .find(|it| !it.kind().is_trivia() && !matches!(it.kind(), T!['('] | T!['['] | T!['{']))
+        .and_then(ast::String::cast)?;
+    let path = RelativePathBuf::from_path(string.value()?.replace("\\", "/")).ok()?;
+
+    let target = match db.resolve_relative_path(file_id, &path) {
+        Some(file_id) => DocumentLinkTarget::File(file_id),
+        None => DocumentLinkTarget::Path { anchor: file_id, path },
+    };
+    acc.push(DocumentLink { range: string.text_range_between_quotes()?, target });
+    Some(())
+}
+
+fn url_links(comment: &ast::Comment, acc: &mut Vec<DocumentLink>) {
+    let text = comment.text().as_str();
+    let comment_start = comment.syntax().text_range().start();
+
+    let mut offset = 0;
+    while let Some(start) = find_url_start(&text[offset..]) {
+        let start = offset + start;
+        let len = text[start..]
+            .find(|c: char| c.is_whitespace() || "<>()[]{}\"'`".contains(c))
+            .unwrap_or(text.len() - start);
+        // Trailing punctuation most likely ends the sentence, not the URL.
+        let url = text[start..start + len].trim_end_matches(|c| ".,;:!?".contains(c));
+        offset = start + len;
+
+        if url.ends_with("://") {
+            continue;
+        }
+        let range = TextRange::at(comment_start + TextSize::from(start as u32), TextSize::of(url));
+        acc.push(DocumentLink { range, target: DocumentLinkTarget::Url(url.to_string()) });
+    }
+}
+
+fn find_url_start(text: &str) -> Option<usize> {
+    ["https://", "http://"].iter().filter_map(|scheme| text.find(scheme)).min()
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_db::FileId;
+
+    use crate::{
+        mock_analysis::{single_file, MockAnalysis},
+        Analysis,
+    };
+
+    fn check(analysis: Analysis, file_id: FileId, expected: &str) {
+        let links = analysis.document_links(file_id).unwrap();
+        let actual: String =
+            links.iter().map(|link| format!("{:?} {:?}\n", link.range, link.target)).collect();
+        assert_eq!(actual.trim(), expected.trim());
+    }
+
+    #[test]
+    fn test_module_links() {
+        let mock = MockAnalysis::with_files(
+            r#"
+            //- /lib.rs
+            mod foo;
+            #[path = "baz.rs"]
+            mod bar;
+            mod inline {}
+            //- /foo.rs
+            // empty
+            //- /baz.rs
+            // empty
+            "#,
+        );
+        let lib = mock.id_of("/lib.rs");
+        check(
+            mock.analysis(),
+            lib,
+            r#"
+4..7 File(FileId(2))
+32..35 File(FileId(3))
+19..25 File(FileId(3))
+"#,
+        );
+    }
+
+    #[test]
+    fn test_include_links() {
+        let mock = MockAnalysis::with_files(
+            r#"
+            //- /lib.rs
+            const A: &str = include_str!("foo.rs");
+            const B: &[u8] = include_bytes!("../data.bin");
+            const C: &str = include_str!["foo.rs"];
+            const D: &str = include_str!{"foo.rs"};
+            //- /foo.rs
+            // empty
+            "#,
+        );
+        let lib = mock.id_of("/lib.rs");
+        check(
+            mock.analysis(),
+            lib,
+            r#"
+30..36 File(FileId(2))
+73..84 Path { anchor: FileId(1), path: "../data.bin" }
+118..124 File(FileId(2))
+158..164 File(FileId(2))
+"#,
+        );
+    }
+
+    #[test]
+    fn test_doc_comment_links() {
+        let (analysis, file_id) = single_file(
+            r#"
+/// See https://example.com/docs, and <http://example.org>.
+// https://not-a-doc-comment.com
+/// https://
+fn foo() {}
+"#,
+        );
+        check(
+            analysis,
+            file_id,
+            r#"
+9..33 Url("https://example.com/docs")
+40..58 Url("http://example.org")
+"#,
+        );
+    }
+}
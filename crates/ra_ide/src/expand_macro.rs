@@ -1,19 +1,38 @@
 //! This modules implements "expand macro" functionality in the IDE
 
-use hir::Semantics;
+use hir::{MacroTokenOrigin, Semantics};
 use ra_ide_db::RootDatabase;
 use ra_syntax::{
     algo::{find_node_at_offset, SyntaxRewriter},
-    ast, AstNode, NodeOrToken, SyntaxKind, SyntaxNode, WalkEvent, T,
+    ast, AstNode, NodeOrToken, SyntaxKind, SyntaxNode, SyntaxToken, TextRange, TextSize, WalkEvent,
+    T,
 };
 
-use crate::FilePosition;
+use crate::{FileId, FilePosition, FileRange};
 
 pub struct ExpandedMacro {
     pub name: String,
     pub expansion: String,
 }
 
+/// A file or an item with all macros expanded, recursively.
+#[derive(Debug)]
+pub struct ExpandedFile {
+    pub text: String,
+    /// Where the tokens of `text` come from, sorted by range. Tokens made up
+    /// by a macro are missing.
+    pub origins: Vec<(TextRange, TokenOrigin)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenOrigin {
+    /// The token is written in the file, maybe as an argument to a macro.
+    Input(FileRange),
+    /// The token comes from a macro definition, like the body of a
+    /// `macro_rules!` rule.
+    Definition(FileRange),
+}
+
 pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<ExpandedMacro> {
     let sema = Semantics::new(db);
     let file = sema.parse(position.file_id);
@@ -55,22 +74,144 @@ fn expand_macro_recur(
     Some(res)
 }
 
+/// Expands all macro calls and derives in the file, or in the top-level item
+/// at `offset`. The rest of the code is kept as written.
+pub(crate) fn expand_all_macros(
+    db: &RootDatabase,
+    file_id: FileId,
+    offset: Option<TextSize>,
+) -> Option<ExpandedFile> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(file_id);
+    let root = match offset {
+        Some(offset) => file
+            .syntax()
+            .token_at_offset(offset)
+            .left_biased()?
+            .parent()
+            .ancestors()
+            .filter(|it| ast::ModuleItem::can_cast(it.kind()))
+            .last()?,
+        None => file.syntax().clone(),
+    };
+
+    let mut printer = Printer { sema: &sema, file_id, text: String::new(), origins: Vec::new() };
+    printer.print_source(&root);
+    Some(ExpandedFile { text: printer.text, origins: printer.origins })
+}
+
+struct Printer<'a> {
+    sema: &'a Semantics<'a, RootDatabase>,
+    file_id: FileId,
+    text: String,
+    origins: Vec<(TextRange, TokenOrigin)>,
+}
+
+impl Printer<'_> {
+    fn print_source(&mut self, node: &SyntaxNode) {
+        if let Some(expansion) =
+            ast::MacroCall::cast(node.clone()).and_then(|it| self.sema.expand(&it))
+        {
+            let mut tokens = Vec::new();
+            collect_expanded_tokens(self.sema, &expansion, &mut tokens);
+            self.print_expanded(tokens);
+            return;
+        }
+
+        for child in node.children_with_tokens() {
+            match child {
+                NodeOrToken::Node(it) => self.print_source(&it),
+                NodeOrToken::Token(it) => {
+                    let start = TextSize::of(self.text.as_str());
+                    self.text.push_str(it.text());
+                    if !it.kind().is_trivia() {
+                        let range = FileRange { file_id: self.file_id, range: it.text_range() };
+                        self.origins.push((
+                            TextRange::at(start, TextSize::of(it.text().as_str())),
+                            TokenOrigin::Input(range),
+                        ));
+                    }
+                }
+            }
+        }
+
+        if let Some(item) = ast::ModuleItem::cast(node.clone()) {
+            for derive in self.sema.expand_derives(&item) {
+                let mut tokens = Vec::new();
+                collect_expanded_tokens(self.sema, &derive, &mut tokens);
+                self.text.push_str("\n\n");
+                self.print_expanded(tokens);
+            }
+        }
+    }
+
+    fn print_expanded(&mut self, tokens: Vec<SyntaxToken>) {
+        let start = TextSize::of(self.text.as_str());
+        let sema = self.sema;
+        let origins = &mut self.origins;
+        let text = format_tokens(tokens.into_iter(), &mut |token, range| {
+            let origin = match sema.macro_token_origin(token) {
+                Some((it, MacroTokenOrigin::Call)) => TokenOrigin::Input(it),
+                Some((it, MacroTokenOrigin::Definition)) => TokenOrigin::Definition(it),
+                None => return,
+            };
+            origins.push((range + start, origin));
+        });
+        self.text.push_str(&text);
+    }
+}
+
+/// Collects the tokens of an expansion, with nested macro calls and derives
+/// expanded as well.
+fn collect_expanded_tokens(
+    sema: &Semantics<RootDatabase>,
+    node: &SyntaxNode,
+    acc: &mut Vec<SyntaxToken>,
+) {
+    if let Some(expansion) = ast::MacroCall::cast(node.clone()).and_then(|it| sema.expand(&it)) {
+        collect_expanded_tokens(sema, &expansion, acc);
+        return;
+    }
+    for child in node.children_with_tokens() {
+        match child {
+            NodeOrToken::Node(it) => collect_expanded_tokens(sema, &it, acc),
+            NodeOrToken::Token(it) => {
+                if !it.kind().is_trivia() {
+                    acc.push(it)
+                }
+            }
+        }
+    }
+    if let Some(item) = ast::ModuleItem::cast(node.clone()) {
+        for derive in sema.expand_derives(&item) {
+            collect_expanded_tokens(sema, &derive, acc);
+        }
+    }
+}
+
+fn insert_whitespaces(syn: SyntaxNode) -> String {
+    let tokens = syn.preorder_with_tokens().filter_map(|event| {
+        if let WalkEvent::Enter(NodeOrToken::Token(token)) = event {
+            Some(token)
+        } else {
+            None
+        }
+    });
+    format_tokens(tokens, &mut |_, _| ())
+}
+
 // FIXME: It would also be cool to share logic here and in the mbe tests,
 // which are pretty unreadable at the moment.
-fn insert_whitespaces(syn: SyntaxNode) -> String {
+/// Joins `tokens` with some whitespace in between, calling `on_token` with
+/// the range of each token in the result.
+fn format_tokens(
+    tokens: impl Iterator<Item = SyntaxToken>,
+    on_token: &mut dyn FnMut(&SyntaxToken, TextRange),
+) -> String {
     use SyntaxKind::*;
 
     let mut res = String::new();
-    let mut token_iter = syn
-        .preorder_with_tokens()
-        .filter_map(|event| {
-            if let WalkEvent::Enter(NodeOrToken::Token(token)) = event {
-                Some(token)
-            } else {
-                None
-            }
-        })
-        .peekable();
+    let mut token_iter = tokens.peekable();
 
     let mut indent = 0;
     let mut last: Option<SyntaxKind> = None;
@@ -82,7 +223,7 @@ fn insert_whitespaces(syn: SyntaxNode) -> String {
         let is_last =
             |f: fn(SyntaxKind) -> bool, default| -> bool { last.map(f).unwrap_or(default) };
 
-        res += &match token.kind() {
+        let piece = match token.kind() {
             k if is_text(k) && is_next(|it| !it.is_punct(), true) => token.text().to_string() + " ",
             L_CURLY if is_next(|it| it != R_CURLY, true) => {
                 indent += 1;
@@ -100,6 +241,11 @@ fn insert_whitespaces(syn: SyntaxNode) -> String {
             T![=>] => " => ".to_string(),
             _ => token.text().to_string(),
         };
+        if let Some(offset) = piece.find(token.text().as_str()) {
+            let start = TextSize::of(res.as_str()) + TextSize::from(offset as u32);
+            on_token(&token, TextRange::at(start, TextSize::of(token.text().as_str())));
+        }
+        res += &piece;
 
         last = Some(token.kind());
     }
@@ -284,4 +430,36 @@ fn some_thing() -> u32 {
         assert_eq!(res.name, "foo");
         assert_snapshot!(res.expansion, @r###"0"###);
     }
+
+    #[test]
+    fn expand_all_macros_maps_tokens_back() {
+        let (analysis, file_id) = crate::mock_analysis::single_file(
+            r#"
+macro_rules! make_fn {
+    ($name:ident) => { fn $name() -> u32 { 92 } }
+}
+make_fn!(foo);
+
+#[derive(Clone)]
+struct S;
+"#,
+        );
+        let source = analysis.file_text(file_id).unwrap();
+        let expanded = analysis.expand_all_macros(file_id, None).unwrap().unwrap();
+        assert!(expanded.text.contains("fn foo() -> u32 {"));
+        assert!(expanded.text.contains("impl crate::clone::Clone for S"));
+
+        let origin_of = |text: &str| {
+            let start = TextSize::from(expanded.text.find(text).unwrap() as u32);
+            let range = TextRange::at(start, TextSize::of(text));
+            expanded.origins.iter().find(|(it, _)| *it == range).map(|(_, origin)| *origin)
+        };
+        let source_range = |text: &str, offset: usize| {
+            let start = TextSize::from((source.find(text).unwrap() + offset) as u32);
+            FileRange { file_id, range: TextRange::at(start, TextSize::of(&text[offset..])) }
+        };
+        assert_eq!(origin_of("foo"), Some(TokenOrigin::Input(source_range("make_fn!(foo", 9))));
+        assert_eq!(origin_of("92"), Some(TokenOrigin::Definition(source_range("92", 0))));
+        assert_eq!(origin_of("struct"), Some(TokenOrigin::Input(source_range("struct", 0))));
+    }
 }
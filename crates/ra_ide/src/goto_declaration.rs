@@ -0,0 +1,252 @@
+//! Go to declaration.
+//!
+//! Rust doesn't separate declarations from definitions, so we use the term
+//! for the place which introduces a name: for an item of a trait impl it's
+//! the item of the trait, and for a name brought into scope by `use` it's
+//! the import. Everything else is declared where it is defined.
+
+use hir::{AsAssocItem, AssocItem, AssocItemContainer, InFile, Semantics};
+use ra_ide_db::{
+    defs::{classify_name, classify_name_ref, Definition},
+    RootDatabase,
+};
+use ra_syntax::{ast, match_ast, AstNode, SyntaxKind::MODULE};
+
+use crate::{
+    display::{ToNav, TryToNav},
+    goto_definition::pick_best,
+    FilePosition, NavigationTarget, RangeInfo,
+};
+
+pub(crate) fn goto_declaration(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id).syntax().clone();
+    let original_token = pick_best(file.token_at_offset(position.offset))?;
+
+    // Imports are looked up syntactically, which only makes sense for a name
+    // that is written in the file, and not produced by a macro.
+    if let Some(name_ref) = ast::NameRef::cast(original_token.parent()) {
+        if let Some(use_tree) = import_site(&sema, &name_ref) {
+            let nav = InFile::new(position.file_id.into(), use_tree).try_to_nav(db)?;
+            return Some(RangeInfo::new(original_token.text_range(), vec![nav]));
+        }
+    }
+
+    let token = sema.descend_into_macros(original_token.clone());
+    let def = match_ast! {
+        match (token.parent()) {
+            ast::NameRef(name_ref) => classify_name_ref(&sema, &name_ref)?.definition(),
+            ast::Name(name) => classify_name(&sema, &name)?.definition(),
+            _ => return None,
+        }
+    };
+    let nav = match def {
+        Definition::ModuleDef(def) => match trait_item(db, def) {
+            Some(item) => item.to_nav(db),
+            None => def.try_to_nav(db)?,
+        },
+        _ => def.try_to_nav(db)?,
+    };
+
+    Some(RangeInfo::new(original_token.text_range(), vec![nav]))
+}
+
+/// For an associated item of a trait impl, finds the item of the trait it
+/// implements.
+fn trait_item(db: &RootDatabase, def: hir::ModuleDef) -> Option<AssocItem> {
+    let assoc = match def {
+        hir::ModuleDef::Function(it) => it.as_assoc_item(db),
+        hir::ModuleDef::Const(it) => it.as_assoc_item(db),
+        hir::ModuleDef::TypeAlias(it) => it.as_assoc_item(db),
+        _ => None,
+    }?;
+    let trait_ = match assoc.container(db) {
+        AssocItemContainer::ImplDef(it) => it.target_trait_def(db)?,
+        AssocItemContainer::Trait(_) => return None,
+    };
+    let name = assoc.name(db)?;
+    trait_.items(db).into_iter().find(|item| {
+        std::mem::discriminant(item) == std::mem::discriminant(&assoc)
+            && item.name(db).as_ref() == Some(&name)
+    })
+}
+
+/// Finds the `use` which brings the first segment of a path into scope.
+fn import_site(sema: &Semantics<RootDatabase>, name_ref: &ast::NameRef) -> Option<ast::UseTree> {
+    let segment = name_ref.syntax().parent().and_then(ast::PathSegment::cast)?;
+    let path = segment.parent_path();
+    if path.qualifier().is_some()
+        || path.syntax().ancestors().any(|it| ast::UseItem::can_cast(it.kind()))
+    {
+        return None;
+    }
+    let def = classify_name_ref(sema, name_ref)?.definition();
+
+    // Walk the enclosing blocks and item lists up to the module boundary,
+    // innermost first, so that shadowing imports win.
+    for scope in name_ref.syntax().ancestors() {
+        let use_trees = scope
+            .children()
+            .filter_map(ast::UseItem::cast)
+            .filter_map(|it| it.use_tree())
+            .flat_map(|it| {
+                it.syntax().descendants().filter_map(ast::UseTree::cast).collect::<Vec<_>>()
+            });
+        for use_tree in use_trees {
+            if use_tree.use_tree_list().is_some() || use_tree.star_token().is_some() {
+                continue;
+            }
+            let imported = match use_tree.path().and_then(|it| it.segment()?.name_ref()) {
+                Some(it) => it,
+                None => continue,
+            };
+            let visible_name = match use_tree.alias() {
+                Some(alias) => match alias.name() {
+                    Some(it) => it.text().clone(),
+                    None => continue,
+                },
+                None => imported.text().clone(),
+            };
+            if visible_name != *name_ref.text() {
+                continue;
+            }
+            if classify_name_ref(sema, &imported).map(|it| it.definition()).as_ref() == Some(&def) {
+                return Some(use_tree);
+            }
+        }
+        if scope.parent().map_or(false, |it| it.kind() == MODULE) {
+            break;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::analysis_and_position;
+
+    fn check_goto(ra_fixture: &str, expected: &str) {
+        let (analysis, pos) = analysis_and_position(ra_fixture);
+
+        let mut navs = analysis.goto_declaration(pos).unwrap().unwrap().info;
+        assert_eq!(navs.len(), 1);
+        let nav = navs.pop().unwrap();
+        nav.assert_match(expected);
+    }
+
+    #[test]
+    fn goto_declaration_of_impl_method() {
+        check_goto(
+            "
+            //- /lib.rs
+            trait Trait {
+                fn foo(&self);
+            }
+            struct S;
+            impl Trait for S {
+                fn fo<|>o(&self) {}
+            }
+            ",
+            "foo FN_DEF FileId(1) 18..32 21..24",
+        );
+    }
+
+    #[test]
+    fn goto_declaration_of_method_call() {
+        check_goto(
+            "
+            //- /lib.rs
+            trait Trait {
+                fn foo(&self);
+            }
+            struct S;
+            impl Trait for S {
+                fn foo(&self) {}
+            }
+            fn f(s: S) {
+                s.fo<|>o();
+            }
+            ",
+            "foo FN_DEF FileId(1) 18..32 21..24",
+        );
+    }
+
+    #[test]
+    fn goto_declaration_of_assoc_type_and_const() {
+        check_goto(
+            "
+            //- /lib.rs
+            trait Trait {
+                type Item;
+                const C: u32;
+            }
+            struct S;
+            impl Trait for S {
+                type It<|>em = u8;
+                const C: u32 = 0;
+            }
+            ",
+            "Item TYPE_ALIAS_DEF FileId(1) 18..28 23..27",
+        );
+        check_goto(
+            "
+            //- /lib.rs
+            trait Trait {
+                type Item;
+                const C: u32;
+            }
+            struct S;
+            impl Trait for S {
+                type Item = u8;
+                const <|>C: u32 = 0;
+            }
+            ",
+            "C CONST_DEF FileId(1) 33..46 39..40",
+        );
+    }
+
+    #[test]
+    fn goto_declaration_of_imported_name() {
+        check_goto(
+            "
+            //- /lib.rs
+            mod foo {
+                pub struct Bar;
+            }
+            use foo::Bar;
+            fn f(_: Ba<|>r) {}
+            ",
+            "Bar USE_TREE FileId(1) 36..44 41..44",
+        );
+    }
+
+    #[test]
+    fn goto_declaration_of_renamed_import() {
+        check_goto(
+            "
+            //- /lib.rs
+            mod foo {
+                pub fn bar() {}
+            }
+            use foo::bar as baz;
+            fn f() { ba<|>z() }
+            ",
+            "baz USE_TREE FileId(1) 36..51 48..51",
+        );
+    }
+
+    #[test]
+    fn goto_declaration_falls_back_to_definition() {
+        check_goto(
+            "
+            //- /lib.rs
+            struct Foo;
+            fn f(_: Fo<|>o) {}
+            ",
+            "Foo STRUCT_DEF FileId(1) 0..11 7..10",
+        );
+    }
+}
@@ -14,6 +14,7 @@ use ra_syntax::{
 
 use crate::{
     display::{ToNav, TryToNav},
+    doc_tests::resolve_in_doc_test,
     FilePosition, NavigationTarget, RangeInfo,
 };
 
@@ -24,6 +25,10 @@ pub(crate) fn goto_definition(
     let sema = Semantics::new(db);
     let file = sema.parse(position.file_id).syntax().clone();
     let original_token = pick_best(file.token_at_offset(position.offset))?;
+    if original_token.kind() == COMMENT {
+        let (range, def) = resolve_in_doc_test(&sema, &original_token, position.offset)?;
+        return Some(RangeInfo::new(range, vec![def.try_to_nav(db)?]));
+    }
     let token = sema.descend_into_macros(original_token.clone());
 
     let nav_targets = match_ast! {
@@ -43,7 +48,7 @@ pub(crate) fn goto_definition(
     Some(RangeInfo::new(original_token.text_range(), nav_targets))
 }
 
-fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
+pub(crate) fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
     return tokens.max_by_key(priority);
     fn priority(n: &SyntaxToken) -> usize {
         match n.kind() {
@@ -854,4 +859,30 @@ mod tests {
             "x",
         )
     }
+
+    #[test]
+    fn goto_def_in_doc_test() {
+        check_goto(
+            r#"
+            //- /lib.rs
+            fn main() {}
+
+            //- /foo/lib.rs
+            pub mod bar {
+                pub struct Baz;
+                impl Baz {
+                    pub fn new() -> Baz { Baz }
+                }
+            }
+
+            /// ```
+            /// use foo::bar::Baz;
+            /// let baz = Baz::ne<|>w();
+            /// ```
+            pub fn quux() {}
+            "#,
+            "new FN_DEF FileId(2) 57..84 64..67",
+            "pub fn new() -> Baz { Baz }|new",
+        );
+    }
 }
@@ -0,0 +1,286 @@
+//! Highlights the occurrences related to the keyword under the cursor: the
+//! exit points of a function, the `break`s and `continue`s of a loop, or the
+//! `.await`s of an async function or block.
+
+use hir::Semantics;
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    ast::{self, AstNode},
+    match_ast, SyntaxKind, SyntaxNode, SyntaxToken, TextRange, T,
+};
+
+use crate::{goto_definition::pick_best, FilePosition};
+
+pub(crate) fn highlight_related(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<TextRange>> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let token = pick_best(file.syntax().token_at_offset(position.offset))?;
+
+    let (keyword, related) = match token.kind() {
+        // `?` also appears in `?Sized` bounds and `fn` in function pointer
+        // types, neither of which has anything to do with exit points.
+        T![fn] if token.parent().kind() == SyntaxKind::FN_DEF => exit_points(&sema, &token)?,
+        T![?] if token.parent().kind() == SyntaxKind::TRY_EXPR => exit_points(&sema, &token)?,
+        T![return] => exit_points(&sema, &token)?,
+        T![loop] | T![while] | T![for] | T![break] | T![continue] => loop_exits(&sema, &token)?,
+        T![async] | T![await] => await_points(&sema, &token)?,
+        _ => return None,
+    };
+
+    let mut res: Vec<TextRange> = keyword.into_iter().collect();
+    for expr in related {
+        let range = sema.original_range(expr.syntax());
+        if range.file_id != position.file_id {
+            continue;
+        }
+        res.push(focus_range(&expr).unwrap_or(range.range));
+    }
+    res.sort_by_key(|range| range.start());
+    res.dedup();
+    Some(res)
+}
+
+fn exit_points(
+    sema: &Semantics<RootDatabase>,
+    token: &SyntaxToken,
+) -> Option<(Option<TextRange>, Vec<ast::Expr>)> {
+    let (owner, body) = enclosing_body(token)?;
+    let keyword = ast::FnDef::cast(owner).and_then(|it| it.fn_token()).map(|it| it.text_range());
+    Some((keyword, sema.exit_points(&body)))
+}
+
+fn loop_exits(
+    sema: &Semantics<RootDatabase>,
+    token: &SyntaxToken,
+) -> Option<(Option<TextRange>, Vec<ast::Expr>)> {
+    let expr = ast::Expr::cast(token.parent())?;
+    let loop_expr = match token.kind() {
+        T![break] | T![continue] => sema.break_target(&expr)?,
+        _ => expr,
+    };
+    let keyword = loop_expr
+        .syntax()
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .find(|it| matches!(it.kind(), T![loop] | T![while] | T![for]))
+        .map(|it| it.text_range());
+    Some((keyword, sema.loop_exits(&loop_expr)))
+}
+
+fn await_points(
+    sema: &Semantics<RootDatabase>,
+    token: &SyntaxToken,
+) -> Option<(Option<TextRange>, Vec<ast::Expr>)> {
+    let (owner, body) = enclosing_body(token)?;
+    let keyword = child_token(&owner, T![async])?.text_range();
+    Some((Some(keyword), sema.await_points(&body)))
+}
+
+/// The function, closure or async block containing `token`, with its body.
+fn enclosing_body(token: &SyntaxToken) -> Option<(SyntaxNode, ast::Expr)> {
+    token.parent().ancestors().find_map(|node| {
+        let body = match_ast! {
+            match node {
+                ast::FnDef(it) => it.body().map(ast::Expr::from),
+                ast::LambdaExpr(it) => it.body(),
+                ast::BlockExpr(it) => {
+                    it.async_token()?;
+                    Some(it.into())
+                },
+                _ => return None,
+            }
+        };
+        Some((node, body?))
+    })
+}
+
+/// The keyword of an exit point, like `return` or `break 'label`. Other
+/// expressions, like tail expressions, are highlighted as a whole.
+fn focus_range(expr: &ast::Expr) -> Option<TextRange> {
+    // The tokens of macro expansions have no place in the file.
+    if expr.syntax().ancestors().last()?.kind() != SyntaxKind::SOURCE_FILE {
+        return None;
+    }
+    let range = match expr {
+        ast::Expr::ReturnExpr(_) => child_token(expr.syntax(), T![return])?.text_range(),
+        ast::Expr::TryExpr(_) => child_token(expr.syntax(), T![?])?.text_range(),
+        ast::Expr::AwaitExpr(_) => child_token(expr.syntax(), T![await])?.text_range(),
+        ast::Expr::BreakExpr(_) | ast::Expr::ContinueExpr(_) => {
+            let keyword = child_token(expr.syntax(), T![break])
+                .or_else(|| child_token(expr.syntax(), T![continue]))?;
+            match child_token(expr.syntax(), SyntaxKind::LIFETIME) {
+                Some(label) => keyword.text_range().cover(label.text_range()),
+                None => keyword.text_range(),
+            }
+        }
+        _ => return None,
+    };
+    Some(range)
+}
+
+fn child_token(node: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
+    node.children_with_tokens().filter_map(|it| it.into_token()).find(|it| it.kind() == kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::analysis_and_position;
+
+    fn check(ra_fixture: &str, expected: &[&str]) {
+        let (analysis, position) = analysis_and_position(ra_fixture);
+        let text = analysis.file_text(position.file_id).unwrap();
+        let ranges = analysis.highlight_related(position).unwrap().unwrap_or_default();
+        let actual: Vec<&str> = ranges.into_iter().map(|range| &text[range]).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn highlights_exit_points() {
+        check(
+            r#"
+            //- /lib.rs
+            fn foo(x: Option<u32>) -> Option<u32> {
+                let y = x?;
+                let f = || { return 1; };
+                if y == 0 {
+                    ret<|>urn None;
+                }
+                Some(y + 1)
+            }
+            "#,
+            &["fn", "?", "return", "Some(y + 1)"],
+        );
+    }
+
+    #[test]
+    fn exit_points_skip_async_blocks() {
+        check(
+            r#"
+            //- /lib.rs
+            fn foo(x: Option<u32>) -> Option<u32> {
+                let f = async { x?; return 1; };
+                re<|>turn x;
+            }
+            "#,
+            &["fn", "return"],
+        );
+        check(
+            r#"
+            //- /lib.rs
+            fn foo(x: Option<u32>) -> Option<u32> {
+                let f = async { x?; re<|>turn 1; };
+                return x;
+            }
+            "#,
+            &["?", "return"],
+        );
+    }
+
+    #[test]
+    fn no_exit_points_for_sized_bounds_and_fn_pointers() {
+        check(
+            r#"
+            //- /lib.rs
+            fn foo<T: <|>?Sized>(x: Option<u32>) -> Option<u32> {
+                return x;
+            }
+            "#,
+            &[],
+        );
+        check(
+            r#"
+            //- /lib.rs
+            fn foo(f: f<|>n() -> Option<u32>) -> Option<u32> {
+                return f();
+            }
+            "#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn highlights_loop_exits() {
+        check(
+            r#"
+            //- /lib.rs
+            fn foo() {
+                'outer: loop {
+                    while true {
+                        if true { break 'outer; }
+                        continue;
+                    }
+                    br<|>eak;
+                }
+            }
+            "#,
+            &["loop", "break 'outer", "break"],
+        );
+        check(
+            r#"
+            //- /lib.rs
+            fn foo() {
+                'outer: loop {
+                    wh<|>ile true {
+                        if true { break 'outer; }
+                        continue;
+                    }
+                    break;
+                }
+            }
+            "#,
+            &["while", "continue"],
+        );
+    }
+
+    #[test]
+    fn highlights_await_points() {
+        check(
+            r#"
+            //- /lib.rs
+            async fn bar() {}
+            async<|> fn foo() {
+                bar().await;
+                let f = async move || { bar().await };
+                bar().await
+            }
+            "#,
+            &["async", "await", "await"],
+        );
+    }
+
+    #[test]
+    fn highlights_await_points_of_async_blocks() {
+        check(
+            r#"
+            //- /lib.rs
+            async fn bar() {}
+            async fn foo() {
+                bar().await;
+                let f = asy<|>nc move {
+                    bar().await;
+                    async { bar().await };
+                    bar().await
+                };
+                f.await
+            }
+            "#,
+            &["async", "await", "await"],
+        );
+        check(
+            r#"
+            //- /lib.rs
+            async fn bar() {}
+            fn foo() {
+                let f = async {
+                    bar().await;
+                    bar().aw<|>ait
+                };
+            }
+            "#,
+            &["async", "await", "await"],
+        );
+    }
+}
@@ -2,8 +2,8 @@
 //! source code items (e.g. function call, struct field, variable symbol...)
 
 use hir::{
-    Adt, AsAssocItem, AssocItemContainer, FieldSource, HasSource, HirDisplay, ModuleDef,
-    ModuleSource, Semantics,
+    Adt, AsAssocItem, AssocItemContainer, FieldSource, HasSource, HirDisplay, Layout, ModuleDef,
+    ModuleSource, Semantics, VariantDef,
 };
 use ra_db::SourceDatabase;
 use ra_ide_db::{
@@ -18,16 +18,40 @@ use ra_syntax::{
 };
 
 use crate::{
-    display::{macro_label, rust_code_markup, rust_code_markup_with_doc, ShortLabel},
-    FilePosition, RangeInfo,
+    display::{
+        macro_label, rust_code_markup, rust_code_markup_with_doc, ShortLabel, ToNav, TryToNav,
+    },
+    doc_tests::resolve_in_doc_test,
+    runnables::runnable_for_function,
+    FilePosition, NavigationTarget, RangeInfo, Runnable,
 };
 use itertools::Itertools;
 use std::iter::once;
+use stdx::format_to;
 
 /// Contains the results when hovering over an item
 #[derive(Debug, Default)]
 pub struct HoverResult {
     results: Vec<String>,
+    actions: Vec<HoverAction>,
+}
+
+/// Something the user can do with the hovered item, which clients show as a
+/// link below the hover.
+#[derive(Debug)]
+pub enum HoverAction {
+    /// Show the impls of the hovered struct, enum, union or trait.
+    Implementation(FilePosition),
+    /// Run or debug the hovered test, benchmark or `main` function.
+    Runnable(Runnable),
+    /// Go to the definitions of the types mentioned in the hovered item.
+    GoToType(Vec<HoverGotoTypeData>),
+}
+
+#[derive(Debug)]
+pub struct HoverGotoTypeData {
+    pub mod_path: String,
+    pub nav: NavigationTarget,
 }
 
 impl HoverResult {
@@ -55,6 +79,14 @@ impl HoverResult {
         &self.results
     }
 
+    pub fn push_action(&mut self, action: HoverAction) {
+        self.actions.push(action);
+    }
+
+    pub fn actions(&self) -> &[HoverAction] {
+        &self.actions
+    }
+
     /// Returns the results converted into markup
     /// for displaying in a UI
     pub fn to_markup(&self) -> String {
@@ -74,6 +106,49 @@ fn hover_text(
     }
 }
 
+/// Like `hover_text`, but shows the memory layout of a type or field before
+/// its docs.
+fn hover_text_with_layout(
+    docs: Option<String>,
+    desc: Option<String>,
+    mod_path: Option<String>,
+    layout: Option<String>,
+) -> Option<String> {
+    let docs = match (layout, docs) {
+        (Some(layout), Some(docs)) => Some(format!("{}\n\n{}", layout, docs)),
+        (layout, docs) => layout.or(docs),
+    };
+    hover_text(docs, desc, mod_path)
+}
+
+fn layout_text(layout: Layout, offset: Option<u64>, approximate: bool) -> String {
+    let mut buf = format!("size = {}, align = {}", layout.size, layout.align);
+    if let Some(offset) = offset {
+        format_to!(buf, ", offset = {}", offset);
+    }
+    if approximate || layout.approximate {
+        buf.push_str(" (approximate)");
+    }
+    buf
+}
+
+fn adt_layout(db: &RootDatabase, adt: Adt) -> Option<String> {
+    Some(layout_text(adt.ty(db).layout(db)?, None, false))
+}
+
+fn field_layout(db: &RootDatabase, field: hir::Field) -> Option<String> {
+    let layout = field.layout(db)?;
+    // The offset depends on the other fields, which we might not know.
+    let parent: Option<Adt> = match field.parent_def(db) {
+        VariantDef::Struct(it) => Some(it.into()),
+        VariantDef::Union(it) => Some(it.into()),
+        VariantDef::EnumVariant(_) => None,
+    };
+    let parent_approximate =
+        parent.and_then(|it| it.ty(db).layout(db)).map_or(false, |it| it.approximate);
+    Some(layout_text(layout, field.offset(db), parent_approximate))
+}
+
 fn definition_owner_name(db: &RootDatabase, def: &Definition) -> Option<String> {
     match def {
         Definition::Field(f) => Some(f.parent_def(db).name(db)),
@@ -119,9 +194,12 @@ fn hover_text_from_name_kind(db: &RootDatabase, def: Definition) -> Option<Strin
         Definition::Field(it) => {
             let src = it.source(db);
             match src.value {
-                FieldSource::Named(it) => {
-                    hover_text(it.doc_comment_text(), it.short_label(), mod_path)
-                }
+                FieldSource::Named(src) => hover_text_with_layout(
+                    src.doc_comment_text(),
+                    src.short_label(),
+                    mod_path,
+                    field_layout(db, it),
+                ),
                 _ => None,
             }
         }
@@ -133,9 +211,15 @@ fn hover_text_from_name_kind(db: &RootDatabase, def: Definition) -> Option<Strin
                 _ => None,
             },
             ModuleDef::Function(it) => from_def_source(db, it, mod_path),
-            ModuleDef::Adt(Adt::Struct(it)) => from_def_source(db, it, mod_path),
-            ModuleDef::Adt(Adt::Union(it)) => from_def_source(db, it, mod_path),
-            ModuleDef::Adt(Adt::Enum(it)) => from_def_source(db, it, mod_path),
+            ModuleDef::Adt(Adt::Struct(it)) => {
+                from_def_source_with_layout(db, it, mod_path, adt_layout(db, it.into()))
+            }
+            ModuleDef::Adt(Adt::Union(it)) => {
+                from_def_source_with_layout(db, it, mod_path, adt_layout(db, it.into()))
+            }
+            ModuleDef::Adt(Adt::Enum(it)) => {
+                from_def_source_with_layout(db, it, mod_path, adt_layout(db, it.into()))
+            }
             ModuleDef::EnumVariant(it) => from_def_source(db, it, mod_path),
             ModuleDef::Const(it) => from_def_source(db, it, mod_path),
             ModuleDef::Static(it) => from_def_source(db, it, mod_path),
@@ -158,16 +242,38 @@ fn hover_text_from_name_kind(db: &RootDatabase, def: Definition) -> Option<Strin
         let src = def.source(db);
         hover_text(src.value.doc_comment_text(), src.value.short_label(), mod_path)
     }
+
+    fn from_def_source_with_layout<A, D>(
+        db: &RootDatabase,
+        def: D,
+        mod_path: Option<String>,
+        layout: Option<String>,
+    ) -> Option<String>
+    where
+        D: HasSource<Ast = A>,
+        A: ast::DocCommentsOwner + ast::NameOwner + ShortLabel,
+    {
+        let src = def.source(db);
+        let docs = src.value.doc_comment_text();
+        hover_text_with_layout(docs, src.value.short_label(), mod_path, layout)
+    }
 }
 
 pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeInfo<HoverResult>> {
     let sema = Semantics::new(db);
     let file = sema.parse(position.file_id).syntax().clone();
     let token = pick_best(file.token_at_offset(position.offset))?;
-    let token = sema.descend_into_macros(token);
 
     let mut res = HoverResult::new();
 
+    if token.kind() == COMMENT {
+        let (range, def) = resolve_in_doc_test(&sema, &token, position.offset)?;
+        res.extend(hover_text_from_name_kind(db, def));
+        return if res.is_empty() { None } else { Some(RangeInfo::new(range, res)) };
+    }
+
+    let token = sema.descend_into_macros(token);
+
     if let Some((node, name_kind)) = match_ast! {
         match (token.parent()) {
             ast::NameRef(name_ref) => {
@@ -180,9 +286,11 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
         }
     } {
         let range = sema.original_range(&node).range;
+        let actions = definition_actions(db, &name_kind, position);
         res.extend(hover_text_from_name_kind(db, name_kind));
 
         if !res.is_empty() {
+            actions.into_iter().for_each(|it| res.push_action(it));
             return Some(RangeInfo::new(range, res));
         }
     }
@@ -209,10 +317,80 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
     }?;
 
     res.extend(Some(rust_code_markup(&ty.display_truncated(db, None))));
+    if let Some(action) = goto_type_action(db, once(ty)) {
+        res.push_action(action);
+    }
     let range = sema.original_range(&node).range;
     Some(RangeInfo::new(range, res))
 }
 
+fn definition_actions(
+    db: &RootDatabase,
+    def: &Definition,
+    position: FilePosition,
+) -> Vec<HoverAction> {
+    let mut res = Vec::new();
+    let module_def = match def {
+        Definition::ModuleDef(it) => Some(*it),
+        _ => None,
+    };
+
+    let impls_target = match module_def {
+        Some(ModuleDef::Adt(it)) => Some(it.to_nav(db)),
+        Some(ModuleDef::Trait(it)) => Some(it.to_nav(db)),
+        _ => None,
+    };
+    if let Some(nav) = impls_target {
+        let offset = nav.focus_range().unwrap_or_else(|| nav.full_range()).start();
+        res.push(HoverAction::Implementation(FilePosition { file_id: nav.file_id(), offset }));
+    }
+
+    if let Some(ModuleDef::Function(it)) = module_def {
+        if let Some(runnable) = runnable_for_function(db, it, position.file_id) {
+            res.push(HoverAction::Runnable(runnable));
+        }
+    }
+
+    let types = match def {
+        Definition::ModuleDef(ModuleDef::Function(it)) => it.signature_types(db),
+        Definition::Field(it) => vec![it.signature_ty(db)],
+        Definition::Local(it) => vec![it.ty(db)],
+        _ => Vec::new(),
+    };
+    res.extend(goto_type_action(db, types));
+    res
+}
+
+fn goto_type_action(
+    db: &RootDatabase,
+    types: impl IntoIterator<Item = hir::Type>,
+) -> Option<HoverAction> {
+    let mut targets: Vec<ModuleDef> = Vec::new();
+    for ty in types {
+        for def in ty.referenced_defs(db) {
+            if !targets.contains(&def) {
+                targets.push(def);
+            }
+        }
+    }
+    let targets: Vec<HoverGotoTypeData> = targets
+        .into_iter()
+        .filter_map(|def| {
+            let nav = def.try_to_nav(db)?;
+            let name = def.name(db)?;
+            let mod_path = match determine_mod_path(db, &Definition::ModuleDef(def)) {
+                Some(path) if !path.is_empty() => format!("{}::{}", path, name),
+                _ => name.to_string(),
+            };
+            Some(HoverGotoTypeData { mod_path, nav })
+        })
+        .collect();
+    if targets.is_empty() {
+        return None;
+    }
+    Some(HoverAction::GoToType(targets))
+}
+
 fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
     return tokens.max_by_key(priority);
     fn priority(n: &SyntaxToken) -> usize {
@@ -230,7 +408,10 @@ mod tests {
     use ra_db::FileLoader;
     use ra_syntax::TextRange;
 
-    use crate::mock_analysis::{analysis_and_position, single_file_with_position};
+    use crate::{
+        mock_analysis::{analysis_and_position, single_file_with_position},
+        HoverAction,
+    };
 
     fn trim_markup(s: &str) -> &str {
         s.trim_start_matches("```rust\n").trim_end_matches("\n```")
@@ -263,6 +444,32 @@ mod tests {
         assert!(analysis.hover(position).unwrap().is_none());
     }
 
+    fn check_actions(fixture: &str, expected: &str) {
+        let (analysis, position) = analysis_and_position(fixture);
+        let hover = analysis.hover(position).unwrap().unwrap();
+        let mut actual = String::new();
+        for action in hover.info.actions() {
+            match action {
+                HoverAction::Implementation(pos) => {
+                    actual += &format!("implementation: {:?} {:?}\n", pos.file_id, pos.offset)
+                }
+                HoverAction::Runnable(runnable) => {
+                    actual += &format!("runnable: {:?}\n", runnable.kind)
+                }
+                HoverAction::GoToType(targets) => {
+                    for target in targets {
+                        actual += &format!(
+                            "go to type: {} {}\n",
+                            target.mod_path,
+                            target.nav.debug_render()
+                        );
+                    }
+                }
+            }
+        }
+        test_utils::assert_eq_text!(expected.trim(), actual.trim());
+    }
+
     #[test]
     fn hover_shows_type_of_an_expression() {
         let (analysis, position) = single_file_with_position(
@@ -334,6 +541,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hover_in_doc_test() {
+        let hover_on = check_hover_result(
+            r#"
+            //- /lib.rs
+            fn main() {}
+
+            //- /foo/lib.rs
+            pub fn add_one(x: i32) -> i32 { x + 1 }
+
+            /// ```
+            /// let three = foo::add_two(foo::add_o<|>ne(0));
+            /// ```
+            pub fn add_two(x: i32) -> i32 { x + 2 }
+        "#,
+            &["foo\npub fn add_one(x: i32) -> i32"],
+        );
+        assert_eq!(hover_on, "add_one");
+    }
+
     #[test]
     fn hover_shows_fn_signature_on_fn_name() {
         check_hover_result(
@@ -364,7 +591,7 @@ mod tests {
                 };
             }
         "#,
-            &["Foo\nfield_a: u32"],
+            &["Foo\nfield_a: u32\n```\n\nsize = 4, align = 4, offset = 0"],
         );
 
         // Hovering over the field in the definition
@@ -381,7 +608,7 @@ mod tests {
                 };
             }
         "#,
-            &["Foo\nfield_a: u32"],
+            &["Foo\nfield_a: u32\n```\n\nsize = 4, align = 4, offset = 0"],
         );
     }
 
@@ -450,6 +677,41 @@ fn main() {
         assert_eq!(trim_markup_opt(hover.info.first()), Some("Option<i32>"));
     }
 
+    #[test]
+    fn hover_shows_layout() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            /// Docs
+            struct Fo<|>o { a: u8, b: u32 }
+        "#,
+            &["struct Foo\n```\n\nsize = 8, align = 4\n\nDocs"],
+        );
+        check_hover_result(
+            r#"
+            //- /main.rs
+            enum Option<T> { None, Some(T) }
+            struct Foo<'a> { a: u8, b: Option<&'a u32> }
+            fn f(foo: Foo) { foo.b<|>; }
+        "#,
+            &["Foo\nb: Option<&'a u32>\n```\n\nsize = 8, align = 8, offset = 0"],
+        );
+        check_hover_result(
+            r#"
+            //- /main.rs
+            struct Foo<T> { a<|>: u8, t: T }
+        "#,
+            &["Foo\na: u8\n```\n\nsize = 1, align = 1, offset = 0 (approximate)"],
+        );
+        check_hover_result(
+            r#"
+            //- /main.rs
+            struct Foo { a: u8, b<|>: [u16; 3] }
+        "#,
+            &["Foo\nb: [u16; 3]\n```\n\nsize = 6, align = 2, offset = 0"],
+        );
+    }
+
     #[test]
     fn hover_enum_variant() {
         check_hover_result(
@@ -844,4 +1106,46 @@ fn func(foo: i32) { if true { <|>foo; }; }
             &["fn foo()\n```\n\n<- `\u{3000}` here"],
         );
     }
+
+    #[test]
+    fn hover_actions_for_struct() {
+        check_actions(
+            "
+            //- /lib.rs
+            struct Foo<|>;
+            impl Foo {}
+            ",
+            "implementation: FileId(1) 7",
+        );
+    }
+
+    #[test]
+    fn hover_actions_for_test_fn() {
+        check_actions(
+            "
+            //- /lib.rs
+            #[test]
+            fn foo_<|>test() {}
+            ",
+            r#"runnable: Test { test_id: Path("foo_test"), attr: TestAttr { ignore: false } }"#,
+        );
+    }
+
+    #[test]
+    fn hover_actions_go_to_types() {
+        check_actions(
+            "
+            //- /lib.rs
+            mod m { pub struct B; }
+            struct A<T>(T);
+            trait Tr {}
+            fn f(x: A<(m::B, &dyn Tr)>) { x<|>; }
+            ",
+            "
+go to type: m::B B STRUCT_DEF FileId(1) 8..21 19..20
+go to type: Tr Tr TRAIT_DEF FileId(1) 40..51 46..48
+go to type: A A STRUCT_DEF FileId(1) 24..39 31..32
+            ",
+        );
+    }
 }
@@ -0,0 +1,85 @@
+//! Finds the code which is disabled by `#[cfg]` attributes for the crate of a
+//! file. Such code never makes it into the HIR, so this works on the syntax.
+
+use ra_cfg::{CfgExpr, CfgOptions};
+use ra_db::SourceDatabase;
+use ra_ide_db::RootDatabase;
+use ra_syntax::{ast, AstNode, SyntaxNode};
+
+use crate::{Diagnostic, FileId, Severity};
+
+/// The cfg options of the crate `file_id` belongs to. For files shared by
+/// several crates, the first one wins.
+pub(crate) fn cfg_options(db: &RootDatabase, file_id: FileId) -> Option<CfgOptions> {
+    let krate = *db.relevant_crates(file_id).first()?;
+    Some(db.crate_graph()[krate].cfg_options.clone())
+}
+
+/// The `#[cfg]` of `node` which evaluates to false, if any.
+pub(crate) fn disabling_cfg(node: &SyntaxNode, cfg_options: &CfgOptions) -> Option<CfgExpr> {
+    node.children()
+        .filter_map(ast::Attr::cast)
+        .filter_map(|attr| hir::parse_cfg_attr(&attr))
+        .find(|cfg| cfg_options.check(cfg) == Some(false))
+}
+
+pub(crate) fn inactive_code_diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic> {
+    let mut res = Vec::new();
+    if let Some(cfg_options) = cfg_options(db, file_id) {
+        collect(&db.parse(file_id).tree().syntax(), &cfg_options, &mut res);
+    }
+    return res;
+
+    fn collect(node: &SyntaxNode, cfg_options: &CfgOptions, acc: &mut Vec<Diagnostic>) {
+        let cfg = match disabling_cfg(node, cfg_options) {
+            Some(it) => it,
+            None => {
+                node.children().for_each(|child| collect(&child, cfg_options, acc));
+                return;
+            }
+        };
+        let reason = cfg_options.explain_disabled(&cfg).unwrap_or_else(|| cfg.to_string());
+        acc.push(Diagnostic {
+            range: node.text_range(),
+            message: format!("code is inactive due to #[cfg] directives: {}", reason),
+            severity: Severity::WeakWarning,
+            fix: None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::single_file;
+
+    #[test]
+    fn inactive_code_diagnostics() {
+        let (analysis, file_id) = single_file(
+            r#"
+#[cfg(feature = "foo")]
+fn foo() {}
+
+#[cfg(not(test))]
+fn bar() {}
+
+fn main() {
+    #[cfg(all(unix, not(unix)))]
+    let x = 92;
+}
+"#,
+        );
+        let messages: Vec<String> = analysis
+            .inactive_code_diagnostics(file_id)
+            .unwrap()
+            .into_iter()
+            .map(|it| it.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                r#"code is inactive due to #[cfg] directives: feature = "foo" is not enabled"#,
+                "code is inactive due to #[cfg] directives: unix is not enabled",
+            ]
+        );
+    }
+}
@@ -1,11 +1,12 @@
 //! This module defines multiple types of inlay hints and their visibility
 
-use hir::{Adt, HirDisplay, Semantics, Type};
+use hir::{Adt, HasSource, HirDisplay, ModuleDef, Mutability, PathResolution, Semantics, Type};
+use itertools::Itertools;
 use ra_ide_db::RootDatabase;
 use ra_prof::profile;
 use ra_syntax::{
-    ast::{self, ArgListOwner, AstNode, TypeAscriptionOwner},
-    match_ast, Direction, NodeOrToken, SmolStr, SyntaxKind, TextRange,
+    ast::{self, ArgListOwner, AstNode, TypeAscriptionOwner, TypeParamsOwner},
+    match_ast, Direction, NodeOrToken, SmolStr, SyntaxKind, SyntaxNode, SyntaxToken, TextRange,
 };
 
 use crate::{FileId, FunctionSignature};
@@ -15,12 +16,25 @@ pub struct InlayHintsConfig {
     pub type_hints: bool,
     pub parameter_hints: bool,
     pub chaining_hints: bool,
+    pub lifetime_elision_hints: bool,
+    pub closure_return_type_hints: bool,
+    pub closure_capture_hints: bool,
+    pub adjustment_hints: bool,
     pub max_length: Option<usize>,
 }
 
 impl Default for InlayHintsConfig {
     fn default() -> Self {
-        Self { type_hints: true, parameter_hints: true, chaining_hints: true, max_length: None }
+        Self {
+            type_hints: true,
+            parameter_hints: true,
+            chaining_hints: true,
+            lifetime_elision_hints: false,
+            closure_return_type_hints: false,
+            closure_capture_hints: false,
+            adjustment_hints: false,
+            max_length: None,
+        }
     }
 }
 
@@ -29,6 +43,14 @@ pub enum InlayKind {
     TypeHint,
     ParameterHint,
     ChainingHint,
+    /// An elided lifetime, shown after the `&` of a reference in a signature.
+    LifetimeHint,
+    /// The return type of a closure, shown after its parameters.
+    ClosureReturnTypeHint,
+    /// How a closure captures the variables it uses, shown before it.
+    ClosureCaptureHint,
+    /// An implicit reborrow or deref coercion, shown before the argument.
+    AdjustmentHint,
 }
 
 #[derive(Debug)]
@@ -55,9 +77,20 @@ pub(crate) fn inlay_hints(
 
         match_ast! {
             match node {
-                ast::CallExpr(it) => { get_param_name_hints(&mut res, &sema, config, ast::Expr::from(it)); },
-                ast::MethodCallExpr(it) => { get_param_name_hints(&mut res, &sema, config, ast::Expr::from(it)); },
+                ast::CallExpr(it) => {
+                    get_param_name_hints(&mut res, &sema, config, ast::Expr::from(it.clone()));
+                    get_adjustment_hints(&mut res, &sema, config, ast::Expr::from(it));
+                },
+                ast::MethodCallExpr(it) => {
+                    get_param_name_hints(&mut res, &sema, config, ast::Expr::from(it.clone()));
+                    get_adjustment_hints(&mut res, &sema, config, ast::Expr::from(it));
+                },
                 ast::BindPat(it) => { get_bind_pat_hints(&mut res, &sema, config, it); },
+                ast::FnDef(it) => { get_lifetime_hints(&mut res, &sema, config, it); },
+                ast::LambdaExpr(it) => {
+                    get_closure_capture_hints(&mut res, &sema, config, file_id, it.clone());
+                    get_closure_return_type_hints(&mut res, &sema, config, it);
+                },
                 _ => (),
             }
         }
@@ -179,6 +212,472 @@ fn get_bind_pat_hints(
     Some(())
 }
 
+fn get_lifetime_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    fn_def: ast::FnDef,
+) -> Option<()> {
+    if !config.lifetime_elision_hints {
+        return None;
+    }
+    let param_list = fn_def.param_list()?;
+
+    let mut hints = Vec::new();
+    let mut next_lifetime = 1;
+    let mut elided_lifetime = |hints: &mut Vec<InlayHint>, amp: &SyntaxToken| -> SmolStr {
+        let label: SmolStr = format!("'{}", next_lifetime).into();
+        next_lifetime += 1;
+        hints.push(InlayHint {
+            range: amp.text_range(),
+            kind: InlayKind::LifetimeHint,
+            label: label.clone(),
+        });
+        label
+    };
+
+    let mut inputs = Vec::new();
+    // The lifetime of `&self`, if it has a name we can show.
+    let mut self_lifetime = None;
+    if let Some(self_param) = param_list.self_param() {
+        let self_ref = match (self_param.amp_token(), self_param.ascribed_type()) {
+            (Some(amp), _) => Some((amp, self_param.lifetime_token())),
+            // `self: &Self` and `self: &mut Self` are the same as `&self`.
+            (None, Some(ast::TypeRef::ReferenceType(it))) if is_self_type(it.type_ref()) => {
+                it.amp_token().map(|amp| (amp, it.lifetime_token()))
+            }
+            (None, ty) => {
+                if let Some(ty) = ty {
+                    inputs.extend(lifetime_positions(sema, ty.syntax()));
+                }
+                None
+            }
+        };
+        if let Some((amp, lifetime)) = self_ref {
+            self_lifetime = Some(match lifetime {
+                Some(it) if it.text() == "'_" => None,
+                Some(it) => Some(it.text().clone()),
+                None => Some(elided_lifetime(&mut hints, &amp)),
+            });
+        }
+    }
+    for param in param_list.params() {
+        if let Some(ty) = param.ascribed_type() {
+            inputs.extend(lifetime_positions(sema, ty.syntax()));
+        }
+    }
+    let inputs: Vec<Option<SmolStr>> = inputs
+        .into_iter()
+        .map(|it| match it {
+            LifetimePosition::Elided(amp) => Some(elided_lifetime(&mut hints, &amp)),
+            LifetimePosition::Named(lifetime) => Some(lifetime),
+            LifetimePosition::Anonymous => None,
+        })
+        .collect();
+
+    // The elision rules: the lifetime of `&self` if there is one, otherwise
+    // the only lifetime of the parameters.
+    let output = match self_lifetime {
+        Some(it) => it,
+        None if inputs.len() == 1 => inputs[0].clone(),
+        None => None,
+    };
+    let ret_type = fn_def.ret_type().and_then(|it| it.type_ref());
+    let elided_outputs: Vec<SyntaxToken> = ret_type
+        .map(|it| lifetime_positions(sema, it.syntax()))
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|it| match it {
+            LifetimePosition::Elided(amp) => Some(amp),
+            _ => None,
+        })
+        .collect();
+    if !elided_outputs.is_empty() {
+        // Hints which don't say where the output lifetimes come from would be
+        // misleading, so there are none at all.
+        let output = output?;
+        for amp in elided_outputs {
+            hints.push(InlayHint {
+                range: amp.text_range(),
+                kind: InlayKind::LifetimeHint,
+                label: output.clone(),
+            });
+        }
+    }
+    acc.extend(hints);
+    Some(())
+}
+
+fn is_self_type(ty: Option<ast::TypeRef>) -> bool {
+    match ty {
+        Some(ast::TypeRef::PathType(it)) => it.syntax().text() == "Self",
+        _ => false,
+    }
+}
+
+enum LifetimePosition {
+    /// The `&` of a reference without a lifetime.
+    Elided(SyntaxToken),
+    Named(SmolStr),
+    /// A lifetime we can't name: `'_`, or one left out of a path, like in
+    /// `Foo` for `Foo<'_>`.
+    Anonymous,
+}
+
+/// The lifetimes in a type, in order. `fn` pointers and `Fn` traits have their
+/// own elision rules, so they are skipped.
+fn lifetime_positions(sema: &Semantics<RootDatabase>, node: &SyntaxNode) -> Vec<LifetimePosition> {
+    let mut res = Vec::new();
+    collect_lifetime_positions(sema, node, &mut res);
+    res
+}
+
+fn collect_lifetime_positions(
+    sema: &Semantics<RootDatabase>,
+    node: &SyntaxNode,
+    acc: &mut Vec<LifetimePosition>,
+) {
+    let named = |lifetime: SyntaxToken| {
+        if lifetime.text() == "'_" {
+            LifetimePosition::Anonymous
+        } else {
+            LifetimePosition::Named(lifetime.text().clone())
+        }
+    };
+    match_ast! {
+        match node {
+            ast::FnPointerType(_it) => return,
+            ast::PathSegment(it) => {
+                if it.param_list().is_some() {
+                    return;
+                }
+            },
+            ast::PathType(it) => {
+                for _ in 0..hidden_lifetimes(sema, &it) {
+                    acc.push(LifetimePosition::Anonymous);
+                }
+            },
+            ast::ReferenceType(it) => match (it.amp_token(), it.lifetime_token()) {
+                (_, Some(lifetime)) => acc.push(named(lifetime)),
+                (Some(amp), None) => acc.push(LifetimePosition::Elided(amp)),
+                (None, None) => (),
+            },
+            ast::LifetimeArg(it) => {
+                if let Some(lifetime) = it.lifetime_token() {
+                    acc.push(named(lifetime));
+                }
+            },
+            _ => (),
+        }
+    }
+    for child in node.children() {
+        collect_lifetime_positions(sema, &child, acc);
+    }
+}
+
+/// The number of lifetime parameters of the type `path_type` refers to, if
+/// they are all left out.
+fn hidden_lifetimes(sema: &Semantics<RootDatabase>, path_type: &ast::PathType) -> usize {
+    let path = match path_type.path() {
+        Some(it) => it,
+        None => return 0,
+    };
+    let args = path.segment().and_then(|it| it.type_arg_list());
+    if args.map_or(false, |it| it.lifetime_args().next().is_some()) {
+        return 0;
+    }
+    let db = sema.db;
+    let params = match sema.resolve_path(&path) {
+        Some(PathResolution::Def(ModuleDef::Adt(Adt::Struct(it)))) => {
+            it.source(db).value.type_param_list()
+        }
+        Some(PathResolution::Def(ModuleDef::Adt(Adt::Enum(it)))) => {
+            it.source(db).value.type_param_list()
+        }
+        Some(PathResolution::Def(ModuleDef::Adt(Adt::Union(it)))) => {
+            it.source(db).value.type_param_list()
+        }
+        Some(PathResolution::Def(ModuleDef::TypeAlias(it))) => {
+            it.source(db).value.type_param_list()
+        }
+        _ => None,
+    };
+    params.map_or(0, |it| it.lifetime_params().count())
+}
+
+fn get_closure_return_type_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    closure: ast::LambdaExpr,
+) -> Option<()> {
+    if !config.closure_return_type_hints || closure.ret_type().is_some() {
+        return None;
+    }
+    let param_list = closure.param_list()?;
+    let ty = sema.type_of_expr(&closure.body()?)?;
+    if ty.is_unknown() || ty.is_unit() {
+        return None;
+    }
+    acc.push(InlayHint {
+        range: param_list.syntax().text_range(),
+        kind: InlayKind::ClosureReturnTypeHint,
+        label: ty.display_truncated(sema.db, config.max_length).to_string().into(),
+    });
+    Some(())
+}
+
+/// Lists the variables a closure captures, and whether it captures them by
+/// value (`move` closures, or values which are moved out), by unique borrow
+/// (if they are mutated) or by shared borrow. If we can't tell for one of the
+/// variables, there's no hint at all.
+fn get_closure_capture_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    file_id: FileId,
+    closure: ast::LambdaExpr,
+) -> Option<()> {
+    if !config.closure_capture_hints {
+        return None;
+    }
+    let param_list = closure.param_list()?;
+    let closure_range = closure.syntax().text_range();
+    let is_move = closure.move_token().is_some();
+
+    let mut captures: Vec<(hir::Local, Capture)> = Vec::new();
+    for path_expr in closure.body()?.syntax().descendants().filter_map(ast::PathExpr::cast) {
+        let local = match path_expr.path().and_then(|it| sema.resolve_path(&it)) {
+            Some(PathResolution::Local(it)) => it,
+            _ => continue,
+        };
+        let src = local.source(sema.db);
+        let local_range =
+            src.value.either(|it| it.syntax().text_range(), |it| it.syntax().text_range());
+        if src.file_id == file_id.into() && closure_range.contains_range(local_range) {
+            continue;
+        }
+        let capture = if is_move { Capture::ByValue } else { capture_of_use(sema, &path_expr)? };
+        match captures.iter_mut().find(|(it, _)| *it == local) {
+            Some((_, it)) => *it = (*it).max(capture),
+            None => captures.push((local, capture)),
+        }
+    }
+    if captures.is_empty() {
+        return None;
+    }
+
+    let label = captures
+        .into_iter()
+        .filter_map(|(local, capture)| {
+            let name = local.name(sema.db)?;
+            Some(match capture {
+                Capture::ByValue => name.to_string(),
+                Capture::Unique => format!("&mut {}", name),
+                Capture::Shared => format!("&{}", name),
+            })
+        })
+        .join(", ");
+    acc.push(InlayHint {
+        range: param_list.syntax().text_range(),
+        kind: InlayKind::ClosureCaptureHint,
+        label: label.into(),
+    });
+    Some(())
+}
+
+/// How a closure captures a variable, from the weakest to the strongest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Capture {
+    Shared,
+    Unique,
+    ByValue,
+}
+
+/// How a use of a variable inside of a closure makes the closure capture it.
+/// `None` if we can't tell, for example because the type isn't known.
+fn capture_of_use(sema: &Semantics<RootDatabase>, expr: &ast::PathExpr) -> Option<Capture> {
+    if is_mutated(sema, expr) {
+        return Some(Capture::Unique);
+    }
+
+    // Moving a field out of a variable moves the whole variable.
+    let mut place = expr.syntax().clone();
+    while let Some(parent) = place.parent() {
+        let is_projection = match_ast! {
+            match parent {
+                ast::FieldExpr(it) => it.expr().map_or(false, |it| it.syntax() == &place),
+                ast::ParenExpr(_it) => true,
+                _ => false,
+            }
+        };
+        if !is_projection {
+            break;
+        }
+        place = parent;
+    }
+
+    enum Use {
+        Borrow,
+        Move,
+        /// Depends on the patterns the value is matched against.
+        Match,
+    }
+    let parent = place.parent()?;
+    let use_ = match_ast! {
+        match parent {
+            ast::RefExpr(_it) => Use::Borrow,
+            ast::IndexExpr(it) => {
+                if it.base().map_or(false, |it| it.syntax() == &place) {
+                    Use::Borrow
+                } else {
+                    Use::Move
+                }
+            },
+            ast::PrefixExpr(it) => match it.op_kind() {
+                Some(ast::PrefixOp::Deref) => Use::Borrow,
+                _ => Use::Move,
+            },
+            ast::BinExpr(it) => match it.op_kind() {
+                Some(ast::BinOp::EqualityTest)
+                | Some(ast::BinOp::NegatedEqualityTest)
+                | Some(ast::BinOp::LesserTest)
+                | Some(ast::BinOp::LesserEqualTest)
+                | Some(ast::BinOp::GreaterTest)
+                | Some(ast::BinOp::GreaterEqualTest) => Use::Borrow,
+                _ => Use::Move,
+            },
+            ast::MethodCallExpr(it) => {
+                match sema.resolve_method_call(&it)?.self_param_access(sema.db)? {
+                    hir::Access::Owned => Use::Move,
+                    hir::Access::Shared | hir::Access::Exclusive => Use::Borrow,
+                }
+            },
+            ast::MatchExpr(_it) => Use::Match,
+            ast::Condition(it) => if it.pat().is_some() { Use::Match } else { Use::Move },
+            _ => Use::Move,
+        }
+    };
+    if let Use::Borrow = use_ {
+        return Some(Capture::Shared);
+    }
+
+    let ty = sema.type_of_expr(&ast::Expr::cast(place)?)?;
+    if ty.is_unknown() {
+        return None;
+    }
+    // Shared references are `Copy`, and mutable ones are reborrowed.
+    if ty.as_reference().is_some() || ty.is_copy(sema.db) {
+        return Some(Capture::Shared);
+    }
+    match use_ {
+        Use::Move => Some(Capture::ByValue),
+        _ => None,
+    }
+}
+
+/// Whether the place `expr` refers to, or a field of it, is assigned to,
+/// mutably borrowed or the receiver of a method taking `&mut self`.
+fn is_mutated(sema: &Semantics<RootDatabase>, expr: &ast::PathExpr) -> bool {
+    let mut place = expr.syntax().clone();
+    while let Some(parent) = place.parent() {
+        let is_projection = match_ast! {
+            match parent {
+                ast::FieldExpr(it) => it.expr().map_or(false, |it| it.syntax() == &place),
+                ast::IndexExpr(it) => it.base().map_or(false, |it| it.syntax() == &place),
+                ast::ParenExpr(_it) => true,
+                _ => false,
+            }
+        };
+        if !is_projection {
+            break;
+        }
+        place = parent;
+    }
+
+    let parent = match place.parent() {
+        Some(it) => it,
+        None => return false,
+    };
+    match_ast! {
+        match parent {
+            ast::BinExpr(it) => {
+                it.op_kind().map_or(false, |op| op.is_assignment())
+                    && it.lhs().map_or(false, |lhs| lhs.syntax() == &place)
+            },
+            ast::RefExpr(it) => it.mut_token().is_some(),
+            ast::MethodCallExpr(it) => {
+                it.expr().map_or(false, |receiver| receiver.syntax() == &place)
+                    && sema.resolve_method_call(&it).map_or(false, |func| {
+                        func.has_self_param(sema.db)
+                            && func
+                                .signature_types(sema.db)
+                                .first()
+                                .map_or(false, |it| it.is_mutable_reference())
+                    })
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Shows the reborrows and deref coercions the compiler inserts when a
+/// reference is passed to a function, like `&*` for a `&mut T` passed as `&T`
+/// or `&**` for a `&String` passed as `&str`.
+fn get_adjustment_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    expr: ast::Expr,
+) -> Option<()> {
+    if !config.adjustment_hints {
+        return None;
+    }
+
+    let (function, arg_list, n_params_to_skip) = match &expr {
+        ast::Expr::CallExpr(expr) => {
+            let function = match sema.type_of_expr(&expr.expr()?)?.as_callable()? {
+                hir::CallableDef::FunctionId(it) => hir::Function::from(it),
+                _ => return None,
+            };
+            (function, expr.arg_list()?, 0)
+        }
+        ast::Expr::MethodCallExpr(expr) => (sema.resolve_method_call(&expr)?, expr.arg_list()?, 1),
+        _ => return None,
+    };
+
+    let mut param_types = function.signature_types(sema.db);
+    param_types.pop();
+    for (param_ty, arg) in param_types.iter().skip(n_params_to_skip).zip(arg_list.args()) {
+        if let Some(label) = adjustment_label(sema, &arg, param_ty) {
+            acc.push(InlayHint {
+                range: arg.syntax().text_range(),
+                kind: InlayKind::AdjustmentHint,
+                label: label.into(),
+            });
+        }
+    }
+    Some(())
+}
+
+fn adjustment_label(
+    sema: &Semantics<RootDatabase>,
+    arg: &ast::Expr,
+    expected: &Type,
+) -> Option<String> {
+    let (expected_pointee, expected_mutability) = expected.as_reference()?;
+    let (actual_pointee, actual_mutability) = sema.type_of_expr(arg)?.as_reference()?;
+    let derefs = actual_pointee.deref_steps_to(sema.db, &expected_pointee)?;
+    let borrow = match (expected_mutability, actual_mutability) {
+        // Shared references are copied.
+        (Mutability::Shared, Mutability::Shared) if derefs == 0 => return None,
+        (Mutability::Shared, _) => "&",
+        (Mutability::Mut, Mutability::Mut) => "&mut ",
+        (Mutability::Mut, Mutability::Shared) => return None,
+    };
+    Some(format!("{}{}", borrow, "*".repeat(derefs + 1)))
+}
+
 fn pat_is_enum_variant(db: &RootDatabase, bind_pat: &ast::BindPat, pat_ty: &Type) -> bool {
     if let Some(Adt::Enum(enum_data)) = pat_ty.as_adt() {
         let pat_text = bind_pat.syntax().to_string();
@@ -319,7 +818,7 @@ mod tests {
                 let _x = foo(4, 4);
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: true, type_hints: false, chaining_hints: false, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: true, type_hints: false, chaining_hints: false, max_length: None, ..Default::default()}).unwrap(), @r###"
         [
             InlayHint {
                 range: 106..107,
@@ -343,7 +842,7 @@ mod tests {
                 let _x = foo(4, 4);
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ type_hints: false, parameter_hints: false, chaining_hints: false, max_length: None}).unwrap(), @r###"[]"###);
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ type_hints: false, parameter_hints: false, chaining_hints: false, max_length: None, ..Default::default()}).unwrap(), @r###"[]"###);
     }
 
     #[test]
@@ -355,7 +854,7 @@ mod tests {
                 let _x = foo(4, 4);
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ type_hints: true, parameter_hints: false, chaining_hints: false, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ type_hints: true, parameter_hints: false, chaining_hints: false, max_length: None, ..Default::default()}).unwrap(), @r###"
         [
             InlayHint {
                 range: 97..99,
@@ -1181,7 +1680,7 @@ fn main() {
                     .into_c();
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None, ..Default::default()}).unwrap(), @r###"
         [
             InlayHint {
                 range: 232..269,
@@ -1210,7 +1709,7 @@ fn main() {
                 let c = A(B(C)).into_b().into_c();
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None}).unwrap(), @r###"[]"###);
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None, ..Default::default()}).unwrap(), @r###"[]"###);
     }
 
     #[test]
@@ -1235,7 +1734,7 @@ fn main() {
                     .foo();
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None, ..Default::default()}).unwrap(), @r###"
         [
             InlayHint {
                 range: 252..323,
@@ -1273,7 +1772,7 @@ fn main() {
                     .into_c();
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None, ..Default::default()}).unwrap(), @r###"
         [
             InlayHint {
                 range: 403..452,
@@ -1287,4 +1786,237 @@ fn main() {
             },
         ]"###);
     }
+
+    #[test]
+    fn lifetime_elision_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct S;
+impl S {
+    fn f(&self, a: &u32) -> &u32 { a }
+}
+fn g(a: &u32) -> &u32 { a }
+fn h(a: &u32, b: &u32) -> &u32 { a }
+fn k(f: fn(&u32) -> &u32) {}
+"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: false, lifetime_elision_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 29..30,
+                kind: LifetimeHint,
+                label: "'1",
+            },
+            InlayHint {
+                range: 39..40,
+                kind: LifetimeHint,
+                label: "'2",
+            },
+            InlayHint {
+                range: 48..49,
+                kind: LifetimeHint,
+                label: "'1",
+            },
+            InlayHint {
+                range: 69..70,
+                kind: LifetimeHint,
+                label: "'1",
+            },
+            InlayHint {
+                range: 78..79,
+                kind: LifetimeHint,
+                label: "'1",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn lifetime_elision_hints_are_omitted_when_ambiguous() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct Foo<'a>(&'a u32);
+struct S;
+impl S {
+    fn typed_self(self: &Self, a: &u32) -> &u32 { a }
+    fn anonymous_self(&'_ self) -> &u32 { &0 }
+}
+fn hidden(a: Foo, b: &u32) -> &u32 { b }
+fn anonymous(a: &'_ u32) -> &u32 { a }
+fn anonymous_arg(a: Foo<'_>) -> &u32 { a.0 }
+fn no_output(a: Foo, b: &u32) {}
+"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: false, lifetime_elision_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 69..70,
+                kind: LifetimeHint,
+                label: "'1",
+            },
+            InlayHint {
+                range: 79..80,
+                kind: LifetimeHint,
+                label: "'2",
+            },
+            InlayHint {
+                range: 88..89,
+                kind: LifetimeHint,
+                label: "'1",
+            },
+            InlayHint {
+                range: 297..298,
+                kind: LifetimeHint,
+                label: "'1",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn closure_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn main() {
+    let mut count = 0u32;
+    let items = 1u32;
+    let add = |x: u32| { count += x; items };
+    let by_move = move || items;
+}
+#[lang = "copy"]
+trait Copy {}
+impl Copy for u32 {}
+"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: false, closure_return_type_hints: true, closure_capture_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 75..83,
+                kind: ClosureCaptureHint,
+                label: "&mut count, &items",
+            },
+            InlayHint {
+                range: 75..83,
+                kind: ClosureReturnTypeHint,
+                label: "u32",
+            },
+            InlayHint {
+                range: 130..132,
+                kind: ClosureCaptureHint,
+                label: "items",
+            },
+            InlayHint {
+                range: 130..132,
+                kind: ClosureReturnTypeHint,
+                label: "u32",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn closure_capture_hints_by_value() {
+        let (analysis, file_id) = single_file(
+            r#"
+#[lang = "copy"]
+trait Copy {}
+impl Copy for u32 {}
+struct S { n: u32 }
+impl S {
+    fn consume(self) {}
+    fn peek(&self) -> u32 { self.n }
+}
+fn take<T>(_: T) {}
+fn main() {
+    let s = S { n: 0 };
+    let t = S { n: 1 };
+    let u = S { n: 2 };
+    let w = missing();
+    let moved = || take(s);
+    let consumed = || t.consume();
+    let borrowed = || u.peek() + u.n;
+    let compared = || u.n == 0 && &u.n == &2;
+    let unknown = || take(w);
+}
+"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: false, closure_capture_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 288..290,
+                kind: ClosureCaptureHint,
+                label: "s",
+            },
+            InlayHint {
+                range: 319..321,
+                kind: ClosureCaptureHint,
+                label: "t",
+            },
+            InlayHint {
+                range: 354..356,
+                kind: ClosureCaptureHint,
+                label: "&u",
+            },
+            InlayHint {
+                range: 392..394,
+                kind: ClosureCaptureHint,
+                label: "&u",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn adjustment_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct S;
+impl S {
+    fn by_ref(&self, s: &S) {}
+}
+fn takes_ref(x: &u32) {}
+fn takes_mut(x: &mut u32) {}
+fn main() {
+    let mut x = 0u32;
+    let r = &mut x;
+    takes_ref(r);
+    takes_mut(r);
+    let b = &&x;
+    takes_ref(b);
+    let mut s = S;
+    let m = &mut s;
+    S.by_ref(m);
+}
+"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: false, adjustment_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 175..176,
+                kind: AdjustmentHint,
+                label: "&*",
+            },
+            InlayHint {
+                range: 193..194,
+                kind: AdjustmentHint,
+                label: "&mut *",
+            },
+            InlayHint {
+                range: 228..229,
+                kind: AdjustmentHint,
+                label: "&**",
+            },
+            InlayHint {
+                range: 284..285,
+                kind: AdjustmentHint,
+                label: "&*",
+            },
+        ]
+        "###);
+    }
 }
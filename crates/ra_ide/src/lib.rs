@@ -22,10 +22,13 @@ mod prime_caches;
 mod status;
 mod completion;
 mod runnables;
+mod doc_tests;
 mod goto_definition;
+mod goto_declaration;
 mod goto_type_definition;
 mod extend_selection;
 mod hover;
+mod highlight_related;
 mod call_hierarchy;
 mod call_info;
 mod syntax_highlighting;
@@ -34,7 +37,11 @@ mod references;
 mod impls;
 mod assists;
 mod diagnostics;
+mod inactive_code;
+mod document_links;
 mod syntax_tree;
+mod view_hir;
+mod view_crate_graph;
 mod folding_ranges;
 mod join_lines;
 mod typing;
@@ -43,6 +50,8 @@ mod display;
 mod inlay_hints;
 mod expand_macro;
 mod ssr;
+mod type_hierarchy;
+mod test_explorer;
 
 #[cfg(test)]
 mod marks;
@@ -66,15 +75,16 @@ use crate::display::ToNav;
 
 pub use crate::{
     assists::{Assist, AssistId},
-    call_hierarchy::CallItem,
+    call_hierarchy::{CallHierarchyConfig, CallItem},
     completion::{
         CompletionConfig, CompletionItem, CompletionItemKind, CompletionScore, InsertTextFormat,
     },
     diagnostics::Severity,
     display::{file_structure, FunctionSignature, NavigationTarget, StructureNode},
-    expand_macro::ExpandedMacro,
+    document_links::{DocumentLink, DocumentLinkTarget},
+    expand_macro::{ExpandedFile, ExpandedMacro, TokenOrigin},
     folding_ranges::{Fold, FoldKind},
-    hover::HoverResult,
+    hover::{HoverAction, HoverGotoTypeData, HoverResult},
     inlay_hints::{InlayHint, InlayHintsConfig, InlayKind},
     references::{Declaration, Reference, ReferenceAccess, ReferenceKind, ReferenceSearchResult},
     runnables::{Runnable, RunnableKind, TestId},
@@ -83,6 +93,9 @@ pub use crate::{
     syntax_highlighting::{
         Highlight, HighlightModifier, HighlightModifiers, HighlightTag, HighlightedRange,
     },
+    test_explorer::{TestItem, TestItemKind},
+    type_hierarchy::TypeHierarchy,
+    view_crate_graph::CrateGraphConfig,
 };
 
 pub use hir::Documentation;
@@ -283,10 +296,30 @@ impl Analysis {
         self.with_db(|db| syntax_tree::syntax_tree(&db, file_id, text_range))
     }
 
+    /// Returns the lowered body of the function at `position`, annotated with
+    /// the inferred types, for debug purposes.
+    pub fn view_hir(&self, position: FilePosition) -> Cancelable<String> {
+        self.with_db(|db| view_hir::view_hir(db, position))
+    }
+
+    /// Returns the crate graph in the Graphviz DOT format, for debug purposes.
+    pub fn view_crate_graph(&self, config: &CrateGraphConfig) -> Cancelable<String> {
+        self.with_db(|db| view_crate_graph::view_crate_graph(db, config))
+    }
+
     pub fn expand_macro(&self, position: FilePosition) -> Cancelable<Option<ExpandedMacro>> {
         self.with_db(|db| expand_macro::expand_macro(db, position))
     }
 
+    /// Expands all macros in the file, or in the top-level item at `offset`.
+    pub fn expand_all_macros(
+        &self,
+        file_id: FileId,
+        offset: Option<TextSize>,
+    ) -> Cancelable<Option<ExpandedFile>> {
+        self.with_db(|db| expand_macro::expand_all_macros(db, file_id, offset))
+    }
+
     /// Returns an edit to remove all newlines in the range, cleaning up minor
     /// stuff like trailing commas.
     pub fn join_lines(&self, frange: FileRange) -> Cancelable<SourceChange> {
@@ -360,6 +393,15 @@ impl Analysis {
         self.with_db(|db| goto_definition::goto_definition(db, position))
     }
 
+    /// Returns the declarations from the symbol at `position`: the trait item
+    /// for an item of a trait impl, or the import for an imported name.
+    pub fn goto_declaration(
+        &self,
+        position: FilePosition,
+    ) -> Cancelable<Option<RangeInfo<Vec<NavigationTarget>>>> {
+        self.with_db(|db| goto_declaration::goto_declaration(db, position))
+    }
+
     /// Returns the impls from the symbol at `position`.
     pub fn goto_implementation(
         &self,
@@ -385,6 +427,12 @@ impl Analysis {
         self.with_db(|db| references::find_all_refs(db, position, search_scope).map(|it| it.info))
     }
 
+    /// Returns the exit points, loop exits or await points related to the
+    /// keyword at `position`.
+    pub fn highlight_related(&self, position: FilePosition) -> Cancelable<Option<Vec<TextRange>>> {
+        self.with_db(|db| highlight_related::highlight_related(db, position))
+    }
+
     /// Returns a short text describing element at position.
     pub fn hover(&self, position: FilePosition) -> Cancelable<Option<RangeInfo<HoverResult>>> {
         self.with_db(|db| hover::hover(db, position))
@@ -404,13 +452,30 @@ impl Analysis {
     }
 
     /// Computes incoming calls for the given file position.
-    pub fn incoming_calls(&self, position: FilePosition) -> Cancelable<Option<Vec<CallItem>>> {
-        self.with_db(|db| call_hierarchy::incoming_calls(db, position))
+    pub fn incoming_calls(
+        &self,
+        position: FilePosition,
+        config: &CallHierarchyConfig,
+    ) -> Cancelable<Option<Vec<CallItem>>> {
+        self.with_db(|db| call_hierarchy::incoming_calls(db, position, config))
     }
 
     /// Computes incoming calls for the given file position.
-    pub fn outgoing_calls(&self, position: FilePosition) -> Cancelable<Option<Vec<CallItem>>> {
-        self.with_db(|db| call_hierarchy::outgoing_calls(db, position))
+    pub fn outgoing_calls(
+        &self,
+        position: FilePosition,
+        config: &CallHierarchyConfig,
+    ) -> Cancelable<Option<Vec<CallItem>>> {
+        self.with_db(|db| call_hierarchy::outgoing_calls(db, position, config))
+    }
+
+    /// Computes the supertraits, subtraits and implementors of a trait, or the
+    /// traits implemented by a type.
+    pub fn type_hierarchy(
+        &self,
+        position: FilePosition,
+    ) -> Cancelable<Option<RangeInfo<TypeHierarchy>>> {
+        self.with_db(|db| type_hierarchy::type_hierarchy(db, position))
     }
 
     /// Returns a `mod name;` declaration which created the current module.
@@ -433,11 +498,23 @@ impl Analysis {
         self.with_db(|db| db.crate_graph()[crate_id].root_file_id)
     }
 
+    /// Returns ranges of the file which link to other files or URLs, like
+    /// `mod foo;` declarations.
+    pub fn document_links(&self, file_id: FileId) -> Cancelable<Vec<DocumentLink>> {
+        self.with_db(|db| document_links::document_links(db, file_id))
+    }
+
     /// Returns the set of possible targets to run for the current file.
     pub fn runnables(&self, file_id: FileId) -> Cancelable<Vec<Runnable>> {
         self.with_db(|db| runnables::runnables(db, file_id))
     }
 
+    /// Returns the tests of the whole workspace as a tree, or only the part of
+    /// it which is defined in `file_id`.
+    pub fn discover_tests(&self, file_id: Option<FileId>) -> Cancelable<Vec<TestItem>> {
+        self.with_db(|db| test_explorer::discover_tests(db, file_id))
+    }
+
     /// Computes syntax highlighting for the given file
     pub fn highlight(&self, file_id: FileId) -> Cancelable<Vec<HighlightedRange>> {
         self.with_db(|db| syntax_highlighting::highlight(db, file_id, None))
@@ -462,6 +539,18 @@ impl Analysis {
         self.with_db(|db| completion::completions(db, position, config).map(Into::into))
     }
 
+    /// Fills in the documentation and the detail of a completion item, if they
+    /// were left out of the completion list.
+    pub fn resolve_completion(&self, item: CompletionItem) -> Cancelable<CompletionItem> {
+        self.with_db(|db| completion::resolve_completion(db, item))
+    }
+
+    /// Returns the file which defines the completion item to resolve. The item
+    /// can be resolved as long as that file doesn't change.
+    pub fn completion_resolve_file(&self, item: &CompletionItem) -> Cancelable<Option<FileId>> {
+        self.with_db(|db| completion::completion_resolve_file(db, item))
+    }
+
     /// Computes assists (aka code actions aka intentions) for the given
     /// position.
     pub fn assists(&self, frange: FileRange) -> Cancelable<Vec<Assist>> {
@@ -473,6 +562,12 @@ impl Analysis {
         self.with_db(|db| diagnostics::diagnostics(db, file_id))
     }
 
+    /// Computes hints for the items, statements and expressions of the file
+    /// which are disabled by `#[cfg]` attributes.
+    pub fn inactive_code_diagnostics(&self, file_id: FileId) -> Cancelable<Vec<Diagnostic>> {
+        self.with_db(|db| inactive_code::inactive_code_diagnostics(db, file_id))
+    }
+
     /// Returns the edit required to rename reference at the position to the new
     /// name.
     pub fn rename(
@@ -483,6 +578,29 @@ impl Analysis {
         self.with_db(|db| references::rename(db, position, new_name))
     }
 
+    /// Computes the edits to the `mod` item and the paths which refer to the
+    /// module defined in `file_id`, when the file is renamed so that the
+    /// module becomes `new_name`.
+    pub fn will_rename_file(
+        &self,
+        file_id: FileId,
+        new_name: &str,
+    ) -> Cancelable<Option<SourceChange>> {
+        self.with_db(|db| references::will_rename_file(db, file_id, new_name))
+    }
+
+    /// Computes the edits to the `mod` items and the paths which refer to the
+    /// module defined in `file_id`, when the file is moved next to the
+    /// children of the module defined in `new_parent`, as `new_name`.
+    pub fn will_move_file(
+        &self,
+        file_id: FileId,
+        new_parent: FileId,
+        new_name: &str,
+    ) -> Cancelable<Option<SourceChange>> {
+        self.with_db(|db| references::will_move_file(db, file_id, new_parent, new_name))
+    }
+
     pub fn structural_search_replace(
         &self,
         query: &str,
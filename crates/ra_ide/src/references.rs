@@ -26,7 +26,7 @@ use ra_syntax::{
 
 use crate::{display::TryToNav, FilePosition, FileRange, NavigationTarget, RangeInfo};
 
-pub(crate) use self::rename::rename;
+pub(crate) use self::rename::{rename, will_move_file, will_rename_file};
 
 pub use ra_ide_db::search::{Reference, ReferenceAccess, ReferenceKind};
 
@@ -1,16 +1,19 @@
 //! FIXME: write short doc here
 
 use hir::{ModuleSource, Semantics};
-use ra_db::{RelativePath, RelativePathBuf, SourceDatabaseExt};
+use ra_db::{FileId, RelativePath, RelativePathBuf, SourceDatabaseExt};
 use ra_ide_db::RootDatabase;
 use ra_syntax::{
-    algo::find_node_at_offset, ast, lex_single_valid_syntax_kind, AstNode, SyntaxKind, SyntaxNode,
+    algo::find_node_at_offset,
+    ast::{self, AttrsOwner, ModuleItemOwner, NameOwner},
+    lex_single_valid_syntax_kind, AstNode, SyntaxKind, SyntaxNode,
 };
 use ra_text_edit::TextEdit;
+use rustc_hash::FxHashSet;
 
 use crate::{
     FilePosition, FileSystemEdit, RangeInfo, Reference, ReferenceKind, SourceChange,
-    SourceFileEdit, TextRange,
+    SourceFileEdit, TextRange, TextSize,
 };
 
 use super::find_all_refs;
@@ -107,21 +110,278 @@ fn rename_mod(
         }
     }
 
-    let edit = SourceFileEdit {
+    source_file_edits.extend(rename_mod_references(sema.db, ast_name, position, new_name));
+
+    Some(SourceChange::from_edits("rename", source_file_edits, file_system_edits))
+}
+
+/// Renames the `mod` item with the given name, and all paths which refer to
+/// the module.
+fn rename_mod_references(
+    db: &RootDatabase,
+    ast_name: &ast::Name,
+    position: FilePosition,
+    new_name: &str,
+) -> Vec<SourceFileEdit> {
+    let mut res = vec![SourceFileEdit {
         file_id: position.file_id,
         edit: TextEdit::replace(ast_name.syntax().text_range(), new_name.into()),
-    };
-    source_file_edits.push(edit);
-
-    if let Some(RangeInfo { range: _, info: refs }) = find_all_refs(sema.db, position, None) {
+    }];
+    if let Some(RangeInfo { range: _, info: refs }) = find_all_refs(db, position, None) {
         let ref_edits = refs
             .references
             .into_iter()
             .map(|reference| source_edit_from_reference(reference, new_name));
-        source_file_edits.extend(ref_edits);
+        res.extend(ref_edits);
     }
+    res
+}
 
-    Some(SourceChange::from_edits("rename", source_file_edits, file_system_edits))
+/// Computes the edits needed when the client renames the file of a module,
+/// such that the module is now called `new_name`. Unlike `rename`, this
+/// doesn't move the file, as the client does that.
+pub(crate) fn will_rename_file(
+    db: &RootDatabase,
+    file_id: FileId,
+    new_name: &str,
+) -> Option<SourceChange> {
+    match lex_single_valid_syntax_kind(new_name)? {
+        SyntaxKind::IDENT => (),
+        _ => return None,
+    }
+
+    let sema = Semantics::new(db);
+    let module = sema.to_module_def(file_id)?;
+    let (decl_file, _, ast_name) = file_module_declaration(db, module)?;
+    if ast_name.text() == new_name {
+        return None;
+    }
+
+    let position =
+        FilePosition { file_id: decl_file, offset: ast_name.syntax().text_range().start() };
+    let edits = rename_mod_references(db, &ast_name, position, new_name);
+    Some(SourceChange::source_file_edits("rename", edits))
+}
+
+/// Computes the edits needed when the client moves the file of a module into
+/// the directory of the module defined in `new_parent`, such that the module
+/// is now called `new_name`. The `mod` item moves from the old parent to the
+/// new one, and paths which refer to the module are rewritten.
+pub(crate) fn will_move_file(
+    db: &RootDatabase,
+    file_id: FileId,
+    new_parent: FileId,
+    new_name: &str,
+) -> Option<SourceChange> {
+    match lex_single_valid_syntax_kind(new_name)? {
+        SyntaxKind::IDENT => (),
+        _ => return None,
+    }
+
+    let sema = Semantics::new(db);
+    let module = sema.to_module_def(file_id)?;
+    let new_parent_module = sema.to_module_def(new_parent)?;
+    if module.parent(db) == Some(new_parent_module) {
+        return will_rename_file(db, file_id, new_name);
+    }
+    if module.krate() != new_parent_module.krate() || new_parent_module == module {
+        return None;
+    }
+    let (decl_file, decl, ast_name) = file_module_declaration(db, module)?;
+
+    let mut edits = Vec::new();
+
+    let mut delete_range = decl.syntax().text_range();
+    if let Some(ws) =
+        decl.syntax().next_sibling_or_token().filter(|it| it.kind() == SyntaxKind::WHITESPACE)
+    {
+        delete_range = delete_range.cover(ws.text_range());
+    }
+    edits.push(SourceFileEdit { file_id: decl_file, edit: TextEdit::delete(delete_range) });
+
+    // Keep the visibility and attributes of the declaration.
+    let decl_text = decl.syntax().to_string();
+    let name_range = ast_name.syntax().text_range() - decl.syntax().text_range().start();
+    let new_decl = format!(
+        "{}{}{}",
+        &decl_text[TextRange::up_to(name_range.start())],
+        new_name,
+        &decl_text[TextRange::new(name_range.end(), decl.syntax().text_range().len())]
+    );
+    let new_parent_file = sema.parse(new_parent);
+    let last_mod = new_parent_file
+        .items()
+        .filter_map(|item| match item {
+            ast::ModuleItem::Module(it) => Some(it),
+            _ => None,
+        })
+        .last();
+    let insert = match last_mod {
+        Some(it) => TextEdit::insert(it.syntax().text_range().end(), format!("\n{}", new_decl)),
+        None => {
+            let text = db.file_text(new_parent);
+            let sep = if text.is_empty() || text.ends_with('\n') { "" } else { "\n" };
+            TextEdit::insert(TextSize::of(&*text), format!("{}{}\n", sep, new_decl))
+        }
+    };
+    edits.push(SourceFileEdit { file_id: new_parent, edit: insert });
+
+    let new_path = format!("{}::{}", absolute_path(db, new_parent_module)?, new_name);
+
+    let position =
+        FilePosition { file_id: decl_file, offset: ast_name.syntax().text_range().start() };
+    if let Some(RangeInfo { range: _, info: refs }) = find_all_refs(db, position, None) {
+        let ref_edits = refs.references.into_iter().map(|reference| {
+            moved_path_edit(&sema, &reference, &new_path)
+                .unwrap_or_else(|| source_edit_from_reference(reference, new_name))
+        });
+        edits.extend(ref_edits);
+    }
+
+    // Edits to references may already cover a `super` path, e.g. `super::foo`
+    // naming the moved module from within.
+    let covered = |super_edit: &SourceFileEdit| {
+        let range = super_edit.edit.as_atoms()[0].delete;
+        edits
+            .iter()
+            .filter(|edit| edit.file_id == super_edit.file_id)
+            .any(|edit| edit.edit.as_atoms().iter().any(|atom| atom.delete.contains_range(range)))
+    };
+    let super_edits =
+        super_path_edits(&sema, module).into_iter().filter(|it| !covered(it)).collect::<Vec<_>>();
+    edits.extend(super_edits);
+    Some(SourceChange::source_file_edits("move", edits))
+}
+
+/// The path of `module` starting with `crate`.
+fn absolute_path(db: &RootDatabase, module: hir::Module) -> Option<String> {
+    let mut path = module
+        .path_to_root(db)
+        .into_iter()
+        .rev()
+        .skip(1)
+        .map(|it| it.name(db).map(|name| name.to_string()))
+        .collect::<Option<Vec<_>>>()?;
+    path.insert(0, "crate".to_string());
+    Some(path.join("::"))
+}
+
+/// Makes the `super` prefixes absolute in paths within `module` and its
+/// children which lead out of `module`, as the module is about to move to
+/// another parent. `self` paths stay valid, so they are left alone.
+fn super_path_edits(sema: &Semantics<RootDatabase>, module: hir::Module) -> Vec<SourceFileEdit> {
+    let db = sema.db;
+    let mut files = FxHashSet::default();
+    let mut stack = vec![module];
+    while let Some(it) = stack.pop() {
+        files.insert(it.definition_file(db));
+        stack.extend(it.children(db));
+    }
+
+    let mut res = Vec::new();
+    for file_id in files {
+        let source_file = sema.parse(file_id);
+        for path in source_file.syntax().descendants().filter_map(ast::Path::cast) {
+            if path.qualifier().is_some() || !is_super(&path) {
+                continue;
+            }
+            let mut prefix = path.clone();
+            let mut depth = 1;
+            while let Some(parent) = prefix.syntax().parent().and_then(ast::Path::cast) {
+                if !is_super(&parent) {
+                    break;
+                }
+                prefix = parent;
+                depth += 1;
+            }
+            let outermost = prefix
+                .syntax()
+                .ancestors()
+                .take_while(|it| ast::Path::can_cast(it.kind()))
+                .last()
+                .and_then(|it| it.parent());
+            // Paths nested in a use tree can't start with `crate`.
+            if outermost
+                .and_then(ast::UseTree::cast)
+                .and_then(|it| it.syntax().parent())
+                .and_then(ast::UseTreeList::cast)
+                .is_some()
+            {
+                continue;
+            }
+            let target = match sema.scope(path.syntax()).module() {
+                Some(it) => it.path_to_root(db).into_iter().nth(depth),
+                None => None,
+            };
+            let target = match target {
+                Some(it) => it,
+                None => continue,
+            };
+            if target.path_to_root(db).contains(&module) {
+                continue;
+            }
+            if let Some(target_path) = absolute_path(db, target) {
+                res.push(SourceFileEdit {
+                    file_id,
+                    edit: TextEdit::replace(prefix.syntax().text_range(), target_path),
+                });
+            }
+        }
+    }
+    res
+}
+
+fn is_super(path: &ast::Path) -> bool {
+    path.segment().and_then(|it| it.kind()) == Some(ast::PathSegmentKind::SuperKw)
+}
+
+/// Finds the `mod` item declaring a module which lives in its own file, unless
+/// the name of the file isn't derived from the name of the module.
+fn file_module_declaration(
+    db: &RootDatabase,
+    module: hir::Module,
+) -> Option<(FileId, ast::Module, ast::Name)> {
+    let decl = module.declaration_source(db)?;
+    let decl_file = decl.file_id.original_file(db);
+    // Modules declared by macros or with an explicit `#[path]` don't follow
+    // the file name.
+    if decl.file_id != decl_file.into()
+        || decl.value.attrs().any(|it| it.simple_name().as_deref() == Some("path"))
+    {
+        return None;
+    }
+    let ast_name = decl.value.name()?;
+    Some((decl_file, decl.value, ast_name))
+}
+
+/// Replaces the path which ends in the reference to a moved module by the
+/// absolute path of its new location. Paths nested in a use tree can't start
+/// with `crate`, so those are left to a plain rename.
+fn moved_path_edit(
+    sema: &Semantics<RootDatabase>,
+    reference: &Reference,
+    new_path: &str,
+) -> Option<SourceFileEdit> {
+    let file_id = reference.file_range.file_id;
+    let source_file = sema.parse(file_id);
+    let name_ref = find_node_at_offset::<ast::NameRef>(
+        source_file.syntax(),
+        reference.file_range.range.start(),
+    )?;
+    let path = name_ref.syntax().ancestors().find_map(ast::Path::cast)?;
+    if path.segment()?.name_ref()? != name_ref {
+        return None;
+    }
+    let outermost =
+        path.syntax().ancestors().take_while(|it| ast::Path::can_cast(it.kind())).last()?;
+    let use_tree = outermost.parent().and_then(ast::UseTree::cast);
+    if use_tree.and_then(|it| it.syntax().parent()).and_then(ast::UseTreeList::cast).is_some() {
+        return None;
+    }
+    Some(SourceFileEdit {
+        file_id,
+        edit: TextEdit::replace(path.syntax().text_range(), new_path.to_string()),
+    })
 }
 
 fn rename_reference(
@@ -150,7 +410,8 @@ mod tests {
     use test_utils::assert_eq_text;
 
     use crate::{
-        mock_analysis::analysis_and_position, mock_analysis::single_file_with_position, FileId,
+        mock_analysis::analysis_and_position, mock_analysis::single_file_with_position,
+        mock_analysis::MockAnalysis, FileId,
     };
 
     #[test]
@@ -709,6 +970,197 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_will_rename_file() {
+        let mock = MockAnalysis::with_files(
+            r#"
+            //- /lib.rs
+            mod foo;
+            use foo::Bar;
+            fn f() -> crate::foo::Bar { foo::baz::quux() }
+            //- /foo.rs
+            pub struct Bar;
+            pub mod baz;
+            //- /foo/baz.rs
+            pub fn quux() {}
+            "#,
+        );
+        let analysis = mock.analysis();
+        let lib = mock.id_of("/lib.rs");
+        let source_change =
+            analysis.will_rename_file(mock.id_of("/foo.rs"), "bar").unwrap().unwrap();
+        assert!(source_change.file_system_edits.is_empty());
+
+        let mut text_edit_builder = TextEditBuilder::default();
+        for edit in source_change.source_file_edits {
+            assert_eq!(edit.file_id, lib);
+            for atom in edit.edit.as_atoms() {
+                text_edit_builder.replace(atom.delete, atom.insert.clone());
+            }
+        }
+        let result = text_edit_builder.finish().apply(&*analysis.file_text(lib).unwrap());
+        assert_eq_text!(
+            "mod bar;\nuse bar::Bar;\nfn f() -> crate::bar::Bar { bar::baz::quux() }",
+            result.trim_end()
+        );
+    }
+
+    #[test]
+    fn test_will_rename_file_keeping_module_name() {
+        let mock = MockAnalysis::with_files(
+            r#"
+            //- /lib.rs
+            mod foo;
+            //- /foo.rs
+            // empty
+            "#,
+        );
+        let analysis = mock.analysis();
+        assert!(analysis.will_rename_file(mock.id_of("/foo.rs"), "foo").unwrap().is_none());
+        assert!(analysis
+            .will_rename_file(mock.id_of("/foo.rs"), "not-an-ident")
+            .unwrap()
+            .is_none());
+        assert!(analysis.will_rename_file(mock.id_of("/lib.rs"), "main").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_will_move_file() {
+        check_will_move_file(
+            r#"
+            //- /lib.rs
+            mod a;
+            mod b;
+            use a::foo::Bar;
+            fn f() -> crate::a::foo::Bar { a::foo::baz() }
+            //- /a.rs
+            pub mod foo;
+            pub struct A;
+            //- /a/foo.rs
+            pub struct Bar;
+            pub fn baz() {}
+            //- /b.rs
+            pub mod quux;
+            //- /b/quux.rs
+            // empty
+            "#,
+            ("/a/foo.rs", "/b.rs", "bar"),
+            &[
+                (
+                    "/lib.rs",
+                    "mod a;\nmod b;\nuse crate::b::bar::Bar;\n\
+                     fn f() -> crate::b::bar::Bar { crate::b::bar::baz() }",
+                ),
+                ("/a.rs", "pub struct A;"),
+                ("/b.rs", "pub mod quux;\npub mod bar;"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_will_move_file_with_children() {
+        check_will_move_file(
+            r#"
+            //- /lib.rs
+            mod b;
+            #[allow(unused)]
+            pub(crate) mod foo;
+            fn f() { foo::baz::quux() }
+            //- /foo.rs
+            pub mod baz;
+            //- /foo/baz.rs
+            pub fn quux() {}
+            //- /b/mod.rs
+            fn g() {}
+            "#,
+            ("/foo.rs", "/b/mod.rs", "foo"),
+            &[
+                ("/lib.rs", "mod b;\nfn f() { crate::b::foo::baz::quux() }"),
+                ("/b/mod.rs", "fn g() {}\n#[allow(unused)]\npub(crate) mod foo;"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_will_move_file_within_parent() {
+        check_will_move_file(
+            r#"
+            //- /lib.rs
+            mod foo;
+            use foo::Bar;
+            //- /foo.rs
+            pub struct Bar;
+            "#,
+            ("/foo.rs", "/lib.rs", "bar"),
+            &[("/lib.rs", "mod bar;\nuse bar::Bar;")],
+        );
+    }
+
+    #[test]
+    fn test_will_move_file_with_super_paths() {
+        check_will_move_file(
+            r#"
+            //- /lib.rs
+            mod a;
+            mod b;
+            pub struct S;
+            //- /a.rs
+            pub mod foo;
+            pub struct A;
+            //- /a/foo.rs
+            use super::A;
+            use super::super::S;
+            use self::inner::X;
+            fn f() -> super::foo::Y { Y }
+            mod inner { pub struct X; use super::super::A; use super::Y; }
+            pub struct Y;
+            //- /b.rs
+            // empty
+            "#,
+            ("/a/foo.rs", "/b.rs", "foo"),
+            &[
+                ("/a.rs", "pub struct A;"),
+                ("/b.rs", "// empty\npub mod foo;"),
+                (
+                    "/a/foo.rs",
+                    "use crate::a::A;\nuse crate::S;\nuse self::inner::X;\n\
+                     fn f() -> crate::b::foo::Y { Y }\n\
+                     mod inner { pub struct X; use crate::a::A; use super::Y; }\n\
+                     pub struct Y;",
+                ),
+            ],
+        );
+    }
+
+    fn check_will_move_file(
+        fixture: &str,
+        (file, new_parent, new_name): (&str, &str, &str),
+        expected: &[(&str, &str)],
+    ) {
+        let mock = MockAnalysis::with_files(fixture);
+        let file_id = mock.id_of(file);
+        let new_parent = mock.id_of(new_parent);
+        let expected: Vec<(FileId, &str)> =
+            expected.iter().map(|&(path, text)| (mock.id_of(path), text)).collect();
+        let analysis = mock.analysis();
+        let source_change =
+            analysis.will_move_file(file_id, new_parent, new_name).unwrap().unwrap();
+        assert!(source_change.file_system_edits.is_empty());
+        for edit in &source_change.source_file_edits {
+            assert!(expected.iter().any(|(file_id, _)| *file_id == edit.file_id));
+        }
+        for (file_id, expected_text) in expected {
+            let mut text_edit_builder = TextEditBuilder::default();
+            for edit in source_change.source_file_edits.iter().filter(|it| it.file_id == file_id) {
+                for atom in edit.edit.as_atoms() {
+                    text_edit_builder.replace(atom.delete, atom.insert.clone());
+                }
+            }
+            let result = text_edit_builder.finish().apply(&*analysis.file_text(file_id).unwrap());
+            assert_eq_text!(expected_text, result.trim());
+        }
+    }
+
     fn test_rename(text: &str, new_name: &str, expected: &str) {
         let (analysis, position) = single_file_with_position(text);
         let source_change = analysis.rename(position, new_name).unwrap();
@@ -1,23 +1,26 @@
 //! FIXME: write short doc here
 
-use hir::Semantics;
+use hir::{HasSource, Semantics};
 use itertools::Itertools;
 use ra_ide_db::RootDatabase;
 use ra_syntax::{
-    ast::{self, AstNode, AttrsOwner, ModuleItemOwner, NameOwner},
+    ast::{self, AstNode, AttrsOwner, DocCommentsOwner, ModuleItemOwner, NameOwner},
     match_ast, SyntaxNode, TextRange,
 };
 
-use crate::FileId;
+use crate::{
+    doc_tests::{is_rust_code_block, RUSTDOC_FENCE},
+    FileId,
+};
 use std::fmt::Display;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Runnable {
     pub range: TextRange,
     pub kind: RunnableKind,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum TestId {
     Name(String),
     Path(String),
@@ -32,18 +35,33 @@ impl Display for TestId {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum RunnableKind {
-    Test { test_id: TestId, attr: TestAttr },
-    TestMod { path: String },
-    Bench { test_id: TestId },
+    Test {
+        test_id: TestId,
+        attr: TestAttr,
+    },
+    TestMod {
+        path: String,
+    },
+    Bench {
+        test_id: TestId,
+    },
+    /// The code blocks in the doc comment of an item.
+    DocTest {
+        test_id: TestId,
+    },
     Bin,
 }
 
 pub(crate) fn runnables(db: &RootDatabase, file_id: FileId) -> Vec<Runnable> {
     let sema = Semantics::new(db);
     let source_file = sema.parse(file_id);
-    source_file.syntax().descendants().filter_map(|i| runnable(&sema, i)).collect()
+    source_file
+        .syntax()
+        .descendants()
+        .flat_map(|i| runnable(&sema, i.clone()).into_iter().chain(runnable_doctest(&sema, i)))
+        .collect()
 }
 
 fn runnable(sema: &Semantics<RootDatabase>, item: SyntaxNode) -> Option<Runnable> {
@@ -57,17 +75,40 @@ fn runnable(sema: &Semantics<RootDatabase>, item: SyntaxNode) -> Option<Runnable
 }
 
 fn runnable_fn(sema: &Semantics<RootDatabase>, fn_def: ast::FnDef) -> Option<Runnable> {
+    let module = sema.to_def(&fn_def).map(|def| def.module(sema.db));
+    runnable_fn_in_module(sema.db, module, fn_def)
+}
+
+/// Returns the runnable for `function`, if it is a test, a benchmark or `main`
+/// defined in `file_id`.
+pub(crate) fn runnable_for_function(
+    db: &RootDatabase,
+    function: hir::Function,
+    file_id: FileId,
+) -> Option<Runnable> {
+    let src = function.source(db);
+    if src.file_id != file_id.into() {
+        return None;
+    }
+    runnable_fn_in_module(db, Some(function.module(db)), src.value)
+}
+
+fn runnable_fn_in_module(
+    db: &RootDatabase,
+    module: Option<hir::Module>,
+    fn_def: ast::FnDef,
+) -> Option<Runnable> {
     let name_string = fn_def.name()?.text().to_string();
 
     let kind = if name_string == "main" {
         RunnableKind::Bin
     } else {
-        let test_id = if let Some(module) = sema.to_def(&fn_def).map(|def| def.module(sema.db)) {
+        let test_id = if let Some(module) = module {
             let path = module
-                .path_to_root(sema.db)
+                .path_to_root(db)
                 .into_iter()
                 .rev()
-                .filter_map(|it| it.name(sema.db))
+                .filter_map(|it| it.name(db))
                 .map(|name| name.to_string())
                 .chain(std::iter::once(name_string))
                 .join("::");
@@ -88,7 +129,70 @@ fn runnable_fn(sema: &Semantics<RootDatabase>, fn_def: ast::FnDef) -> Option<Run
     Some(Runnable { range: fn_def.syntax().text_range(), kind })
 }
 
-#[derive(Debug)]
+fn runnable_doctest(sema: &Semantics<RootDatabase>, item: SyntaxNode) -> Option<Runnable> {
+    let (docs, name) = match_ast! {
+        match item {
+            ast::FnDef(it) => (it.doc_comment_text(), it.name()),
+            ast::StructDef(it) => (it.doc_comment_text(), it.name()),
+            ast::UnionDef(it) => (it.doc_comment_text(), it.name()),
+            ast::EnumDef(it) => (it.doc_comment_text(), it.name()),
+            ast::TraitDef(it) => (it.doc_comment_text(), it.name()),
+            ast::Module(it) => (it.doc_comment_text(), it.name()),
+            ast::ConstDef(it) => (it.doc_comment_text(), it.name()),
+            ast::StaticDef(it) => (it.doc_comment_text(), it.name()),
+            ast::TypeAliasDef(it) => (it.doc_comment_text(), it.name()),
+            ast::MacroCall(it) => (it.doc_comment_text(), it.name()),
+            _ => return None,
+        }
+    };
+    if !has_runnable_doc_test(&docs?) {
+        return None;
+    }
+
+    // rustdoc names doctests after the path of the item, with the self type
+    // or trait for associated items, but without the crate name.
+    let mut path: Vec<String> = sema
+        .scope(&item)
+        .module()?
+        .path_to_root(sema.db)
+        .into_iter()
+        .rev()
+        .filter_map(|it| it.name(sema.db))
+        .map(|name| name.to_string())
+        .collect();
+    let container = item.ancestors().skip(1).find_map(|it| {
+        match_ast! {
+            match it {
+                ast::ImplDef(it) => Some(it.target_type()?.syntax().text().to_string()),
+                ast::TraitDef(it) => Some(it.name()?.text().to_string()),
+                _ => None,
+            }
+        }
+    });
+    path.extend(container);
+    path.push(name?.text().to_string());
+
+    let test_id = TestId::Path(path.join("::"));
+    Some(Runnable { range: item.text_range(), kind: RunnableKind::DocTest { test_id } })
+}
+
+/// Whether `docs` contain a code block which rustdoc compiles as a test.
+fn has_runnable_doc_test(docs: &str) -> bool {
+    let mut in_code_block = false;
+    for line in docs.lines() {
+        let line = line.trim_start();
+        if !line.starts_with(RUSTDOC_FENCE) {
+            continue;
+        }
+        if !in_code_block && is_rust_code_block(&line[RUSTDOC_FENCE.len()..]) {
+            return true;
+        }
+        in_code_block = !in_code_block;
+    }
+    false
+}
+
+#[derive(Debug, Clone)]
 pub struct TestAttr {
     pub ignore: bool,
 }
@@ -194,6 +298,62 @@ mod tests {
                 );
     }
 
+    #[test]
+    fn test_runnables_doc_test() {
+        let (analysis, pos) = analysis_and_position(
+            r#"
+        //- /lib.rs
+        <|> //empty
+        fn main() {}
+
+        /// ```
+        /// let x = 5;
+        /// ```
+        fn foo() {}
+
+        /// ```ignore
+        /// let x = 5;
+        /// ```
+        fn bar() {}
+
+        struct Data;
+        impl Data {
+            /// ```
+            /// let x = 5;
+            /// ```
+            fn baz() {}
+        }
+        "#,
+        );
+        let runnables = analysis.runnables(pos.file_id).unwrap();
+        assert_debug_snapshot!(&runnables,
+        @r###"
+        [
+            Runnable {
+                range: 1..21,
+                kind: Bin,
+            },
+            Runnable {
+                range: 23..65,
+                kind: DocTest {
+                    test_id: Path(
+                        "foo",
+                    ),
+                },
+            },
+            Runnable {
+                range: 146..200,
+                kind: DocTest {
+                    test_id: Path(
+                        "Data::baz",
+                    ),
+                },
+            },
+        ]
+        "###
+                );
+    }
+
     #[test]
     fn test_runnables_module() {
         let (analysis, pos) = analysis_and_position(
@@ -6,6 +6,7 @@ mod html;
 mod tests;
 
 use hir::{Name, Semantics};
+use ra_db::SourceDatabaseExt;
 use ra_ide_db::{
     defs::{classify_name, classify_name_ref, Definition, NameClass, NameRefClass},
     RootDatabase,
@@ -15,11 +16,11 @@ use ra_syntax::{
     ast::{self, HasFormatSpecifier, HasQuotes, HasStringValue},
     AstNode, AstToken, Direction, NodeOrToken, SyntaxElement,
     SyntaxKind::*,
-    SyntaxToken, TextRange, WalkEvent, T,
+    SyntaxNode, SyntaxToken, TextRange, WalkEvent, T,
 };
 use rustc_hash::FxHashMap;
 
-use crate::{call_info::ActiveParameter, Analysis, FileId};
+use crate::{call_info::ActiveParameter, doc_tests, inactive_code, Analysis, FileId};
 
 use ast::FormatSpecifier;
 pub(crate) use html::highlight_as_html;
@@ -137,6 +138,10 @@ pub(crate) fn highlight(
 
     let mut current_macro_call: Option<ast::MacroCall> = None;
     let mut format_string: Option<SyntaxElement> = None;
+    let cfg_options = inactive_code::cfg_options(db, file_id);
+    let mut current_inactive_node: Option<SyntaxNode> = None;
+    let mut doc_test_highlights: FxHashMap<SyntaxNode, Vec<HighlightedRange>> =
+        FxHashMap::default();
 
     // Walk all nodes, keeping track of whether we are inside a macro or not.
     // If in macro, expand it first and highlight the expanded code.
@@ -156,6 +161,30 @@ pub(crate) fn highlight(
             continue;
         }
 
+        // Code disabled by `#[cfg]` is highlighted as a whole, without looking inside.
+        match (&event, &current_inactive_node) {
+            (WalkEvent::Leave(NodeOrToken::Node(node)), Some(inactive)) if node == inactive => {
+                current_inactive_node = None;
+                continue;
+            }
+            (_, Some(_)) => continue,
+            (WalkEvent::Enter(NodeOrToken::Node(node)), None) => {
+                let is_inactive = cfg_options
+                    .as_ref()
+                    .map_or(false, |it| inactive_code::disabling_cfg(node, it).is_some());
+                if is_inactive {
+                    stack.add(HighlightedRange {
+                        range: node.text_range(),
+                        highlight: HighlightTag::Inactive.into(),
+                        binding_hash: None,
+                    });
+                    current_inactive_node = Some(node.clone());
+                    continue;
+                }
+            }
+            _ => (),
+        }
+
         // Track "inside macro" state
         match event.clone().map(|it| it.into_node().and_then(ast::MacroCall::cast)) {
             WalkEvent::Enter(Some(mc)) => {
@@ -271,6 +300,12 @@ pub(crate) fn highlight(
                     });
                 }
                 stack.pop();
+            } else if let Some(comment) =
+                element_to_highlight.as_token().cloned().and_then(ast::Comment::cast)
+            {
+                stack.push();
+                highlight_doc_test(&mut stack, &mut doc_test_highlights, &comment);
+                stack.pop();
             }
         }
     }
@@ -278,6 +313,41 @@ pub(crate) fn highlight(
     stack.flattened()
 }
 
+/// Highlights the code of the doctests within the doc comment `comment`. All
+/// doctests of an item are highlighted at once, the first time we see one of
+/// its doc comments.
+fn highlight_doc_test(
+    acc: &mut HighlightedRangeStack,
+    cache: &mut FxHashMap<SyntaxNode, Vec<HighlightedRange>>,
+    comment: &ast::Comment,
+) {
+    if comment.kind().doc.is_none() {
+        return;
+    }
+    let owner = comment.syntax().parent();
+    let highlights = cache.entry(owner.clone()).or_insert_with(|| {
+        let code = match doc_tests::doc_test_code(&owner) {
+            Some(it) => it,
+            None => return Vec::new(),
+        };
+        let (analysis, tmp_file_id) = Analysis::from_single_file(code.text.clone());
+        analysis
+            .highlight(tmp_file_id)
+            .unwrap()
+            .into_iter()
+            .filter_map(|mut h| {
+                h.range = code.map_range_up(h.range)?;
+                h.highlight |= HighlightModifier::Documentation;
+                Some(h)
+            })
+            .collect()
+    });
+    let range = comment.syntax().text_range();
+    for h in highlights.iter().filter(|h| range.contains_range(h.range)) {
+        acc.add(h.clone());
+    }
+}
+
 fn highlight_format_specifier(kind: FormatSpecifier) -> Option<HighlightTag> {
     Some(match kind {
         FormatSpecifier::Open
@@ -319,6 +389,7 @@ fn highlight_element(
 ) -> Option<(Highlight, Option<u64>)> {
     let db = sema.db;
     let mut binding_hash = None;
+    let in_attribute = element.kind() != ATTR && element.ancestors().any(|it| it.kind() == ATTR);
     let highlight: Highlight = match element.kind() {
         FN_DEF => {
             bindings_shadow_count.clear();
@@ -361,7 +432,7 @@ fn highlight_element(
                                 binding_hash = Some(calc_binding_hash(&name, *shadow_count))
                             }
                         };
-                        highlight_name(db, def)
+                        highlight_name_ref(sema, &name_ref, def)
                     }
                     NameRefClass::FieldShorthand { .. } => HighlightTag::Field.into(),
                 },
@@ -370,7 +441,14 @@ fn highlight_element(
         }
 
         // Simple token-based highlighting
-        COMMENT => HighlightTag::Comment.into(),
+        COMMENT => {
+            let comment = element.into_token().and_then(ast::Comment::cast)?;
+            let h = Highlight::new(HighlightTag::Comment);
+            match comment.kind().doc {
+                Some(_) => h | HighlightModifier::Documentation,
+                None => h,
+            }
+        }
         STRING | RAW_STRING | RAW_BYTE_STRING | BYTE_STRING => HighlightTag::StringLiteral.into(),
         ATTR => HighlightTag::Attribute.into(),
         INT_NUMBER | FLOAT_NUMBER => HighlightTag::NumericLiteral.into(),
@@ -384,6 +462,16 @@ fn highlight_element(
             }
         }
 
+        T![*] => {
+            // Only dereferencing a raw pointer is interesting enough to highlight.
+            let prefix_expr = element.parent().and_then(ast::PrefixExpr::cast)?;
+            let ty = sema.type_of_expr(&prefix_expr.expr()?)?;
+            if !ty.is_raw_ptr() {
+                return None;
+            }
+            HighlightTag::Operator | HighlightModifier::Unsafe
+        }
+
         k if k.is_keyword() => {
             let h = Highlight::new(HighlightTag::Keyword);
             match k {
@@ -397,12 +485,19 @@ fn highlight_element(
                 | T![return]
                 | T![while] => h | HighlightModifier::ControlFlow,
                 T![unsafe] => h | HighlightModifier::Unsafe,
+                T![self] => match element.parent().and_then(ast::SelfParam::cast) {
+                    Some(self_param) if self_param.mut_token().is_some() => {
+                        h | HighlightModifier::Mutable
+                    }
+                    _ => h,
+                },
                 _ => h,
             }
         }
 
         _ => return None,
     };
+    let highlight = if in_attribute { highlight | HighlightModifier::Attribute } else { highlight };
 
     return Some((highlight, binding_hash));
 
@@ -419,35 +514,105 @@ fn highlight_element(
     }
 }
 
+/// Like `highlight_name`, with the modifiers which depend on how `def` is used.
+fn highlight_name_ref(
+    sema: &Semantics<RootDatabase>,
+    name_ref: &ast::NameRef,
+    def: Definition,
+) -> Highlight {
+    let db = sema.db;
+    let parent = name_ref.syntax().parent();
+    let mut modifiers = Vec::new();
+    match &def {
+        Definition::ModuleDef(hir::ModuleDef::Function(func)) => {
+            if let Some(method_call) = parent.and_then(ast::MethodCallExpr::cast) {
+                match func.self_param_access(db) {
+                    Some(hir::Access::Exclusive) => modifiers.push(HighlightModifier::Mutable),
+                    Some(hir::Access::Owned) => {
+                        let receiver_ty =
+                            method_call.expr().and_then(|expr| sema.type_of_expr(&expr));
+                        if receiver_ty.map_or(false, |ty| !ty.is_copy(db)) {
+                            modifiers.push(HighlightModifier::Consuming);
+                        }
+                    }
+                    Some(hir::Access::Shared) | None => (),
+                }
+            }
+        }
+        Definition::ModuleDef(hir::ModuleDef::Static(it)) if it.is_mut(db) => {
+            modifiers.push(HighlightModifier::Unsafe);
+        }
+        Definition::Field(field) => {
+            let is_union = matches!(field.parent_def(db), hir::VariantDef::Union(_));
+            if is_union && parent.and_then(ast::FieldExpr::cast).is_some() {
+                modifiers.push(HighlightModifier::Unsafe);
+            }
+        }
+        _ => (),
+    }
+    modifiers.into_iter().fold(highlight_name(db, def), |h, modifier| h | modifier)
+}
+
 fn highlight_name(db: &RootDatabase, def: Definition) -> Highlight {
-    match def {
-        Definition::Macro(_) => HighlightTag::Macro,
-        Definition::Field(_) => HighlightTag::Field,
+    let from_library = is_from_library(db, &def);
+    let mut h: Highlight = match def {
+        Definition::Macro(_) => HighlightTag::Macro.into(),
+        Definition::Field(_) => HighlightTag::Field.into(),
         Definition::ModuleDef(def) => match def {
-            hir::ModuleDef::Module(_) => HighlightTag::Module,
-            hir::ModuleDef::Function(_) => HighlightTag::Function,
-            hir::ModuleDef::Adt(hir::Adt::Struct(_)) => HighlightTag::Struct,
-            hir::ModuleDef::Adt(hir::Adt::Enum(_)) => HighlightTag::Enum,
-            hir::ModuleDef::Adt(hir::Adt::Union(_)) => HighlightTag::Union,
-            hir::ModuleDef::EnumVariant(_) => HighlightTag::EnumVariant,
-            hir::ModuleDef::Const(_) => HighlightTag::Constant,
-            hir::ModuleDef::Static(_) => HighlightTag::Static,
-            hir::ModuleDef::Trait(_) => HighlightTag::Trait,
-            hir::ModuleDef::TypeAlias(_) => HighlightTag::TypeAlias,
-            hir::ModuleDef::BuiltinType(_) => HighlightTag::BuiltinType,
+            hir::ModuleDef::Module(_) => HighlightTag::Module.into(),
+            hir::ModuleDef::Function(func) => {
+                let h = Highlight::new(HighlightTag::Function);
+                if func.is_unsafe(db) {
+                    h | HighlightModifier::Unsafe
+                } else {
+                    h
+                }
+            }
+            hir::ModuleDef::Adt(hir::Adt::Struct(_)) => HighlightTag::Struct.into(),
+            hir::ModuleDef::Adt(hir::Adt::Enum(_)) => HighlightTag::Enum.into(),
+            hir::ModuleDef::Adt(hir::Adt::Union(_)) => HighlightTag::Union.into(),
+            hir::ModuleDef::EnumVariant(_) => HighlightTag::EnumVariant.into(),
+            hir::ModuleDef::Const(_) => HighlightTag::Constant.into(),
+            hir::ModuleDef::Static(it) => {
+                let h = Highlight::new(HighlightTag::Static);
+                if it.is_mut(db) {
+                    h | HighlightModifier::Mutable
+                } else {
+                    h
+                }
+            }
+            hir::ModuleDef::Trait(_) => HighlightTag::Trait.into(),
+            hir::ModuleDef::TypeAlias(_) => HighlightTag::TypeAlias.into(),
+            hir::ModuleDef::BuiltinType(_) => HighlightTag::BuiltinType.into(),
         },
-        Definition::SelfType(_) => HighlightTag::SelfType,
-        Definition::TypeParam(_) => HighlightTag::TypeParam,
+        Definition::SelfType(_) => HighlightTag::SelfType.into(),
+        Definition::TypeParam(_) => HighlightTag::TypeParam.into(),
         // FIXME: distinguish between locals and parameters
         Definition::Local(local) => {
             let mut h = Highlight::new(HighlightTag::Local);
-            if local.is_mut(db) || local.ty(db).is_mutable_reference() {
+            let ty = local.ty(db);
+            if local.is_mut(db) || ty.is_mutable_reference() {
                 h |= HighlightModifier::Mutable;
             }
-            return h;
+            if ty.is_closure() || ty.is_fn() {
+                h |= HighlightModifier::Callable;
+            }
+            h
         }
+    };
+    if from_library {
+        h |= HighlightModifier::Library;
     }
-    .into()
+    h
+}
+
+fn is_from_library(db: &RootDatabase, def: &Definition) -> bool {
+    let krate = match def.module(db) {
+        Some(module) => module.krate(),
+        None => return false,
+    };
+    let source_root = db.file_source_root(krate.root_file(db));
+    db.source_root(source_root).is_library
 }
 
 fn highlight_name_by_syntax(name: ast::Name) -> Highlight {
@@ -29,6 +29,7 @@ pub enum HighlightTag {
     Macro,
     Module,
     NumericLiteral,
+    Operator,
     SelfType,
     Static,
     StringLiteral,
@@ -40,6 +41,8 @@ pub enum HighlightTag {
     Local,
     UnresolvedReference,
     FormatSpecifier,
+    /// Code disabled by `#[cfg]`.
+    Inactive,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -50,8 +53,21 @@ pub enum HighlightModifier {
     /// `foo` in `fn foo(x: i32)` is a definition, `foo` in `foo(90 + 2)` is
     /// not.
     Definition,
+    /// Mutable bindings, `static mut`s and methods which take `&mut self`.
     Mutable,
+    /// Calls to unsafe functions and other operations which need an `unsafe`
+    /// block.
     Unsafe,
+    /// Methods which take `self` by value, when the receiver isn't `Copy`.
+    Consuming,
+    /// Locals which can be called, like closures and function pointers.
+    Callable,
+    /// Items defined outside of the workspace.
+    Library,
+    /// Tokens inside of an attribute.
+    Attribute,
+    /// Doc comments and the code injected from them.
+    Documentation,
 }
 
 impl HighlightTag {
@@ -72,6 +88,7 @@ impl HighlightTag {
             HighlightTag::Macro => "macro",
             HighlightTag::Module => "module",
             HighlightTag::NumericLiteral => "numeric_literal",
+            HighlightTag::Operator => "operator",
             HighlightTag::SelfType => "self_type",
             HighlightTag::Static => "static",
             HighlightTag::StringLiteral => "string_literal",
@@ -83,6 +100,7 @@ impl HighlightTag {
             HighlightTag::Local => "variable",
             HighlightTag::UnresolvedReference => "unresolved_reference",
             HighlightTag::FormatSpecifier => "format_specifier",
+            HighlightTag::Inactive => "inactive",
         }
     }
 }
@@ -99,6 +117,11 @@ impl HighlightModifier {
         HighlightModifier::Definition,
         HighlightModifier::Mutable,
         HighlightModifier::Unsafe,
+        HighlightModifier::Consuming,
+        HighlightModifier::Callable,
+        HighlightModifier::Library,
+        HighlightModifier::Attribute,
+        HighlightModifier::Documentation,
     ];
 
     fn as_str(self) -> &'static str {
@@ -107,6 +130,11 @@ impl HighlightModifier {
             HighlightModifier::Definition => "declaration",
             HighlightModifier::Mutable => "mutable",
             HighlightModifier::Unsafe => "unsafe",
+            HighlightModifier::Consuming => "consuming",
+            HighlightModifier::Callable => "callable",
+            HighlightModifier::Library => "library",
+            HighlightModifier::Attribute => "attribute",
+            HighlightModifier::Documentation => "documentation",
         }
     }
 
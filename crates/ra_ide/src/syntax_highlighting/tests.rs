@@ -132,6 +132,124 @@ fn test_ranges() {
     assert_eq!(&highlights[0].highlight.to_string(), "field.declaration");
 }
 
+#[test]
+fn test_semantic_modifiers() {
+    let (analysis, file_id) = single_file(
+        r#"
+static mut GLOBAL: u32 = 0;
+
+union U { a: u32 }
+
+unsafe fn danger() {}
+
+#[doc = "attr"]
+struct S;
+impl S {
+    fn take(self) {}
+    fn poke(&mut self) {}
+}
+
+/// Docs
+fn main() {
+    let mut s = S;
+    s.poke();
+    s.take();
+    let f = || 92;
+    f();
+    let p = &0u32 as *const u32;
+    unsafe {
+        danger();
+        GLOBAL += 1;
+        let u = U { a: 0 };
+        u.a;
+        *p;
+    }
+}
+"#
+        .trim(),
+    );
+    let text = analysis.file_text(file_id).unwrap();
+    let highlights: Vec<(String, String)> = analysis
+        .highlight(file_id)
+        .unwrap()
+        .into_iter()
+        .map(|it| (text[it.range].to_string(), it.highlight.to_string()))
+        .collect();
+
+    for (token, highlight) in &[
+        ("GLOBAL", "static.declaration.mutable"),
+        ("GLOBAL", "static.mutable.unsafe"),
+        ("danger", "function.declaration.unsafe"),
+        ("danger", "function.unsafe"),
+        ("a", "field.unsafe"),
+        ("\"attr\"", "string_literal.attribute"),
+        ("self", "keyword.mutable"),
+        ("poke", "function.mutable"),
+        ("take", "function.consuming"),
+        ("f", "variable.declaration.callable"),
+        ("f", "variable.callable"),
+        ("*", "operator.unsafe"),
+        ("/// Docs", "comment.documentation"),
+    ] {
+        let expected = (token.to_string(), highlight.to_string());
+        assert!(highlights.contains(&expected), "missing {:?} in {:#?}", expected, highlights);
+    }
+}
+
+#[test]
+fn test_inactive_code_highlighting() {
+    let (analysis, file_id) = single_file(
+        r#"
+#[cfg(feature = "foo")]
+fn foo() { let x = 92; }
+
+fn main() {}
+"#
+        .trim(),
+    );
+    let text = analysis.file_text(file_id).unwrap();
+    let highlights: Vec<(String, String)> = analysis
+        .highlight(file_id)
+        .unwrap()
+        .into_iter()
+        .map(|it| (text[it.range].to_string(), it.highlight.to_string()))
+        .collect();
+
+    assert_eq!(highlights[0].0, "#[cfg(feature = \"foo\")]\nfn foo() { let x = 92; }");
+    assert_eq!(highlights[0].1, "inactive");
+    assert_eq!(highlights[1], ("fn".to_string(), "keyword".to_string()));
+}
+
+#[test]
+fn test_doc_test_highlighting() {
+    let (analysis, file_id) = single_file(
+        r#"
+/// Does nothing.
+///
+/// ```
+/// let x = 92;
+/// ```
+fn foo() {}
+"#
+        .trim(),
+    );
+    let text = analysis.file_text(file_id).unwrap();
+    let highlights: Vec<(String, String)> = analysis
+        .highlight(file_id)
+        .unwrap()
+        .into_iter()
+        .map(|it| (text[it.range].to_string(), it.highlight.to_string()))
+        .collect();
+
+    assert_eq!(
+        highlights[0],
+        ("/// Does nothing.".to_string(), "comment.documentation".to_string())
+    );
+    assert!(highlights.contains(&("/// ".to_string(), "comment.documentation".to_string())));
+    assert!(highlights.contains(&("let".to_string(), "keyword.documentation".to_string())));
+    assert!(highlights.contains(&("92".to_string(), "numeric_literal.documentation".to_string())));
+}
+
 #[test]
 fn test_flattening() {
     let (analysis, file_id) = single_file(
@@ -0,0 +1,260 @@
+//! Collects the tests of all workspace crates into a crate -> module -> test
+//! tree, so that test explorers don't have to open every file.
+
+use itertools::Itertools;
+use ra_db::{FileId, SourceDatabaseExt};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{AstNode, TextRange};
+use rustc_hash::FxHashSet;
+
+use crate::runnables::{runnable_for_function, Runnable, RunnableKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestItemKind {
+    Crate,
+    Module,
+    Test,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestItem {
+    /// Kind and path of the item relative to the crate root, like
+    /// `mod:foo::bar` or `test:foo::bar::baz`. A module and a test with the
+    /// same path get different ids, and ids only change when the item is
+    /// renamed or moved.
+    pub id: String,
+    pub parent: Option<String>,
+    pub kind: TestItemKind,
+    pub label: String,
+    /// Ids are only unique within a crate, this is the root file of it.
+    pub crate_root: FileId,
+    pub file_id: FileId,
+    pub range: TextRange,
+    /// How to run the item and everything below it, `None` for crates.
+    pub runnable: Option<Runnable>,
+}
+
+/// Returns the tests of all local crates, parents before their children.
+///
+/// With `file_id`, only the items defined in that file and their ancestors
+/// are returned, which is enough to refresh a tree after the file changed.
+pub(crate) fn discover_tests(db: &RootDatabase, file_id: Option<FileId>) -> Vec<TestItem> {
+    let mut res = Vec::new();
+    for krate in hir::Crate::all(db) {
+        let crate_root = krate.root_file(db);
+        if db.source_root(db.file_source_root(crate_root)).is_library {
+            continue;
+        }
+        if let Some(file_id) = file_id {
+            if !db.relevant_crates(file_id).contains(&krate.into()) {
+                continue;
+            }
+        }
+        let root_module = match krate.root_module(db) {
+            Some(it) => it,
+            None => continue,
+        };
+        let label = match krate.display_name(db) {
+            Some(name) => name.to_string(),
+            None => "crate".to_string(),
+        };
+        let mut items = Vec::new();
+        collect_module(db, crate_root, root_module, None, &mut items);
+        if let Some(root) = items.first_mut() {
+            root.label = label;
+            root.runnable = None;
+        }
+        match file_id {
+            Some(file_id) => res.extend(in_file_with_ancestors(items, file_id)),
+            None => res.extend(items),
+        }
+    }
+    res
+}
+
+/// Pushes the item for `module` followed by its tests and submodules, unless
+/// there are no tests in it at all.
+fn collect_module(
+    db: &RootDatabase,
+    crate_root: FileId,
+    module: hir::Module,
+    parent: Option<String>,
+    acc: &mut Vec<TestItem>,
+) {
+    let path = module
+        .path_to_root(db)
+        .into_iter()
+        .rev()
+        .filter_map(|it| it.name(db))
+        .map(|name| name.to_string())
+        .join("::");
+    let src = module.definition_source(db);
+    let file_id = src.file_id.original_file(db);
+    let range = match &src.value {
+        hir::ModuleSource::SourceFile(it) => it.syntax().text_range(),
+        hir::ModuleSource::Module(it) => it.syntax().text_range(),
+    };
+    let label = module.name(db).map(|name| name.to_string()).unwrap_or_default();
+    let kind = match module.parent(db) {
+        Some(_) => TestItemKind::Module,
+        None => TestItemKind::Crate,
+    };
+    let id = item_id(kind, &path);
+    let runnable = Runnable { range, kind: RunnableKind::TestMod { path } };
+
+    let start = acc.len();
+    acc.push(TestItem {
+        id: id.clone(),
+        parent,
+        kind,
+        label,
+        crate_root,
+        file_id,
+        range,
+        runnable: Some(runnable),
+    });
+
+    for def in module.declarations(db) {
+        let function = match def {
+            hir::ModuleDef::Function(it) => it,
+            _ => continue,
+        };
+        let runnable = match runnable_for_function(db, function, file_id) {
+            Some(it) => it,
+            None => continue,
+        };
+        let test_id = match &runnable.kind {
+            RunnableKind::Test { test_id, .. } | RunnableKind::Bench { test_id } => {
+                test_id.to_string()
+            }
+            _ => continue,
+        };
+        acc.push(TestItem {
+            id: item_id(TestItemKind::Test, &test_id),
+            parent: Some(id.clone()),
+            kind: TestItemKind::Test,
+            label: function.name(db).to_string(),
+            crate_root,
+            file_id,
+            range: runnable.range,
+            runnable: Some(runnable),
+        });
+    }
+    let mut children: Vec<_> = module.children(db).collect();
+    children.sort_by_key(|it| it.name(db).map(|name| name.to_string()));
+    for child in children {
+        collect_module(db, crate_root, child, Some(id.clone()), acc);
+    }
+
+    if acc.len() == start + 1 {
+        acc.pop();
+    }
+}
+
+fn item_id(kind: TestItemKind, path: &str) -> String {
+    let prefix = match kind {
+        TestItemKind::Crate => "crate",
+        TestItemKind::Module => "mod",
+        TestItemKind::Test => "test",
+    };
+    format!("{}:{}", prefix, path)
+}
+
+fn in_file_with_ancestors(items: Vec<TestItem>, file_id: FileId) -> Vec<TestItem> {
+    let mut keep: FxHashSet<String> = FxHashSet::default();
+    // Children always come after their parents, so walking backwards sees
+    // every kept item before its parent.
+    for item in items.iter().rev() {
+        if item.file_id == file_id || keep.contains(&item.id) {
+            keep.extend(item.parent.clone());
+            keep.insert(item.id.clone());
+        }
+    }
+    items.into_iter().filter(|it| keep.contains(&it.id)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::MockAnalysis;
+
+    use super::TestItemKind;
+
+    #[test]
+    fn test_discover_tests() {
+        let mock = MockAnalysis::with_files(
+            r#"
+            //- /lib.rs
+            mod foo;
+            mod empty;
+
+            #[test]
+            fn root_test() {}
+
+            mod inline {
+                #[test]
+                fn inline_test() {}
+                fn helper() {}
+            }
+
+            mod same_name {
+                #[test]
+                fn same_name() {}
+
+                mod same_name {
+                    #[test]
+                    fn nested_test() {}
+                }
+            }
+
+            //- /foo.rs
+            #[test]
+            fn foo_test() {}
+
+            //- /empty.rs
+            fn not_a_test() {}
+            "#,
+        );
+        let foo = mock.id_of("/foo.rs");
+        let analysis = mock.analysis();
+
+        let summary = |file_id| {
+            analysis
+                .discover_tests(file_id)
+                .unwrap()
+                .into_iter()
+                .map(|it| (it.id, it.parent, it.kind))
+                .collect::<Vec<_>>()
+        };
+        let item = |id: &str, parent: Option<&str>, kind| {
+            (id.to_string(), parent.map(|it| it.to_string()), kind)
+        };
+
+        assert_eq!(
+            summary(None),
+            vec![
+                item("crate:", None, TestItemKind::Crate),
+                item("test:root_test", Some("crate:"), TestItemKind::Test),
+                item("mod:foo", Some("crate:"), TestItemKind::Module),
+                item("test:foo::foo_test", Some("mod:foo"), TestItemKind::Test),
+                item("mod:inline", Some("crate:"), TestItemKind::Module),
+                item("test:inline::inline_test", Some("mod:inline"), TestItemKind::Test),
+                item("mod:same_name", Some("crate:"), TestItemKind::Module),
+                item("test:same_name::same_name", Some("mod:same_name"), TestItemKind::Test),
+                item("mod:same_name::same_name", Some("mod:same_name"), TestItemKind::Module),
+                item(
+                    "test:same_name::same_name::nested_test",
+                    Some("mod:same_name::same_name"),
+                    TestItemKind::Test
+                ),
+            ]
+        );
+        assert_eq!(
+            summary(Some(foo)),
+            vec![
+                item("crate:", None, TestItemKind::Crate),
+                item("mod:foo", Some("crate:"), TestItemKind::Module),
+                item("test:foo::foo_test", Some("mod:foo"), TestItemKind::Test),
+            ]
+        );
+    }
+}
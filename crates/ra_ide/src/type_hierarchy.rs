@@ -0,0 +1,218 @@
+//! Entry point for type-hierarchy.
+//!
+//! For a trait, we list the traits it inherits from, the traits inheriting
+//! from it and the types implementing it. For a struct, enum or union, we list
+//! the traits it implements.
+
+use hir::{Adt, Crate, GenericDef, ImplDef, Semantics, Trait};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{algo::find_node_at_offset, ast, AstNode};
+use rustc_hash::FxHashSet;
+
+use crate::{display::ToNav, FilePosition, NavigationTarget, RangeInfo};
+
+#[derive(Debug, Clone)]
+pub struct TypeHierarchy {
+    pub target: NavigationTarget,
+    /// For a trait, the traits it inherits from.
+    pub supertraits: Vec<NavigationTarget>,
+    /// For a trait, the traits which inherit from it.
+    pub subtraits: Vec<NavigationTarget>,
+    /// For a trait, the types implementing it. If the self type of an impl is
+    /// not a struct, enum or union, the impl itself is listed instead.
+    pub implementors: Vec<NavigationTarget>,
+    /// For a struct, enum or union, the traits it implements.
+    pub implemented_traits: Vec<NavigationTarget>,
+}
+
+pub(crate) fn type_hierarchy(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<TypeHierarchy>> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(position.file_id);
+    let syntax = source_file.syntax();
+
+    if let Some(nominal_def) = find_node_at_offset::<ast::NominalDef>(syntax, position.offset) {
+        let adt: Adt = match &nominal_def {
+            ast::NominalDef::StructDef(def) => sema.to_def(def)?.into(),
+            ast::NominalDef::EnumDef(def) => sema.to_def(def)?.into(),
+            ast::NominalDef::UnionDef(def) => sema.to_def(def)?.into(),
+        };
+        return Some(RangeInfo::new(nominal_def.syntax().text_range(), adt_hierarchy(db, adt)));
+    }
+
+    let trait_def = find_node_at_offset::<ast::TraitDef>(syntax, position.offset)?;
+    let trait_ = sema.to_def(&trait_def)?;
+    Some(RangeInfo::new(trait_def.syntax().text_range(), trait_hierarchy(db, trait_)))
+}
+
+fn trait_hierarchy(db: &RootDatabase, trait_: Trait) -> TypeHierarchy {
+    let supertraits = trait_.direct_super_traits(db).into_iter().map(|it| it.to_nav(db)).collect();
+    let subtraits = all_traits(db)
+        .into_iter()
+        .filter(|it| it.direct_super_traits(db).contains(&trait_))
+        .map(|it| it.to_nav(db))
+        .collect();
+
+    let mut implementors = Vec::new();
+    let mut seen = FxHashSet::default();
+    for krate in Crate::all(db) {
+        for impl_def in ImplDef::for_trait(db, krate, trait_) {
+            if impl_def.is_negative(db) {
+                continue;
+            }
+            match impl_def.target_ty(db).as_adt() {
+                Some(adt) => {
+                    if seen.insert(adt) {
+                        implementors.push(adt.to_nav(db));
+                    }
+                }
+                None => implementors.push(impl_def.to_nav(db)),
+            }
+        }
+    }
+
+    TypeHierarchy {
+        target: trait_.to_nav(db),
+        supertraits,
+        subtraits,
+        implementors,
+        implemented_traits: Vec::new(),
+    }
+}
+
+fn adt_hierarchy(db: &RootDatabase, adt: Adt) -> TypeHierarchy {
+    let mut traits = Vec::new();
+    // Traits with impls for type parameters or references, like
+    // `impl<T: Display> ToString for T`, which might apply to `adt`.
+    let mut blanket_traits = Vec::new();
+    for krate in Crate::all(db) {
+        for impl_def in ImplDef::all_in_crate(db, krate) {
+            if impl_def.is_negative(db) {
+                continue;
+            }
+            let trait_ = match impl_def.target_trait_def(db) {
+                Some(it) => it,
+                None => continue,
+            };
+            let acc = match impl_def.target_ty(db).as_adt() {
+                Some(it) if it == adt => &mut traits,
+                Some(_) => continue,
+                None => &mut blanket_traits,
+            };
+            if !acc.contains(&trait_) {
+                acc.push(trait_);
+            }
+        }
+    }
+
+    // Whether a blanket impl applies is up to the trait solver. We can't guess
+    // the arguments of a generic trait, so those are skipped.
+    let ty = adt.ty(db);
+    for trait_ in blanket_traits {
+        if !traits.contains(&trait_)
+            && GenericDef::from(trait_).params(db).len() == 1
+            && ty.impls_trait(db, trait_, &[])
+        {
+            traits.push(trait_);
+        }
+    }
+
+    TypeHierarchy {
+        target: adt.to_nav(db),
+        supertraits: Vec::new(),
+        subtraits: Vec::new(),
+        implementors: Vec::new(),
+        implemented_traits: traits.into_iter().map(|it| it.to_nav(db)).collect(),
+    }
+}
+
+fn all_traits(db: &RootDatabase) -> Vec<Trait> {
+    let mut res = Vec::new();
+    let mut modules: Vec<_> =
+        Crate::all(db).into_iter().filter_map(|krate| krate.root_module(db)).collect();
+    while let Some(module) = modules.pop() {
+        modules.extend(module.children(db));
+        for decl in module.declarations(db) {
+            if let hir::ModuleDef::Trait(it) = decl {
+                res.push(it);
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::analysis_and_position;
+
+    fn check_hierarchy(ra_fixture: &str, expected: &str) {
+        let (analysis, pos) = analysis_and_position(ra_fixture);
+        let hierarchy = analysis.type_hierarchy(pos).unwrap().unwrap().info;
+
+        let mut actual = format!("target: {}\n", hierarchy.target.debug_render());
+        let sections = [
+            ("supertrait", &hierarchy.supertraits),
+            ("subtrait", &hierarchy.subtraits),
+            ("implementor", &hierarchy.implementors),
+            ("implemented", &hierarchy.implemented_traits),
+        ];
+        for (title, navs) in sections.iter() {
+            let mut navs = navs.to_vec();
+            navs.sort_by_key(|nav| nav.full_range().start());
+            for nav in navs {
+                actual.push_str(&format!("{}: {}\n", title, nav.debug_render()));
+            }
+        }
+        test_utils::assert_eq_text!(expected.trim(), actual.trim());
+    }
+
+    #[test]
+    fn type_hierarchy_of_trait() {
+        check_hierarchy(
+            "
+            //- /lib.rs
+            trait A {}
+            trait B<|>: A {}
+            trait C: B {}
+            trait D where Self: B {}
+            struct S;
+            enum E {}
+            impl B for S {}
+            impl B for E {}
+            impl B for &S {}
+            ",
+            "
+target: B TRAIT_DEF FileId(1) 11..24 17..18
+supertrait: A TRAIT_DEF FileId(1) 0..10 6..7
+subtrait: C TRAIT_DEF FileId(1) 25..38 31..32
+subtrait: D TRAIT_DEF FileId(1) 39..63 45..46
+implementor: S STRUCT_DEF FileId(1) 64..73 71..72
+implementor: E ENUM_DEF FileId(1) 74..83 79..80
+implementor: impl IMPL_DEF FileId(1) 116..132
+            ",
+        );
+    }
+
+    #[test]
+    fn type_hierarchy_of_struct() {
+        check_hierarchy(
+            "
+            //- /lib.rs
+            trait Marker {}
+            trait Tr {}
+            trait Other {}
+            impl<T: Marker> Tr for T {}
+            impl<T: Other> Other for &T {}
+            struct S<|>;
+            impl Marker for S {}
+            ",
+            "
+target: S STRUCT_DEF FileId(1) 102..111 109..110
+implemented: Marker TRAIT_DEF FileId(1) 0..15 6..12
+implemented: Tr TRAIT_DEF FileId(1) 16..27 22..24
+            ",
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Renders the crate graph in the Graphviz DOT format, to debug the project
+//! model, like the crates of a `rust-project.json` not seeing each other.
+
+use ra_db::{CrateId, FileId, SourceDatabase};
+use ra_ide_db::RootDatabase;
+use rustc_hash::FxHashSet;
+use stdx::SepBy;
+
+#[derive(Debug, Clone, Default)]
+pub struct CrateGraphConfig {
+    /// Crates whose root file is one of these are left out, together with the
+    /// edges to them. Used to hide the sysroot.
+    pub hidden_roots: FxHashSet<FileId>,
+}
+
+pub(crate) fn view_crate_graph(db: &RootDatabase, config: &CrateGraphConfig) -> String {
+    let graph = db.crate_graph();
+    let mut crates: Vec<CrateId> = graph
+        .iter()
+        .filter(|&krate| !config.hidden_roots.contains(&graph[krate].root_file_id))
+        .collect();
+    crates.sort();
+
+    let mut buf = String::new();
+    buf.push_str("digraph rust_analyzer_crate_graph {\n");
+    buf.push_str("    node [shape=box];\n");
+    for &krate in &crates {
+        let data = &graph[krate];
+        let mut lines = vec![match &data.display_name {
+            Some(name) => name.to_string(),
+            None => db.debug_file_path(data.root_file_id),
+        }];
+        lines.push(format!("edition {}", data.edition));
+        let mut cfgs: Vec<String> = data.cfg_options.enabled().map(|it| it.to_string()).collect();
+        if !cfgs.is_empty() {
+            cfgs.sort();
+            lines.push(format!("cfg: {}", cfgs.join(", ")));
+        }
+        let mut style = "";
+        if !data.proc_macro.is_empty() {
+            let names = data.proc_macro.iter().map(|it| it.name.clone()).sep_by(", ");
+            lines.push(format!("proc-macro: {}", names));
+            style = ", style=dashed";
+        }
+        let label = lines.iter().map(|it| escape(it)).sep_by("\\n");
+        buf.push_str(&format!("    {} [label=\"{}\"{}];\n", node_id(krate), label, style));
+    }
+    for &krate in &crates {
+        for dep in &graph[krate].dependencies {
+            if config.hidden_roots.contains(&graph[dep.crate_id].root_file_id) {
+                continue;
+            }
+            buf.push_str(&format!(
+                "    {} -> {} [label=\"{}\"];\n",
+                node_id(krate),
+                node_id(dep.crate_id),
+                escape(&dep.name)
+            ));
+        }
+    }
+    buf.push_str("}\n");
+    buf
+}
+
+fn node_id(krate: CrateId) -> String {
+    format!("crate{}", krate.0)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::assert_eq_text;
+
+    use crate::{mock_analysis::MockAnalysis, CrateGraphConfig, FileId};
+
+    #[test]
+    fn test_view_crate_graph() {
+        let analysis = MockAnalysis::with_files(
+            r#"
+//- /lib.rs
+extern crate foo;
+//- /foo/lib.rs
+pub struct Foo;
+"#,
+        )
+        .analysis();
+        let dot = analysis.view_crate_graph(&CrateGraphConfig::default()).unwrap();
+        assert_eq_text!(
+            &dot,
+            r#"digraph rust_analyzer_crate_graph {
+    node [shape=box];
+    crate0 [label="lib.rs\nedition 2018"];
+    crate1 [label="foo\nedition 2018"];
+    crate0 -> crate1 [label="foo"];
+}
+"#
+        );
+
+        let config = CrateGraphConfig { hidden_roots: vec![FileId(2)].into_iter().collect() };
+        let dot = analysis.view_crate_graph(&config).unwrap();
+        assert_eq_text!(
+            &dot,
+            r#"digraph rust_analyzer_crate_graph {
+    node [shape=box];
+    crate0 [label="lib.rs\nedition 2018"];
+}
+"#
+        );
+    }
+}
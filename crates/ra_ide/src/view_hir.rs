@@ -0,0 +1,59 @@
+//! Shows the lowered body of the function at the cursor, annotated with what
+//! type inference found out about it.
+
+use hir::Semantics;
+use ra_ide_db::RootDatabase;
+use ra_syntax::{algo::find_node_at_offset, ast, AstNode};
+
+use crate::FilePosition;
+
+pub(crate) fn view_hir(db: &RootDatabase, position: FilePosition) -> String {
+    body_hir(db, position).unwrap_or_else(|| "Not inside a function body".to_string())
+}
+
+fn body_hir(db: &RootDatabase, position: FilePosition) -> Option<String> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(position.file_id);
+    let function = find_node_at_offset::<ast::FnDef>(source_file.syntax(), position.offset)?;
+    function.body()?;
+    let function = sema.to_def(&function)?;
+    Some(function.debug_hir(db))
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::assert_eq_text;
+
+    use crate::mock_analysis::analysis_and_position;
+
+    #[test]
+    fn test_view_hir() {
+        let (analysis, position) = analysis_and_position(
+            r#"
+//- /lib.rs
+struct S;
+impl S {
+    fn get(&self) -> u32 { 0 }
+}
+fn foo(s: S) -> u32 {
+    let x = s.get();
+    x<|>
+}
+"#,
+        );
+        let hir = analysis.view_hir(position).unwrap();
+        assert_eq_text!(
+            hir.trim(),
+            r#"
+param: Bind `s`: S
+body: Block: u32
+  Let
+    pat: Bind `x`: u32
+    init: MethodCall `get`: u32 (resolved to <S>::get)
+      receiver: Path `s`: S (adjusted to &S)
+  tail: Path `x`: u32
+"#
+            .trim()
+        );
+    }
+}
@@ -10,6 +10,7 @@ use ra_db::{
 };
 use ra_prof::{memory_usage, profile, Bytes};
 use ra_syntax::SourceFile;
+use ra_text_edit::AtomTextEdit;
 #[cfg(not(feature = "wasm"))]
 use rayon::prelude::*;
 use rustc_hash::FxHashMap;
@@ -23,7 +24,7 @@ use crate::{
 pub struct AnalysisChange {
     new_roots: Vec<(SourceRootId, bool)>,
     roots_changed: FxHashMap<SourceRootId, RootChange>,
-    files_changed: Vec<(FileId, Arc<String>)>,
+    files_changed: Vec<(FileId, Arc<String>, Option<Vec<AtomTextEdit>>)>,
     libraries_added: Vec<LibraryData>,
     crate_graph: Option<CrateGraph>,
     debug_data: DebugData,
@@ -72,7 +73,19 @@ impl AnalysisChange {
     }
 
     pub fn change_file(&mut self, file_id: FileId, new_text: Arc<String>) {
-        self.files_changed.push((file_id, new_text))
+        self.files_changed.push((file_id, new_text, None))
+    }
+
+    /// Like `change_file`, but the syntax tree of the file is updated by
+    /// reparsing only what `edits` touch. Applied in order, the edits must turn
+    /// the current text of the file into `new_text`.
+    pub fn change_file_incrementally(
+        &mut self,
+        file_id: FileId,
+        new_text: Arc<String>,
+        edits: Vec<AtomTextEdit>,
+    ) {
+        self.files_changed.push((file_id, new_text, Some(edits)))
     }
 
     pub fn remove_file(&mut self, root_id: SourceRootId, file_id: FileId, path: RelativePathBuf) {
@@ -175,26 +188,33 @@ impl RootDatabase {
         self.request_cancellation();
         log::info!("apply_change {:?}", change);
         if !change.new_roots.is_empty() {
+            // Adding a root which already exists starts it afresh, possibly
+            // with a different kind.
             let mut local_roots = Vec::clone(&self.local_roots());
+            let mut libraries = Vec::clone(&self.library_roots());
             for (root_id, is_local) in change.new_roots {
                 let root =
                     if is_local { SourceRoot::new_local() } else { SourceRoot::new_library() };
                 let durability = durability(&root);
                 self.set_source_root_with_durability(root_id, Arc::new(root), durability);
+                local_roots.retain(|it| *it != root_id);
+                libraries.retain(|it| *it != root_id);
                 if is_local {
                     local_roots.push(root_id);
                 }
             }
             self.set_local_roots_with_durability(Arc::new(local_roots), Durability::HIGH);
+            self.set_library_roots_with_durability(Arc::new(libraries), Durability::HIGH);
         }
 
         for (root_id, root_change) in change.roots_changed {
             self.apply_root_change(root_id, root_change);
         }
-        for (file_id, text) in change.files_changed {
+        for (file_id, text, edits) in change.files_changed {
             let source_root_id = self.file_source_root(file_id);
             let source_root = self.source_root(source_root_id);
             let durability = durability(&source_root);
+            self.set_pending_reparse(file_id, edits);
             self.set_file_text_with_durability(file_id, text, durability)
         }
         if !change.libraries_added.is_empty() {
@@ -222,10 +242,33 @@ impl RootDatabase {
         Arc::make_mut(&mut self.debug_data).merge(change.debug_data)
     }
 
+    /// Remembers the current syntax tree of the file, so that `parse` can
+    /// apply `edits` to it once the new text is set. Without `edits`, the file
+    /// is parsed from scratch.
+    fn set_pending_reparse(&mut self, file_id: FileId, edits: Option<Vec<AtomTextEdit>>) {
+        let pending = self.pending_reparses.lock().unwrap().remove(&file_id);
+        let edits = match edits {
+            Some(it) => it,
+            None => return,
+        };
+        let pending = match pending {
+            // `parse` didn't run since the previous change, so the edits of
+            // both changes apply to the same old tree.
+            Some((parse, mut old_edits)) => {
+                old_edits.extend(edits);
+                (parse, old_edits)
+            }
+            // For files being edited this tree is usually already memoized.
+            None => (self.parse(file_id), edits),
+        };
+        self.pending_reparses.lock().unwrap().insert(file_id, pending);
+    }
+
     fn apply_root_change(&mut self, root_id: SourceRootId, root_change: RootChange) {
         let mut source_root = SourceRoot::clone(&self.source_root(root_id));
         let durability = durability(&source_root);
         for add_file in root_change.added {
+            self.set_pending_reparse(add_file.file_id, None);
             self.set_file_text_with_durability(add_file.file_id, add_file.text, durability);
             self.set_file_relative_path_with_durability(
                 add_file.file_id,
@@ -236,6 +279,7 @@ impl RootDatabase {
             source_root.insert_file(add_file.path, add_file.file_id);
         }
         for remove_file in root_change.removed {
+            self.set_pending_reparse(remove_file.file_id, None);
             self.set_file_text_with_durability(remove_file.file_id, Default::default(), durability);
             source_root.remove_file(&remove_file.path);
         }
@@ -388,3 +432,50 @@ fn durability(source_root: &SourceRoot) -> Durability {
         Durability::LOW
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ra_db::{FileId, SourceDatabase, SourceRootId};
+    use ra_syntax::{SourceFile, TextRange, TextSize};
+    use ra_text_edit::AtomTextEdit;
+
+    use super::AnalysisChange;
+    use crate::RootDatabase;
+
+    #[test]
+    fn change_file_incrementally_reparses() {
+        let mut db = RootDatabase::new(None);
+        let root = SourceRootId(0);
+        let file_id = FileId(0);
+        let mut change = AnalysisChange::new();
+        change.add_root(root, true);
+        change.add_file(root, file_id, "main.rs".into(), Arc::new("fn foo() { 1 + 1 }".into()));
+        db.apply_change(change);
+        db.parse(file_id);
+
+        let edits = vec![
+            AtomTextEdit::replace(
+                TextRange::new(TextSize::from(11), TextSize::from(12)),
+                "2".into(),
+            ),
+            AtomTextEdit::insert(TextSize::from(18), "\nfn bar() {}".into()),
+        ];
+        let text = "fn foo() { 2 + 1 }\nfn bar() {}";
+        let mut change = AnalysisChange::new();
+        change.change_file_incrementally(file_id, Arc::new(text.into()), edits);
+        db.apply_change(change);
+        assert!(db.pending_reparses.lock().unwrap().contains_key(&file_id));
+        assert_eq!(db.parse(file_id).debug_dump(), SourceFile::parse(text).debug_dump());
+        assert!(!db.pending_reparses.lock().unwrap().contains_key(&file_id));
+
+        // Edits which don't produce the new text are ignored.
+        let edits = vec![AtomTextEdit::delete(TextRange::up_to(TextSize::from(100)))];
+        let text = "struct S;";
+        let mut change = AnalysisChange::new();
+        change.change_file_incrementally(file_id, Arc::new(text.into()), edits);
+        db.apply_change(change);
+        assert_eq!(db.parse(file_id).debug_dump(), SourceFile::parse(text).debug_dump());
+    }
+}
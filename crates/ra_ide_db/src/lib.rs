@@ -12,14 +12,16 @@ pub mod search;
 pub mod imports_locator;
 mod wasm_shims;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use hir::db::{AstDatabase, DefDatabase};
 use ra_db::{
     salsa::{self, Database, Durability},
     Canceled, CheckCanceled, CrateId, FileId, FileLoader, FileLoaderDelegate, RelativePath,
-    SourceDatabase, SourceRootId, Upcast,
+    SourceDatabase, SourceDatabaseExt, SourceRootId, Upcast,
 };
+use ra_syntax::{Parse, SourceFile};
+use ra_text_edit::AtomTextEdit;
 use rustc_hash::FxHashMap;
 
 use crate::{line_index::LineIndex, symbol_index::SymbolsDatabase};
@@ -38,6 +40,10 @@ use crate::{line_index::LineIndex, symbol_index::SymbolsDatabase};
 pub struct RootDatabase {
     runtime: salsa::Runtime<RootDatabase>,
     pub(crate) debug_data: Arc<DebugData>,
+    /// Trees of incrementally changed files which `parse` hasn't caught up
+    /// with yet, see `FileLoader::take_pending_reparse`.
+    pub(crate) pending_reparses:
+        Arc<Mutex<FxHashMap<FileId, (Parse<SourceFile>, Vec<AtomTextEdit>)>>>,
     pub last_gc: crate::wasm_shims::Instant,
     pub last_gc_check: crate::wasm_shims::Instant,
 }
@@ -75,6 +81,12 @@ impl FileLoader for RootDatabase {
     ) -> Option<FileId> {
         FileLoaderDelegate(self).resolve_extern_path(extern_id, relative_path)
     }
+    fn take_pending_reparse(
+        &self,
+        file_id: FileId,
+    ) -> Option<(Parse<SourceFile>, Vec<AtomTextEdit>)> {
+        self.pending_reparses.lock().unwrap().remove(&file_id)
+    }
 }
 
 impl salsa::Database for RootDatabase {
@@ -111,6 +123,7 @@ impl RootDatabase {
             last_gc: crate::wasm_shims::Instant::now(),
             last_gc_check: crate::wasm_shims::Instant::now(),
             debug_data: Default::default(),
+            pending_reparses: Default::default(),
         };
         db.set_crate_graph_with_durability(Default::default(), Durability::HIGH);
         db.set_local_roots_with_durability(Default::default(), Durability::HIGH);
@@ -119,6 +132,15 @@ impl RootDatabase {
         db
     }
 
+    /// The path of the file, as far as it is known, for debug output.
+    pub fn debug_file_path(&self, file_id: FileId) -> String {
+        let path = self.file_relative_path(file_id);
+        match self.debug_data.root_paths.get(&self.file_source_root(file_id)) {
+            Some(root_path) => format!("{}/{}", root_path, path),
+            None => path.to_string(),
+        }
+    }
+
     pub fn update_lru_capacity(&mut self, lru_capacity: Option<usize>) {
         let lru_capacity = lru_capacity.unwrap_or(ra_db::DEFAULT_LRU_CAP);
         self.query_mut(ra_db::ParseQuery).set_lru_capacity(lru_capacity);
@@ -134,6 +156,7 @@ impl salsa::ParallelDatabase for RootDatabase {
             last_gc: self.last_gc,
             last_gc_check: self.last_gc_check,
             debug_data: Arc::clone(&self.debug_data),
+            pending_reparses: Arc::clone(&self.pending_reparses),
         })
     }
 }
@@ -165,6 +165,12 @@ impl MacroRules {
         mbe_expander::expand(self, &tt)
     }
 
+    /// The index of the rule that `tt` matches, or the one that comes closest
+    /// if none matches completely.
+    pub fn matching_rule(&self, tt: &tt::Subtree) -> Option<usize> {
+        mbe_expander::matching_rule(self, tt)
+    }
+
     pub fn map_id_down(&self, id: tt::TokenId) -> tt::TokenId {
         self.shift.shift(id)
     }
@@ -14,6 +14,27 @@ pub(crate) fn expand(rules: &crate::MacroRules, input: &tt::Subtree) -> ExpandRe
     expand_rules(&rules.rules, input)
 }
 
+/// Finds the rule which `input` matches. If there's no exact match, picks the
+/// one which gets furthest, like `expand` does, so that incomplete input still
+/// gets a rule.
+pub(crate) fn matching_rule(rules: &crate::MacroRules, input: &tt::Subtree) -> Option<usize> {
+    let mut best: Option<(usize, (usize, usize))> = None;
+    for (idx, rule) in rules.rules.iter().enumerate() {
+        let match_ = match matcher::match_(&rule.lhs, input) {
+            Ok(m) => m,
+            Err(_e) => continue,
+        };
+        if match_.err.is_none() {
+            return Some(idx);
+        }
+        let score = (match_.unmatched_tts, match_.err_count);
+        if best.map_or(true, |(_, best_score)| score < best_score) {
+            best = Some((idx, score));
+        }
+    }
+    best.map(|(idx, _)| idx)
+}
+
 fn expand_rules(rules: &[crate::Rule], input: &tt::Subtree) -> ExpandResult<tt::Subtree> {
     let mut match_: Option<(matcher::Match, &crate::Rule)> = None;
     for rule in rules {
@@ -306,6 +306,23 @@ fn test_fail_match_pattern_by_first_token() {
     .assert_expand_items("foo! { + Baz }", "struct Baz ;");
 }
 
+#[test]
+fn test_matching_rule() {
+    let macro_fixture = parse_macro(
+        r#"
+        macro_rules! foo {
+            ($ i:ident) => ();
+            ($ a:expr, $ b:expr) => ();
+            (+ $ i:ident) => ()
+        }
+"#,
+    );
+    assert_eq!(macro_fixture.matching_rule("foo!(foo)"), Some(0));
+    assert_eq!(macro_fixture.matching_rule("foo!(+ Baz)"), Some(2));
+    // Incomplete input still picks the rule which gets furthest.
+    assert_eq!(macro_fixture.matching_rule("foo!(1, )"), Some(1));
+}
+
 #[test]
 fn test_fail_match_pattern_by_last_token() {
     parse_macro(
@@ -1585,6 +1602,14 @@ impl MacroFixture {
         self.rules.expand(&invocation_tt).result()
     }
 
+    fn matching_rule(&self, invocation: &str) -> Option<usize> {
+        let source_file = ast::SourceFile::parse(invocation).tree();
+        let macro_invocation =
+            source_file.syntax().descendants().find_map(ast::MacroCall::cast).unwrap();
+        let (invocation_tt, _) = ast_to_token_tree(&macro_invocation.token_tree().unwrap())?;
+        self.rules.matching_rule(&invocation_tt)
+    }
+
     fn assert_expand_err(&self, invocation: &str, err: &ExpandError) {
         assert_eq!(self.try_expand_tt(invocation).as_ref(), Err(err));
     }
@@ -242,6 +242,16 @@ impl ProjectWorkspace {
         }
     }
 
+    /// The root files of the sysroot crates, like `libcore/lib.rs`.
+    pub fn sysroot_crate_roots(&self) -> Vec<PathBuf> {
+        match self {
+            ProjectWorkspace::Json { .. } => Vec::new(),
+            ProjectWorkspace::Cargo { cargo: _, sysroot } => {
+                sysroot.crates().map(|krate| sysroot[krate].root.clone()).collect()
+            }
+        }
+    }
+
     pub fn n_packages(&self) -> usize {
         match self {
             ProjectWorkspace::Json { project } => project.crates.len(),
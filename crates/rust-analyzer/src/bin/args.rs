@@ -45,6 +45,12 @@ pub(crate) enum Command {
         /// this would include the parser test files.
         all: bool,
     },
+    CrateGraph {
+        path: PathBuf,
+        hide_sysroot: bool,
+        load_output_dirs: bool,
+        with_proc_macro: bool,
+    },
     ProcMacro,
     RunServer,
     Version,
@@ -269,6 +275,42 @@ ARGS:
 
                 Command::Diagnostics { path, load_output_dirs, with_proc_macro, all }
             }
+            "crate-graph" => {
+                if matches.contains(["-h", "--help"]) {
+                    eprintln!(
+                        "\
+rust-analyzer crate-graph
+
+Prints the crate graph in the Graphviz DOT format.
+
+USAGE:
+    rust-analyzer crate-graph [FLAGS] [PATH]
+
+FLAGS:
+    -h, --help              Prints help information
+        --hide-sysroot      Leave out the crates of the sysroot, like `core` and `std`
+        --load-output-dirs  Load OUT_DIR values by running `cargo check` before analysis
+        --with-proc-macro   Use ra-proc-macro-srv for proc-macro expanding
+
+ARGS:
+    <PATH>"
+                    );
+                    return Ok(Err(HelpPrinted));
+                }
+
+                let hide_sysroot = matches.contains("--hide-sysroot");
+                let load_output_dirs = matches.contains("--load-output-dirs");
+                let with_proc_macro = matches.contains("--with-proc-macro");
+                let path = {
+                    let mut trailing = matches.free()?;
+                    if trailing.len() != 1 {
+                        bail!("Invalid flags");
+                    }
+                    trailing.pop().unwrap().into()
+                };
+
+                Command::CrateGraph { path, hide_sysroot, load_output_dirs, with_proc_macro }
+            }
             "proc-macro" => Command::ProcMacro,
             _ => {
                 print_subcommands();
@@ -293,6 +335,7 @@ FLAGS:
 SUBCOMMANDS:
     analysis-bench
     analysis-stats
+    crate-graph
     highlight
     diagnostics
     proc-macro
@@ -3,7 +3,7 @@
 //! Based on cli flags, either spawns an LSP server, or runs a batch analysis
 mod args;
 
-use lsp_server::Connection;
+use lsp_server::{Connection, Message, Response};
 use rust_analyzer::{cli, config::Config, from_json, Result};
 
 use crate::args::HelpPrinted;
@@ -51,6 +51,10 @@ fn main() -> Result<()> {
             cli::diagnostics(path.as_ref(), load_output_dirs, with_proc_macro, all)?
         }
 
+        args::Command::CrateGraph { path, hide_sysroot, load_output_dirs, with_proc_macro } => {
+            cli::crate_graph(path.as_ref(), hide_sysroot, load_output_dirs, with_proc_macro)?
+        }
+
         args::Command::ProcMacro => run_proc_macro_srv()?,
         args::Command::RunServer => run_server()?,
         args::Command::Version => println!("rust-analyzer {}", env!("REV")),
@@ -74,9 +78,13 @@ fn run_server() -> Result<()> {
     log::info!("lifecycle: server started");
 
     let (connection, io_threads) = Connection::stdio();
-    let server_capabilities = serde_json::to_value(rust_analyzer::server_capabilities()).unwrap();
 
-    let initialize_params = connection.initialize(server_capabilities)?;
+    // Some server capabilities depend on the settings and on the client's
+    // capabilities, so we answer the `initialize` request ourselves.
+    let (initialize_id, initialize_params) = match connection.receiver.recv()? {
+        Message::Request(req) if req.method == "initialize" => (req.id, req.params),
+        msg => return Err(format!("expected initialize request, got {:?}", msg).into()),
+    };
     let initialize_params =
         from_json::<lsp_types::InitializeParams>("InitializeParams", initialize_params)?;
 
@@ -95,16 +103,27 @@ fn run_server() -> Result<()> {
         .filter(|workspaces| !workspaces.is_empty())
         .unwrap_or_else(|| vec![root]);
 
-    let config = {
-        let mut config = Config::default();
-        if let Some(value) = &initialize_params.initialization_options {
-            config.update(value);
-        }
-        if let Some(caps) = &initialize_params.capabilities.text_document {
-            config.update_caps(caps);
-        }
-        config
+    let mut config = Config::default();
+    let config_errors = match &initialize_params.initialization_options {
+        Some(value) => config.update(value),
+        None => Vec::new(),
     };
+    if let Some(caps) = &initialize_params.capabilities.text_document {
+        config.update_caps(caps);
+    }
+    if let Some(caps) = &initialize_params.capabilities.experimental {
+        config.update_experimental_caps(caps);
+    }
+
+    let initialize_result =
+        serde_json::json!({ "capabilities": rust_analyzer::server_capabilities_json(&config) });
+    connection.sender.send(Response::new_ok(initialize_id, initialize_result).into()).unwrap();
+    match connection.receiver.recv()? {
+        Message::Notification(not) if not.method == "initialized" => (),
+        msg => return Err(format!("expected initialized notification, got {:?}", msg).into()),
+    }
+    // Messages to the client have to wait until it's initialized.
+    rust_analyzer::show_config_errors(&config_errors, &connection.sender);
 
     rust_analyzer::main_loop(workspace_roots, config, connection)?;
 
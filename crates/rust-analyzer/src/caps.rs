@@ -1,38 +1,46 @@
 //! Advertizes the capabilities of the LSP Server.
 
-use crate::semantic_tokens;
+use serde_json::json;
+
+use crate::{config::Config, req, semantic_tokens};
 
 use lsp_types::{
     CallHierarchyServerCapability, CodeActionProviderCapability, CodeLensOptions,
-    CompletionOptions, DocumentOnTypeFormattingOptions, FoldingRangeProviderCapability,
-    ImplementationProviderCapability, RenameOptions, RenameProviderCapability, SaveOptions,
-    SelectionRangeProviderCapability, SemanticTokensDocumentProvider, SemanticTokensLegend,
-    SemanticTokensOptions, ServerCapabilities, SignatureHelpOptions, TextDocumentSyncCapability,
-    TextDocumentSyncKind, TextDocumentSyncOptions, TypeDefinitionProviderCapability,
-    WorkDoneProgressOptions,
+    CompletionOptions, DocumentLinkOptions, DocumentOnTypeFormattingOptions, ExecuteCommandOptions,
+    FoldingRangeProviderCapability, ImplementationProviderCapability, RenameOptions,
+    RenameProviderCapability, SaveOptions, SelectionRangeProviderCapability,
+    SemanticTokensDocumentProvider, SemanticTokensLegend, SemanticTokensOptions,
+    ServerCapabilities, SignatureHelpOptions, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextDocumentSyncOptions, TypeDefinitionProviderCapability, WorkDoneProgressOptions,
+    WorkspaceCapability, WorkspaceFolderCapability, WorkspaceFolderCapabilityChangeNotifications,
 };
 
-pub fn server_capabilities() -> ServerCapabilities {
+pub fn server_capabilities(config: &Config) -> ServerCapabilities {
     ServerCapabilities {
         text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
             open_close: Some(true),
-            change: Some(TextDocumentSyncKind::Full),
+            change: Some(TextDocumentSyncKind::Incremental),
             will_save: None,
             will_save_wait_until: None,
             save: Some(SaveOptions::default()),
         })),
         hover_provider: Some(true),
         completion_provider: Some(CompletionOptions {
-            resolve_provider: None,
+            resolve_provider: Some(true),
             trigger_characters: Some(vec![":".to_string(), ".".to_string()]),
             work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
         }),
         signature_help_provider: Some(SignatureHelpOptions {
-            trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
+            trigger_characters: Some(vec![
+                "(".to_string(),
+                ",".to_string(),
+                "<".to_string(),
+                "{".to_string(),
+            ]),
             retrigger_characters: None,
             work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
         }),
-        declaration_provider: None,
+        declaration_provider: Some(true),
         definition_provider: Some(true),
         type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
         implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
@@ -43,7 +51,15 @@ pub fn server_capabilities() -> ServerCapabilities {
         code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
         code_lens_provider: Some(CodeLensOptions { resolve_provider: Some(true) }),
         document_formatting_provider: Some(true),
-        document_range_formatting_provider: None,
+        // Registered dynamically when `rustfmt.enableRangeFormatting` is set,
+        // if the client allows that.
+        document_range_formatting_provider: if config.range_formatting_enabled()
+            && !config.client_caps.range_formatting_dynamic_registration
+        {
+            Some(true)
+        } else {
+            None
+        },
         document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
             first_trigger_character: "=".to_string(),
             more_trigger_character: Some(vec![".".to_string(), ">".to_string()]),
@@ -55,10 +71,23 @@ pub fn server_capabilities() -> ServerCapabilities {
             prepare_provider: Some(true),
             work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
         })),
-        document_link_provider: None,
+        document_link_provider: Some(DocumentLinkOptions {
+            resolve_provider: None,
+            work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
+        }),
         color_provider: None,
-        execute_command_provider: None,
-        workspace: None,
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: req::SERVER_COMMANDS.iter().map(|it| it.to_string()).collect(),
+            work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
+        }),
+        workspace: Some(WorkspaceCapability {
+            workspace_folders: Some(WorkspaceFolderCapability {
+                supported: Some(true),
+                change_notifications: Some(WorkspaceFolderCapabilityChangeNotifications::Bool(
+                    true,
+                )),
+            }),
+        }),
         call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
         semantic_tokens_provider: Some(
             SemanticTokensOptions {
@@ -76,3 +105,20 @@ pub fn server_capabilities() -> ServerCapabilities {
         experimental: Default::default(),
     }
 }
+
+/// Like `server_capabilities`, but also advertises the capabilities which
+/// `lsp-types` can't express yet.
+pub fn server_capabilities_json(config: &Config) -> serde_json::Value {
+    let mut res = serde_json::to_value(server_capabilities(config)).unwrap();
+    // Renaming a file or directory renames the module declared by it.
+    res["workspace"]["fileOperations"] = json!({
+        "willRename": {
+            "filters": [
+                { "scheme": "file", "pattern": { "glob": "**/*.rs", "matches": "file" } },
+                { "scheme": "file", "pattern": { "glob": "**", "matches": "folder" } },
+            ]
+        }
+    });
+    res["typeHierarchyProvider"] = json!(true);
+    res
+}
@@ -57,6 +57,16 @@ impl CargoTargetSpec {
                 }
                 extra_args.push("--nocapture".to_string());
             }
+            RunnableKind::DocTest { test_id } => {
+                args.push("test".to_string());
+                // `--doc` can't be combined with the other target flags.
+                if let Some(spec) = spec {
+                    args.push("--package".to_string());
+                    args.push(spec.package);
+                }
+                args.push("--doc".to_string());
+                extra_args.push(test_id.to_string());
+            }
             RunnableKind::Bin => {
                 args.push("run".to_string());
                 if let Some(spec) = spec {
@@ -67,6 +77,17 @@ impl CargoTargetSpec {
         Ok((args, extra_args))
     }
 
+    /// Whether cargo can run a runnable of `kind` in the target. `cargo test
+    /// --doc` only runs the doctests of the library of a package.
+    pub(crate) fn can_run(spec: Option<&CargoTargetSpec>, kind: &RunnableKind) -> bool {
+        match kind {
+            RunnableKind::DocTest { .. } => {
+                matches!(spec, Some(spec) if spec.target_kind == TargetKind::Lib)
+            }
+            _ => true,
+        }
+    }
+
     pub(crate) fn for_file(
         world: &WorldSnapshot,
         file_id: FileId,
@@ -118,3 +139,37 @@ impl CargoTargetSpec {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ra_ide::{RunnableKind, TestId};
+    use ra_project_model::TargetKind;
+
+    use super::CargoTargetSpec;
+
+    fn spec(target_kind: TargetKind) -> CargoTargetSpec {
+        CargoTargetSpec { package: "foo".to_string(), target: "foo".to_string(), target_kind }
+    }
+
+    #[test]
+    fn doctest_args() {
+        let kind = RunnableKind::DocTest { test_id: TestId::Path("Bar::baz".to_string()) };
+        let (args, extra_args) =
+            CargoTargetSpec::runnable_args(Some(spec(TargetKind::Lib)), &kind).unwrap();
+        assert_eq!(args, ["test", "--package", "foo", "--doc"]);
+        assert_eq!(extra_args, ["Bar::baz"]);
+    }
+
+    #[test]
+    fn doctests_run_only_in_libraries() {
+        let kind = RunnableKind::DocTest { test_id: TestId::Path("Bar::baz".to_string()) };
+        assert!(CargoTargetSpec::can_run(Some(&spec(TargetKind::Lib)), &kind));
+        for &target_kind in
+            [TargetKind::Bin, TargetKind::Example, TargetKind::Test, TargetKind::Bench].iter()
+        {
+            assert!(!CargoTargetSpec::can_run(Some(&spec(target_kind)), &kind));
+        }
+        assert!(!CargoTargetSpec::can_run(None, &kind));
+        assert!(CargoTargetSpec::can_run(Some(&spec(TargetKind::Bin)), &RunnableKind::Bin));
+    }
+}
@@ -4,6 +4,7 @@ mod load_cargo;
 mod analysis_stats;
 mod analysis_bench;
 mod diagnostics;
+mod crate_graph;
 mod progress_report;
 
 use std::io::Read;
@@ -15,6 +16,7 @@ use ra_syntax::{AstNode, SourceFile};
 
 pub use analysis_bench::{analysis_bench, BenchWhat, Position};
 pub use analysis_stats::analysis_stats;
+pub use crate_graph::crate_graph;
 pub use diagnostics::diagnostics;
 
 #[derive(Clone, Copy)]
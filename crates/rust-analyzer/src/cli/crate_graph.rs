@@ -0,0 +1,28 @@
+//! Prints the crate graph of a project in the Graphviz DOT format.
+
+use std::path::Path;
+
+use ra_ide::CrateGraphConfig;
+
+use crate::{
+    cli::{load_cargo::load_cargo_workspace, Result},
+    world::sysroot_crate_roots,
+};
+
+pub fn crate_graph(
+    path: &Path,
+    hide_sysroot: bool,
+    load_output_dirs: bool,
+    with_proc_macro: bool,
+) -> Result<()> {
+    let (host, _roots, ws, vfs) = load_cargo_workspace(path, load_output_dirs, with_proc_macro)?;
+
+    let mut config = CrateGraphConfig::default();
+    if hide_sysroot {
+        config.hidden_roots = sysroot_crate_roots(&[ws], &vfs);
+    }
+
+    let dot = host.analysis().view_crate_graph(&config).unwrap();
+    print!("{}", dot);
+    Ok(())
+}
@@ -27,6 +27,18 @@ pub(crate) fn load_cargo(
     load_out_dirs_from_check: bool,
     with_proc_macro: bool,
 ) -> Result<(AnalysisHost, FxHashMap<SourceRootId, PackageRoot>)> {
+    let (host, source_roots, _ws, _vfs) =
+        load_cargo_workspace(root, load_out_dirs_from_check, with_proc_macro)?;
+    Ok((host, source_roots))
+}
+
+/// Like `load_cargo`, but also returns the loaded workspace and the VFS it was
+/// loaded into.
+pub(crate) fn load_cargo_workspace(
+    root: &Path,
+    load_out_dirs_from_check: bool,
+    with_proc_macro: bool,
+) -> Result<(AnalysisHost, FxHashMap<SourceRootId, PackageRoot>, ProjectWorkspace, Vfs)> {
     let root = std::env::current_dir()?.join(root);
     let root = ProjectRoot::discover_single(&root)?;
     let ws = ProjectWorkspace::load(
@@ -78,13 +90,13 @@ pub(crate) fn load_cargo(
         let path = std::env::current_exe()?;
         ProcMacroClient::extern_process(path, &["proc-macro"]).unwrap()
     };
-    let host = load(&source_roots, ws, &mut vfs, receiver, extern_dirs, &proc_macro_client);
-    Ok((host, source_roots))
+    let host = load(&source_roots, &ws, &mut vfs, receiver, extern_dirs, &proc_macro_client);
+    Ok((host, source_roots, ws, vfs))
 }
 
 pub(crate) fn load(
     source_roots: &FxHashMap<SourceRootId, PackageRoot>,
-    ws: ProjectWorkspace,
+    ws: &ProjectWorkspace,
     vfs: &mut Vfs,
     receiver: Receiver<VfsTask>,
     extern_dirs: FxHashSet<PathBuf>,
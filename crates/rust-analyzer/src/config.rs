@@ -1,26 +1,33 @@
 //! Config used by the language server.
 //!
-//! We currently get this config from `initialize` LSP request, which is not the
-//! best way to do it, but was the simplest thing we could implement.
+//! The initial config comes from the `initializationOptions` of the
+//! `initialize` request. Afterwards, the server pulls the settings with
+//! `workspace/configuration` each time the client notifies us that they have
+//! changed. Settings we don't know about or fail to parse are reported back to
+//! the user, instead of being silently ignored.
 //!
 //! Of particular interest is the `feature_flags` hash map: while other fields
 //! configure the server itself, feature flags are passed into analysis, and
 //! tweak things like automatic insertion of `()` in completions.
 
-use std::{ffi::OsString, path::PathBuf};
+use std::{ffi::OsString, fmt, path::PathBuf};
 
 use lsp_types::TextDocumentClientCapabilities;
 use ra_flycheck::FlycheckConfig;
-use ra_ide::{CompletionConfig, InlayHintsConfig};
+use ra_ide::{CallHierarchyConfig, CompletionConfig, InlayHintsConfig};
 use ra_project_model::CargoConfig;
 use serde::Deserialize;
 
+use crate::vfs_glob::Glob;
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub client_caps: ClientCapsConfig,
 
     pub with_sysroot: bool,
     pub publish_diagnostics: bool,
+    /// Hints for the code disabled by `#[cfg]`, with the reason.
+    pub inactive_code_diagnostics: bool,
     pub lru_capacity: Option<usize>,
     pub proc_macro_srv: Option<(PathBuf, Vec<OsString>)>,
     pub files: FilesConfig,
@@ -33,15 +40,58 @@ pub struct Config {
     pub inlay_hints: InlayHintsConfig,
     pub completion: CompletionConfig,
     pub call_info_full: bool,
+    pub call_hierarchy: CallHierarchyConfig,
+    pub hover_actions: HoverActionsConfig,
 }
 
-#[derive(Debug, Clone)]
+/// Which links to show below hovers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HoverActionsConfig {
+    pub implementations: bool,
+    pub run: bool,
+    pub debug: bool,
+    pub goto_type_def: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FilesConfig {
     pub watcher: FilesWatcher,
     pub exclude: Vec<String>,
+    pub roots: Vec<RootConfig>,
 }
 
-#[derive(Debug, Clone)]
+/// A directory which gets its own source root, with its own filtering rules.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RootConfig {
+    /// Relative paths are resolved against each workspace folder.
+    pub path: PathBuf,
+    #[serde(default)]
+    pub kind: RootKind,
+    /// If not empty, only files matching one of these globs are loaded.
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RootKind {
+    /// Code the user is working on.
+    Local,
+    /// Code the user doesn't edit, like vendored dependencies. It is not
+    /// expected to change, which makes analysis cheaper.
+    Library,
+}
+
+impl Default for RootKind {
+    fn default() -> RootKind {
+        RootKind::Local
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FilesWatcher {
     Client,
     Notify,
@@ -57,6 +107,7 @@ pub struct NotificationsConfig {
 pub enum RustfmtConfig {
     Rustfmt {
         extra_args: Vec<String>,
+        enable_range_formatting: bool,
     },
     #[allow(unused)]
     CustomCommand {
@@ -70,6 +121,12 @@ pub struct ClientCapsConfig {
     pub location_link: bool,
     pub line_folding_only: bool,
     pub hierarchical_symbols: bool,
+    /// Whether the client renders the `actions` of hovers, see
+    /// `req::Hover`.
+    pub hover_actions: bool,
+    /// Whether range formatting can be registered when the user enables it.
+    /// Otherwise, it's advertised statically, if enabled at startup.
+    pub range_formatting_dynamic_registration: bool,
 }
 
 impl Default for Config {
@@ -79,16 +136,24 @@ impl Default for Config {
 
             with_sysroot: true,
             publish_diagnostics: true,
+            inactive_code_diagnostics: false,
             lru_capacity: None,
             proc_macro_srv: None,
-            files: FilesConfig { watcher: FilesWatcher::Notify, exclude: Vec::new() },
+            files: FilesConfig {
+                watcher: FilesWatcher::Notify,
+                exclude: Vec::new(),
+                roots: Vec::new(),
+            },
             notifications: NotificationsConfig {
                 workspace_loaded: true,
                 cargo_toml_not_found: true,
             },
 
             cargo: CargoConfig::default(),
-            rustfmt: RustfmtConfig::Rustfmt { extra_args: Vec::new() },
+            rustfmt: RustfmtConfig::Rustfmt {
+                extra_args: Vec::new(),
+                enable_range_formatting: false,
+            },
             check: Some(FlycheckConfig::CargoCommand {
                 command: "check".to_string(),
                 all_targets: true,
@@ -99,6 +164,10 @@ impl Default for Config {
                 type_hints: true,
                 parameter_hints: true,
                 chaining_hints: true,
+                lifetime_elision_hints: false,
+                closure_return_type_hints: false,
+                closure_capture_hints: false,
+                adjustment_hints: false,
                 max_length: None,
             },
             completion: CompletionConfig {
@@ -108,26 +177,69 @@ impl Default for Config {
                 ..CompletionConfig::default()
             },
             call_info_full: true,
+            call_hierarchy: CallHierarchyConfig { include_trait_impls: false },
+            hover_actions: HoverActionsConfig {
+                implementations: true,
+                run: true,
+                debug: true,
+                goto_type_def: true,
+            },
         }
     }
 }
 
+/// A setting which couldn't be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    Unknown { key: String },
+    Invalid { key: String, message: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Unknown { key } => write!(f, "`{}` is not a known setting", key),
+            ConfigError::Invalid { key, message } => write!(f, "`{}`: {}", key, message),
+        }
+    }
+}
+
+/// Settings which are handled by the client, so the server doesn't read them,
+/// but they are still valid.
+const CLIENT_SETTINGS: &[&str] = &["/updates", "/serverPath", "/trace"];
+
 impl Config {
+    /// Replaces the current settings with the ones in `value`, returning the
+    /// settings which were not recognized or had the wrong type.
     #[rustfmt::skip]
-    pub fn update(&mut self, value: &serde_json::Value) {
+    pub fn update(&mut self, value: &serde_json::Value) -> Vec<ConfigError> {
         log::info!("Config::update({:#})", value);
 
         let client_caps = self.client_caps.clone();
         *self = Default::default();
         self.client_caps = client_caps;
 
+        let value = &mut Settings { json: value, read: Vec::new(), errors: Vec::new() };
+
         set(value, "/withSysroot", &mut self.with_sysroot);
         set(value, "/diagnostics/enable", &mut self.publish_diagnostics);
+        set(value, "/diagnostics/inactiveCode", &mut self.inactive_code_diagnostics);
         set(value, "/lruCapacity", &mut self.lru_capacity);
         self.files.watcher = match get(value, "/files/watcher") {
             Some("client") => FilesWatcher::Client,
-            Some("notify") | _ => FilesWatcher::Notify
+            Some("notify") | None => FilesWatcher::Notify,
+            Some(other) => {
+                value.invalid("/files/watcher", format!("unknown watcher `{}`, expected `client` or `notify`", other));
+                FilesWatcher::Notify
+            }
         };
+        set(value, "/files/exclude", &mut self.files.exclude);
+        set(value, "/files/roots", &mut self.files.roots);
+        retain_valid_globs(value, "/files/exclude", &mut self.files.exclude);
+        for root in self.files.roots.iter_mut() {
+            retain_valid_globs(value, "/files/roots", &mut root.include);
+            retain_valid_globs(value, "/files/roots", &mut root.exclude);
+        }
         set(value, "/notifications/workspaceLoaded", &mut self.notifications.workspace_loaded);
         set(value, "/notifications/cargoTomlNotFound", &mut self.notifications.cargo_toml_not_found);
 
@@ -147,6 +259,8 @@ impl Config {
 
         match get::<Vec<String>>(value, "/rustfmt/overrideCommand") {
             Some(mut args) if !args.is_empty() => {
+                // The other rustfmt settings don't apply, but are still valid.
+                value.read.push("/rustfmt");
                 let command = args.remove(0);
                 self.rustfmt = RustfmtConfig::CustomCommand {
                     command,
@@ -154,20 +268,23 @@ impl Config {
                 }
             }
             _ => {
-                if let RustfmtConfig::Rustfmt { extra_args } = &mut self.rustfmt {
+                if let RustfmtConfig::Rustfmt { extra_args, enable_range_formatting } = &mut self.rustfmt {
                     set(value, "/rustfmt/extraArgs", extra_args);
+                    set(value, "/rustfmt/enableRangeFormatting", enable_range_formatting);
                 }
             }
         };
 
         if let Some(false) = get(value, "/checkOnSave/enable") {
             // check is disabled
+            value.read.push("/checkOnSave");
             self.check = None;
         } else {
             // check is enabled
             match get::<Vec<String>>(value, "/checkOnSave/overrideCommand") {
                 // first see if the user has completely overridden the command
                 Some(mut args) if !args.is_empty() => {
+                    value.read.push("/checkOnSave");
                     let command = args.remove(0);
                     self.check = Some(FlycheckConfig::CustomCommand {
                         command,
@@ -190,22 +307,56 @@ impl Config {
         set(value, "/inlayHints/typeHints", &mut self.inlay_hints.type_hints);
         set(value, "/inlayHints/parameterHints", &mut self.inlay_hints.parameter_hints);
         set(value, "/inlayHints/chainingHints", &mut self.inlay_hints.chaining_hints);
+        set(value, "/inlayHints/lifetimeElisionHints", &mut self.inlay_hints.lifetime_elision_hints);
+        set(value, "/inlayHints/closureReturnTypeHints", &mut self.inlay_hints.closure_return_type_hints);
+        set(value, "/inlayHints/closureCaptureHints", &mut self.inlay_hints.closure_capture_hints);
+        set(value, "/inlayHints/adjustmentHints", &mut self.inlay_hints.adjustment_hints);
         set(value, "/inlayHints/maxLength", &mut self.inlay_hints.max_length);
         set(value, "/completion/postfix/enable", &mut self.completion.enable_postfix_completions);
         set(value, "/completion/addCallParenthesis", &mut self.completion.add_call_parenthesis);
         set(value, "/completion/addCallArgumentSnippets", &mut self.completion.add_call_argument_snippets);
+        set(value, "/completion/resolveLazily", &mut self.completion.resolve_lazily);
         set(value, "/callInfo/full", &mut self.call_info_full);
+        set(value, "/callHierarchy/traitImpls", &mut self.call_hierarchy.include_trait_impls);
+        set(value, "/hoverActions/implementations", &mut self.hover_actions.implementations);
+        set(value, "/hoverActions/run", &mut self.hover_actions.run);
+        set(value, "/hoverActions/debug", &mut self.hover_actions.debug);
+        set(value, "/hoverActions/gotoTypeDef", &mut self.hover_actions.goto_type_def);
+        if let Some(false) = get(value, "/hoverActions/enable") {
+            self.hover_actions = HoverActionsConfig { implementations: false, run: false, debug: false, goto_type_def: false };
+        }
 
         log::info!("Config::update() = {:#?}", self);
 
-        fn get<'a, T: Deserialize<'a>>(value: &'a serde_json::Value, pointer: &str) -> Option<T> {
-            value.pointer(pointer).and_then(|it| T::deserialize(it).ok())
+        value.read.extend(CLIENT_SETTINGS);
+        let mut errors = std::mem::take(&mut value.errors);
+        unknown_settings(value.json, "", &value.read, &mut errors);
+        if !errors.is_empty() {
+            log::warn!("invalid settings: {:?}", errors);
         }
+        errors
+    }
 
-        fn set<'a, T: Deserialize<'a>>(value: &'a serde_json::Value, pointer: &str, slot: &mut T) {
-            if let Some(new_value) = get(value, pointer) {
-                *slot = new_value
-            }
+    /// Whether going from `self` to `new` requires loading the workspaces
+    /// again, rather than just updating the world state.
+    pub(crate) fn requires_reload(&self, new: &Config) -> bool {
+        self.cargo != new.cargo
+            || self.with_sysroot != new.with_sysroot
+            || self.proc_macro_srv != new.proc_macro_srv
+            || self.files != new.files
+    }
+
+    /// Range formatting relies on an unstable rustfmt option, so we only
+    /// advertise it when the user opted into it.
+    pub(crate) fn range_formatting_enabled(&self) -> bool {
+        matches!(self.rustfmt, RustfmtConfig::Rustfmt { enable_range_formatting: true, .. })
+    }
+
+    /// Reads the capabilities of rust-analyzer's own extensions to the
+    /// protocol, which the client lists under `experimental`.
+    pub fn update_experimental_caps(&mut self, caps: &serde_json::Value) {
+        if let Some(value) = caps.get("hoverActions").and_then(|it| it.as_bool()) {
+            self.client_caps.hover_actions = value;
         }
     }
 
@@ -221,6 +372,9 @@ impl Config {
         {
             self.client_caps.hierarchical_symbols = value
         }
+        if let Some(value) = caps.range_formatting.as_ref().and_then(|it| it.dynamic_registration) {
+            self.client_caps.range_formatting_dynamic_registration = value
+        }
         self.completion.allow_snippets(false);
         if let Some(completion) = &caps.completion {
             if let Some(completion_item) = &completion.completion_item {
@@ -231,3 +385,159 @@ impl Config {
         }
     }
 }
+
+/// The settings JSON, together with the bookkeeping needed to report back
+/// what was wrong with it.
+struct Settings<'a> {
+    json: &'a serde_json::Value,
+    read: Vec<&'static str>,
+    errors: Vec<ConfigError>,
+}
+
+impl Settings<'_> {
+    fn invalid(&mut self, pointer: &str, message: String) {
+        self.errors.push(ConfigError::Invalid { key: setting_name(pointer), message })
+    }
+}
+
+fn get<'a, T: Deserialize<'a>>(value: &mut Settings<'a>, pointer: &'static str) -> Option<T> {
+    value.read.push(pointer);
+    // Clients send `null` for settings which are not set.
+    let json = value.json.pointer(pointer).filter(|it| !it.is_null())?;
+    match T::deserialize(json) {
+        Ok(it) => Some(it),
+        Err(e) => {
+            value.invalid(pointer, e.to_string());
+            None
+        }
+    }
+}
+
+fn set<'a, T: Deserialize<'a>>(value: &mut Settings<'a>, pointer: &'static str, slot: &mut T) {
+    if let Some(new_value) = get(value, pointer) {
+        *slot = new_value
+    }
+}
+
+fn retain_valid_globs(value: &mut Settings<'_>, pointer: &str, globs: &mut Vec<String>) {
+    globs.retain(|glob| match Glob::new(glob) {
+        Ok(_) => true,
+        Err(e) => {
+            value.invalid(pointer, format!("invalid glob `{}`: {}", glob, e));
+            false
+        }
+    })
+}
+
+fn unknown_settings(
+    json: &serde_json::Value,
+    pointer: &str,
+    read: &[&str],
+    acc: &mut Vec<ConfigError>,
+) {
+    let object = match json.as_object() {
+        Some(it) => it,
+        None => return,
+    };
+    for (key, value) in object {
+        let pointer = format!("{}/{}", pointer, key);
+        let is_read = |it: &&str| {
+            // Settings can be grouped, like `/checkOnSave/enable`, or read as
+            // a whole, like the `/cargo/features` list.
+            pointer == *it || pointer.starts_with(&format!("{}/", it))
+        };
+        if read.iter().any(is_read) {
+            continue;
+        }
+        if value.is_object() && read.iter().any(|it| it.starts_with(&format!("{}/", pointer))) {
+            unknown_settings(value, &pointer, read, acc);
+        } else {
+            acc.push(ConfigError::Unknown { key: setting_name(&pointer) });
+        }
+    }
+}
+
+/// Turns `/cargo/allFeatures` into `rust-analyzer.cargo.allFeatures`, the way
+/// the user spells it.
+fn setting_name(pointer: &str) -> String {
+    format!("rust-analyzer{}", pointer.replace('/', "."))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn reports_unknown_and_invalid_settings() {
+        let mut config = Config::default();
+        let errors = config.update(&json!({
+            "cargo": { "allFeatures": true, "feature": ["foo"] },
+            "checkOnSave": { "enable": "yes" },
+            "lruCapacity": null,
+            "trace": { "server": "verbose" },
+            "inlayHints": { "maxLength": 20 },
+            "nonsense": 92,
+        }));
+
+        assert!(config.cargo.all_features);
+        assert!(config.check.is_some());
+        assert_eq!(config.inlay_hints.max_length, Some(20));
+
+        let errors = errors.iter().map(|it| it.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                "`rust-analyzer.checkOnSave.enable`: invalid type: string \"yes\", expected a boolean",
+                "`rust-analyzer.cargo.feature` is not a known setting",
+                "`rust-analyzer.nonsense` is not a known setting",
+            ]
+        );
+    }
+
+    #[test]
+    fn settings_changes_which_require_reload() {
+        let mut new = Config::default();
+        new.update(&json!({ "inlayHints": { "typeHints": false } }));
+        assert!(!Config::default().requires_reload(&new));
+
+        new.update(&json!({ "cargo": { "noDefaultFeatures": true } }));
+        assert!(Config::default().requires_reload(&new));
+    }
+
+    #[test]
+    fn reads_file_roots() {
+        let mut config = Config::default();
+        let errors = config.update(&json!({
+            "files": {
+                "exclude": ["[", "docs/**"],
+                "roots": [
+                    { "path": "vendor", "kind": "library" },
+                    { "path": "/abs/gen", "include": ["**/*_gen.rs"], "exclude": ["{"] },
+                ],
+            },
+        }));
+
+        assert_eq!(config.files.exclude, vec!["docs/**".to_string()]);
+        assert_eq!(
+            config.files.roots,
+            vec![
+                RootConfig {
+                    path: "vendor".into(),
+                    kind: RootKind::Library,
+                    include: Vec::new(),
+                    exclude: Vec::new(),
+                },
+                RootConfig {
+                    path: "/abs/gen".into(),
+                    kind: RootKind::Local,
+                    include: vec!["**/*_gen.rs".to_string()],
+                    exclude: Vec::new(),
+                },
+            ]
+        );
+        assert_eq!(errors.len(), 2);
+        assert!(Config::default().requires_reload(&config));
+    }
+}
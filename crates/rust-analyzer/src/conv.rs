@@ -20,13 +20,16 @@ use ra_vfs::LineEndings;
 
 use crate::{
     req,
-    semantic_tokens::{self, ModifierSet, CONSTANT, CONTROL_FLOW, MUTABLE, UNSAFE},
+    semantic_tokens::{
+        self, ModifierSet, ATTRIBUTE_MODIFIER, CALLABLE, CONSTANT, CONSUMING, CONTROL_FLOW,
+        LIBRARY, MUTABLE, UNSAFE,
+    },
     world::WorldSnapshot,
     Result,
 };
 use semantic_tokens::{
-    ATTRIBUTE, BUILTIN_TYPE, ENUM_MEMBER, FORMAT_SPECIFIER, LIFETIME, TYPE_ALIAS, UNION,
-    UNRESOLVED_REFERENCE,
+    ATTRIBUTE, BUILTIN_TYPE, ENUM_MEMBER, FORMAT_SPECIFIER, INACTIVE_CODE, LIFETIME, TYPE_ALIAS,
+    UNION, UNRESOLVED_REFERENCE,
 };
 
 pub trait Conv {
@@ -341,6 +344,10 @@ impl ConvWith<&LineIndex> for InlayHint {
                 InlayKind::ParameterHint => req::InlayKind::ParameterHint,
                 InlayKind::TypeHint => req::InlayKind::TypeHint,
                 InlayKind::ChainingHint => req::InlayKind::ChainingHint,
+                InlayKind::LifetimeHint => req::InlayKind::LifetimeHint,
+                InlayKind::ClosureReturnTypeHint => req::InlayKind::ClosureReturnTypeHint,
+                InlayKind::ClosureCaptureHint => req::InlayKind::ClosureCaptureHint,
+                InlayKind::AdjustmentHint => req::InlayKind::AdjustmentHint,
             },
         }
     }
@@ -381,8 +388,10 @@ impl Conv for Highlight {
             HighlightTag::Comment => SemanticTokenType::COMMENT,
             HighlightTag::Attribute => ATTRIBUTE,
             HighlightTag::Keyword => SemanticTokenType::KEYWORD,
+            HighlightTag::Operator => SemanticTokenType::OPERATOR,
             HighlightTag::UnresolvedReference => UNRESOLVED_REFERENCE,
             HighlightTag::FormatSpecifier => FORMAT_SPECIFIER,
+            HighlightTag::Inactive => INACTIVE_CODE,
         };
 
         for modifier in self.modifiers.iter() {
@@ -391,6 +400,11 @@ impl Conv for Highlight {
                 HighlightModifier::ControlFlow => CONTROL_FLOW,
                 HighlightModifier::Mutable => MUTABLE,
                 HighlightModifier::Unsafe => UNSAFE,
+                HighlightModifier::Consuming => CONSUMING,
+                HighlightModifier::Callable => CALLABLE,
+                HighlightModifier::Library => LIBRARY,
+                HighlightModifier::Attribute => ATTRIBUTE_MODIFIER,
+                HighlightModifier::Documentation => SemanticTokenModifier::DOCUMENTATION,
             };
             mods |= modifier;
         }
@@ -619,6 +633,21 @@ pub fn to_call_hierarchy_item(
     })
 }
 
+pub fn to_type_hierarchy_item(
+    world: &WorldSnapshot,
+    nav: NavigationTarget,
+) -> Result<req::TypeHierarchyItem> {
+    let line_index = world.analysis().file_line_index(nav.file_id())?;
+    Ok(req::TypeHierarchyItem {
+        name: nav.name().to_string(),
+        kind: nav.kind().conv(),
+        detail: nav.description().map(|it| it.to_string()),
+        uri: nav.file_id().try_conv_with(&world)?,
+        range: nav.full_range().conv_with(&line_index),
+        selection_range: nav.range().conv_with(&line_index),
+    })
+}
+
 pub fn to_location(
     file_id: FileId,
     range: TextRange,
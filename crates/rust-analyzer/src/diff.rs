@@ -0,0 +1,187 @@
+//! Computes a `TextEdit` which turns one text into another.
+//!
+//! This is used to map the output of external tools, like `rustfmt`, back to
+//! edits which touch only the changed parts of the document. Replacing the
+//! whole document instead moves the cursor and destroys editor state like
+//! folds and bookmarks.
+
+use std::ops::Range;
+
+use ra_syntax::{TextRange, TextSize};
+use ra_text_edit::{TextEdit, TextEditBuilder};
+
+/// Past this many `left lines * right lines`, we don't look for the common
+/// lines inside of the changed part, and replace it as a whole.
+const MAX_LCS_CELLS: usize = 1_000_000;
+
+pub(crate) fn diff(left: &str, right: &str) -> TextEdit {
+    let left_lines = lines(left);
+    let right_lines = lines(right);
+    let left_starts = line_starts(&left_lines);
+    let right_starts = line_starts(&right_lines);
+
+    let mut builder = TextEditBuilder::default();
+    for (left_hunk, right_hunk) in changed_hunks(&left_lines, &right_lines) {
+        let offset = left_starts[left_hunk.start];
+        let deleted = &left[offset..left_starts[left_hunk.end]];
+        let inserted = &right[right_starts[right_hunk.start]..right_starts[right_hunk.end]];
+        // Shrink the hunk to the changed part, so that `fn f(){}` ->
+        // `fn f() {}` is a single insertion.
+        let prefix = common_prefix_len(deleted, inserted);
+        // Don't let the suffix overlap with the prefix, so that
+        // `aa -> aaa` is a single insertion.
+        let suffix = common_suffix_len(&deleted[prefix..], &inserted[prefix..]);
+        let range = TextRange::new(
+            TextSize::from((offset + prefix) as u32),
+            TextSize::from((offset + deleted.len() - suffix) as u32),
+        );
+        builder.replace(range, inserted[prefix..inserted.len() - suffix].to_string());
+    }
+    builder.finish()
+}
+
+/// Splits the text into lines, keeping the line terminators.
+fn lines(text: &str) -> Vec<&str> {
+    let mut res = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = rest.find('\n').map_or(rest.len(), |it| it + 1);
+        res.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    res
+}
+
+/// The offset of each line, followed by the length of the text.
+fn line_starts(lines: &[&str]) -> Vec<usize> {
+    let mut res = vec![0];
+    let mut offset = 0;
+    for line in lines {
+        offset += line.len();
+        res.push(offset);
+    }
+    res
+}
+
+/// Returns the line ranges which differ between `left` and `right`, keeping
+/// the longest common subsequence of lines intact.
+fn changed_hunks(left: &[&str], right: &[&str]) -> Vec<(Range<usize>, Range<usize>)> {
+    let prefix = left.iter().zip(right).take_while(|(l, r)| l == r).count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count();
+    let left = &left[prefix..left.len() - suffix];
+    let right = &right[prefix..right.len() - suffix];
+    let (n, m) = (left.len(), right.len());
+    if n == 0 && m == 0 {
+        return Vec::new();
+    }
+    if n.saturating_mul(m) > MAX_LCS_CELLS {
+        return vec![(prefix..prefix + n, prefix..prefix + m)];
+    }
+
+    // `lcs[i][j]` is the length of the longest common subsequence of
+    // `left[i..]` and `right[j..]`.
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if left[i] == right[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut res = Vec::new();
+    let mut hunk_start = None;
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && left[i] == right[j] {
+            if let Some((start_i, start_j)) = hunk_start.take() {
+                res.push((prefix + start_i..prefix + i, prefix + start_j..prefix + j));
+            }
+            i += 1;
+            j += 1;
+            continue;
+        }
+        hunk_start.get_or_insert((i, j));
+        if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            j += 1;
+        } else {
+            i += 1;
+        }
+    }
+    if let Some((start_i, start_j)) = hunk_start {
+        res.push((prefix + start_i..prefix + n, prefix + start_j..prefix + m));
+    }
+    res
+}
+
+fn common_prefix_len(left: &str, right: &str) -> usize {
+    left.char_indices()
+        .zip(right.chars())
+        .find(|((_, l), r)| l != r)
+        .map_or(left.len().min(right.len()), |((idx, _), _)| idx)
+}
+
+fn common_suffix_len(left: &str, right: &str) -> usize {
+    left.chars()
+        .rev()
+        .zip(right.chars().rev())
+        .take_while(|(l, r)| l == r)
+        .map(|(l, _)| l.len_utf8())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff;
+
+    fn check(left: &str, right: &str) {
+        let edit = diff(left, right);
+        let actual = edit.apply(left);
+        assert_eq!(actual, right);
+    }
+
+    #[test]
+    fn diff_applies() {
+        check("", "");
+        check("fn main() {}", "fn main() {}");
+        check("", "fn main() {}\n");
+        check("fn main() {}\n", "");
+        check("fn main(){}", "fn main() {}");
+        check("aa", "aaa");
+        check("aaa", "aa");
+        check("fn f() { \"❤️\" }", "fn f() {\n    \"❤\"\n}\n");
+        check("ăâ", "ăî");
+        check("a\nb\nc\n", "c\nb\na\n");
+        check("a\n\nb\n\n", "\na\nb\n");
+        check("fn f() {\n}\nfn g() {\n}", "fn f() {}\n\nfn g() {}\n");
+    }
+
+    #[test]
+    fn diff_is_minimal() {
+        let edit = diff("fn main(){ 92 }", "fn main() { 92 }");
+        let atoms = edit.as_atoms();
+        assert_eq!(atoms.len(), 1);
+        assert_eq!(atoms[0].delete, ra_syntax::TextRange::empty(9.into()));
+        assert_eq!(atoms[0].insert, " ");
+    }
+
+    #[test]
+    fn diff_keeps_unchanged_lines() {
+        let left = "fn f(){}\nfn g() {}\nfn h(){}\n";
+        let right = "fn f() {}\nfn g() {}\nfn h() {}\n";
+        let edit = diff(left, right);
+        let atoms = edit.as_atoms();
+        assert_eq!(atoms.len(), 2);
+        assert_eq!(atoms[0].delete, ra_syntax::TextRange::empty(6.into()));
+        assert_eq!(atoms[0].insert, " ");
+        assert_eq!(atoms[1].delete, ra_syntax::TextRange::empty(25.into()));
+        assert_eq!(atoms[1].insert, " ");
+    }
+}
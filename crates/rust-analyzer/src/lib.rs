@@ -21,6 +21,7 @@ mod vfs_glob;
 mod caps;
 mod cargo_target_spec;
 mod conv;
+mod diff;
 mod main_loop;
 mod markdown;
 pub mod req;
@@ -33,9 +34,9 @@ use serde::de::DeserializeOwned;
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 pub use crate::{
-    caps::server_capabilities,
+    caps::{server_capabilities, server_capabilities_json},
     main_loop::LspError,
-    main_loop::{main_loop, show_message},
+    main_loop::{main_loop, show_config_errors, show_message},
 };
 
 pub fn from_json<T: DeserializeOwned>(what: &'static str, json: serde_json::Value) -> Result<T> {
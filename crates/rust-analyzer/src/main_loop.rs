@@ -4,42 +4,46 @@
 mod handlers;
 mod subscriptions;
 pub(crate) mod pending_requests;
+pub(crate) mod completion_resolve;
 
 use std::{
     env,
     error::Error,
     fmt, panic,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::Arc,
     time::{Duration, Instant},
 };
 
-use crossbeam_channel::{never, select, unbounded, RecvError, Sender};
+use crossbeam_channel::{after, never, select, unbounded, RecvError, Sender};
 use itertools::Itertools;
 use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
 use lsp_types::{
-    NumberOrString, WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressCreateParams,
-    WorkDoneProgressEnd, WorkDoneProgressReport,
+    DidChangeTextDocumentParams, DocumentFilter, NumberOrString, TextDocumentContentChangeEvent,
+    TextDocumentRegistrationOptions, WorkDoneProgress, WorkDoneProgressBegin,
+    WorkDoneProgressCreateParams, WorkDoneProgressEnd, WorkDoneProgressReport,
 };
 use ra_flycheck::{url_from_path_with_drive_lowercasing, CheckTask};
-use ra_ide::{Canceled, FileId, LibraryData, SourceRootId};
+use ra_ide::{Canceled, FileId, LibraryData, LineIndex, SourceRootId};
 use ra_prof::profile;
-use ra_project_model::{PackageRoot, ProjectWorkspace};
+use ra_project_model::{ProjectRoot, ProjectWorkspace};
+use ra_text_edit::AtomTextEdit;
 use ra_vfs::{VfsFile, VfsTask, Watch};
 use relative_path::RelativePathBuf;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{de::DeserializeOwned, Serialize};
 use threadpool::ThreadPool;
 
 use crate::{
-    config::{Config, FilesWatcher},
+    config::{Config, ConfigError, FilesWatcher},
+    conv::ConvWith,
     diagnostics::DiagnosticTask,
     main_loop::{
         pending_requests::{PendingRequest, PendingRequests},
         subscriptions::Subscriptions,
     },
     req,
-    world::{WorldSnapshot, WorldState},
+    world::{custom_roots, WorldSnapshot, WorldState},
     Result,
 };
 
@@ -88,93 +92,19 @@ pub fn main_loop(ws_roots: Vec<PathBuf>, config: Config, connection: Connection)
     }
 
     let mut loop_state = LoopState::default();
-    let mut world_state = {
-        let workspaces = {
-            // FIXME: support dynamic workspace loading.
-            let mut visited = FxHashSet::default();
-            let project_roots = ws_roots
-                .iter()
-                .filter_map(|it| ra_project_model::ProjectRoot::discover(it).ok())
-                .flatten()
-                .filter(|it| visited.insert(it.clone()))
-                .collect::<Vec<_>>();
-
-            if project_roots.is_empty() && config.notifications.cargo_toml_not_found {
-                show_message(
-                        req::MessageType::Error,
-                        format!(
-                            "rust-analyzer failed to discover workspace, no Cargo.toml found, dirs searched: {}",
-                            ws_roots.iter().format_with(", ", |it, f| f(&it.display()))
-                        ),
-                        &connection.sender,
-                    );
-            };
-
-            project_roots
-                .into_iter()
-                .filter_map(|root| {
-                    ra_project_model::ProjectWorkspace::load(
-                        root,
-                        &config.cargo,
-                        config.with_sysroot,
-                    )
-                    .map_err(|err| {
-                        log::error!("failed to load workspace: {:#}", err);
-                        show_message(
-                            req::MessageType::Error,
-                            format!("rust-analyzer failed to load workspace: {:#}", err),
-                            &connection.sender,
-                        );
-                    })
-                    .ok()
-                })
-                .collect::<Vec<_>>()
-        };
-
-        let globs = config
-            .files
-            .exclude
-            .iter()
-            .map(|glob| crate::vfs_glob::Glob::new(glob))
-            .collect::<std::result::Result<Vec<_>, _>>()?;
-
-        if let FilesWatcher::Client = config.files.watcher {
-            let registration_options = req::DidChangeWatchedFilesRegistrationOptions {
-                watchers: workspaces
-                    .iter()
-                    .flat_map(ProjectWorkspace::to_roots)
-                    .filter(PackageRoot::is_member)
-                    .map(|root| format!("{}/**/*.rs", root.path().display()))
-                    .map(|glob_pattern| req::FileSystemWatcher { glob_pattern, kind: None })
-                    .collect(),
-            };
-            let registration = req::Registration {
-                id: "file-watcher".to_string(),
-                method: "workspace/didChangeWatchedFiles".to_string(),
-                register_options: Some(serde_json::to_value(registration_options).unwrap()),
-            };
-            let params = req::RegistrationParams { registrations: vec![registration] };
-            let request =
-                request_new::<req::RegisterCapability>(loop_state.next_request_id(), params);
-            connection.sender.send(request.into()).unwrap();
-        }
-
-        WorldState::new(
-            ws_roots,
-            workspaces,
-            config.lru_capacity,
-            &globs,
-            Watch(matches!(config.files.watcher, FilesWatcher::Notify)),
-            config,
-        )
-    };
-
-    loop_state.roots_total = world_state.vfs.read().n_roots();
-    loop_state.roots_scanned = 0;
+    let mut world_state = load_world_state(ws_roots, config, &mut loop_state, &connection.sender)?;
+    loop_state.start_loading(&world_state);
+    // Without dynamic registration, range formatting was advertised in the
+    // server capabilities, if at all.
+    if world_state.config.client_caps.range_formatting_dynamic_registration
+        && world_state.config.range_formatting_enabled()
+    {
+        register_range_formatting(&mut loop_state, &connection.sender);
+    }
 
     let pool = ThreadPool::default();
     let (task_sender, task_receiver) = unbounded::<Task>();
-    let (libdata_sender, libdata_receiver) = unbounded::<LibraryData>();
+    let (libdata_sender, libdata_receiver) = unbounded::<(u32, LibraryData)>();
 
     log::info!("server initialized, serving requests");
     {
@@ -182,6 +112,10 @@ pub fn main_loop(ws_roots: Vec<PathBuf>, config: Config, connection: Connection)
         let libdata_sender = libdata_sender;
         loop {
             log::trace!("selecting");
+            let vfs_flush = match loop_state.vfs_flush_deadline() {
+                Some(deadline) => after(deadline.saturating_duration_since(Instant::now())),
+                None => never(),
+            };
             let event = select! {
                 recv(&connection.receiver) -> msg => match msg {
                     Ok(msg) => Event::Msg(msg),
@@ -192,11 +126,15 @@ pub fn main_loop(ws_roots: Vec<PathBuf>, config: Config, connection: Connection)
                     Ok(task) => Event::Vfs(task),
                     Err(RecvError) => return Err("vfs died".into()),
                 },
-                recv(libdata_receiver) -> data => Event::Lib(data.unwrap()),
+                recv(libdata_receiver) -> data => {
+                    let (generation, data) = data.unwrap();
+                    Event::Lib(generation, data)
+                },
                 recv(world_state.flycheck.as_ref().map_or(&never(), |it| &it.task_recv)) -> task => match task {
                     Ok(task) => Event::CheckWatcher(task),
                     Err(RecvError) => return Err("check watcher died".into()),
-                }
+                },
+                recv(vfs_flush) -> _ => Event::FlushVfs,
             };
             if let Event::Msg(Message::Request(req)) = &event {
                 if connection.handle_shutdown(&req)? {
@@ -216,9 +154,9 @@ pub fn main_loop(ws_roots: Vec<PathBuf>, config: Config, connection: Connection)
     }
     world_state.analysis_host.request_cancellation();
     log::info!("waiting for tasks to finish...");
-    task_receiver.into_iter().for_each(|task| {
-        on_task(task, &connection.sender, &mut loop_state.pending_requests, &mut world_state)
-    });
+    task_receiver
+        .into_iter()
+        .for_each(|task| on_task(task, &connection.sender, &mut loop_state, &mut world_state));
     libdata_receiver.into_iter().for_each(drop);
     log::info!("...tasks have finished");
     log::info!("joining threadpool...");
@@ -232,18 +170,391 @@ pub fn main_loop(ws_roots: Vec<PathBuf>, config: Config, connection: Connection)
     Ok(())
 }
 
+fn load_world_state(
+    ws_roots: Vec<PathBuf>,
+    config: Config,
+    loop_state: &mut LoopState,
+    sender: &Sender<Message>,
+) -> Result<WorldState> {
+    let workspaces = load_workspaces(&ws_roots, &config, loop_state, sender);
+    let globs = exclude_globs(&config)?;
+    register_file_watchers(&ws_roots, &workspaces, &config, loop_state, sender);
+    Ok(WorldState::new(
+        ws_roots,
+        workspaces,
+        config.lru_capacity,
+        &globs,
+        Watch(matches!(config.files.watcher, FilesWatcher::Notify)),
+        config,
+    ))
+}
+
+/// Discovers the projects in `ws_roots`. The ones which are already loaded are
+/// reused, so `cargo metadata` only runs for the new ones.
+fn load_workspaces(
+    ws_roots: &[PathBuf],
+    config: &Config,
+    loop_state: &mut LoopState,
+    sender: &Sender<Message>,
+) -> Vec<ProjectWorkspace> {
+    let reusable = std::mem::take(&mut loop_state.loaded_workspaces);
+    let workspaces = load_project_workspaces(ws_roots, config, reusable, sender);
+    loop_state.loaded_workspaces = workspaces.iter().cloned().collect();
+    workspaces.into_iter().map(|(_, workspace)| workspace).collect::<Vec<_>>()
+}
+
+/// Discovers the projects in `ws_roots` and loads the ones which aren't in
+/// `reusable`. This doesn't touch the loop state, so it can run on the thread
+/// pool.
+fn load_project_workspaces(
+    ws_roots: &[PathBuf],
+    config: &Config,
+    mut reusable: FxHashMap<ProjectRoot, ProjectWorkspace>,
+    sender: &Sender<Message>,
+) -> Vec<(ProjectRoot, ProjectWorkspace)> {
+    let mut visited = FxHashSet::default();
+    let project_roots = ws_roots
+        .iter()
+        .filter_map(|it| ProjectRoot::discover(it).ok())
+        .flatten()
+        .filter(|it| visited.insert(it.clone()))
+        .collect::<Vec<_>>();
+
+    if project_roots.is_empty() && config.notifications.cargo_toml_not_found {
+        show_message(
+            req::MessageType::Error,
+            format!(
+                "rust-analyzer failed to discover workspace, no Cargo.toml found, dirs searched: {}",
+                ws_roots.iter().format_with(", ", |it, f| f(&it.display()))
+            ),
+            sender,
+        );
+    };
+
+    project_roots
+        .into_iter()
+        .filter_map(|root| {
+            if let Some(workspace) = reusable.remove(&root) {
+                return Some((root, workspace));
+            }
+            ProjectWorkspace::load(root.clone(), &config.cargo, config.with_sysroot)
+                .map_err(|err| {
+                    log::error!("failed to load workspace: {:#}", err);
+                    show_message(
+                        req::MessageType::Error,
+                        format!("rust-analyzer failed to load workspace: {:#}", err),
+                        sender,
+                    );
+                })
+                .ok()
+                .map(|workspace| (root, workspace))
+        })
+        .collect()
+}
+
+fn exclude_globs(config: &Config) -> Result<Vec<crate::vfs_glob::Glob>> {
+    let globs = config
+        .files
+        .exclude
+        .iter()
+        .map(|glob| crate::vfs_glob::Glob::new(glob))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(globs)
+}
+
+/// The globs the client watches for us: every root of the `Vfs`, restricted to
+/// the files a custom root includes.
+fn watcher_globs(
+    ws_roots: &[PathBuf],
+    workspaces: &[ProjectWorkspace],
+    config: &Config,
+) -> Vec<String> {
+    let rust_files = |path: &Path| format!("{}/**/*.rs", path.display());
+    let mut res: Vec<String> = ws_roots
+        .iter()
+        .map(|it| rust_files(it))
+        .chain(
+            workspaces.iter().flat_map(ProjectWorkspace::to_roots).map(|it| rust_files(it.path())),
+        )
+        .chain(workspaces.iter().flat_map(ProjectWorkspace::out_dirs).map(|it| rust_files(&it)))
+        .collect();
+    for (path, root) in custom_roots(ws_roots, config) {
+        if root.include.is_empty() {
+            res.push(rust_files(&path));
+        } else {
+            res.extend(root.include.iter().map(|glob| format!("{}/{}", path.display(), glob)));
+        }
+    }
+    res.sort();
+    res.dedup();
+    res
+}
+
+fn register_file_watchers(
+    ws_roots: &[PathBuf],
+    workspaces: &[ProjectWorkspace],
+    config: &Config,
+    loop_state: &mut LoopState,
+    sender: &Sender<Message>,
+) {
+    if let FilesWatcher::Client = config.files.watcher {
+        let registration_options = req::DidChangeWatchedFilesRegistrationOptions {
+            watchers: watcher_globs(ws_roots, workspaces, config)
+                .into_iter()
+                .map(|glob_pattern| req::FileSystemWatcher { glob_pattern, kind: None })
+                .collect(),
+        };
+        let registration = req::Registration {
+            id: FILE_WATCHER_REGISTRATION_ID.to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: Some(serde_json::to_value(registration_options).unwrap()),
+        };
+        let params = req::RegistrationParams { registrations: vec![registration] };
+        let request = request_new::<req::RegisterCapability>(loop_state.next_request_id(), params);
+        sender.send(request.into()).unwrap();
+    }
+}
+
+fn unregister_file_watchers(config: &Config, loop_state: &mut LoopState, sender: &Sender<Message>) {
+    if let FilesWatcher::Client = config.files.watcher {
+        let unregistration = req::Unregistration {
+            id: FILE_WATCHER_REGISTRATION_ID.to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+        };
+        let params = req::UnregistrationParams { unregisterations: vec![unregistration] };
+        let request =
+            request_new::<req::UnregisterCapability>(loop_state.next_request_id(), params);
+        sender.send(request.into()).unwrap();
+    }
+}
+
+const FILE_WATCHER_REGISTRATION_ID: &str = "file-watcher";
+
+fn register_range_formatting(loop_state: &mut LoopState, sender: &Sender<Message>) {
+    let registration_options = TextDocumentRegistrationOptions {
+        document_selector: Some(vec![DocumentFilter {
+            language: Some("rust".to_string()),
+            scheme: None,
+            pattern: None,
+        }]),
+    };
+    let registration = req::Registration {
+        id: RANGE_FORMATTING_REGISTRATION_ID.to_string(),
+        method: "textDocument/rangeFormatting".to_string(),
+        register_options: Some(serde_json::to_value(registration_options).unwrap()),
+    };
+    let params = req::RegistrationParams { registrations: vec![registration] };
+    let request = request_new::<req::RegisterCapability>(loop_state.next_request_id(), params);
+    sender.send(request.into()).unwrap();
+}
+
+fn unregister_range_formatting(loop_state: &mut LoopState, sender: &Sender<Message>) {
+    let unregistration = req::Unregistration {
+        id: RANGE_FORMATTING_REGISTRATION_ID.to_string(),
+        method: "textDocument/rangeFormatting".to_string(),
+    };
+    let params = req::UnregistrationParams { unregisterations: vec![unregistration] };
+    let request = request_new::<req::UnregisterCapability>(loop_state.next_request_id(), params);
+    sender.send(request.into()).unwrap();
+}
+
+const RANGE_FORMATTING_REGISTRATION_ID: &str = "range-formatting";
+
+/// Loads the workspaces from scratch on the thread pool, as `cargo metadata`
+/// and loading the sysroot take a while. The result comes back as a
+/// `Task::WorkspacesLoaded`, and is answered to the request `id`, if any.
+fn spawn_workspace_reload(
+    world_state: &WorldState,
+    pool: &ThreadPool,
+    task_sender: &Sender<Task>,
+    msg_sender: &Sender<Message>,
+    id: Option<RequestId>,
+) {
+    world_state.analysis_host.request_cancellation();
+    let roots = world_state.roots.clone();
+    let config = world_state.config.clone();
+    let task_sender = task_sender.clone();
+    let msg_sender = msg_sender.clone();
+    pool.execute(move || {
+        let workspaces =
+            load_project_workspaces(&roots, &config, FxHashMap::default(), &msg_sender);
+        task_sender.send(Task::WorkspacesLoaded { id, roots, workspaces }).unwrap();
+    });
+}
+
+/// Throws away the current `WorldState` and builds a new one for the freshly
+/// loaded `workspaces`, carrying over the contents of the opened files.
+fn reload_workspace(
+    world_state: &mut WorldState,
+    loop_state: &mut LoopState,
+    sender: &Sender<Message>,
+    roots: Vec<PathBuf>,
+    workspaces: Vec<(ProjectRoot, ProjectWorkspace)>,
+) -> Result<()> {
+    if roots != world_state.roots {
+        // The workspace folders changed while we were loading, and the
+        // projects of the new ones are already loaded.
+        log::info!("discarding the reloaded workspaces of {:?}", roots);
+        return Ok(());
+    }
+    let open_files = open_files(world_state, loop_state)?;
+    unregister_file_watchers(&world_state.config, loop_state, sender);
+
+    world_state.analysis_host.request_cancellation();
+    loop_state.loaded_workspaces = workspaces.into_iter().collect();
+    let new_world_state = load_world_state(roots, world_state.config.clone(), loop_state, sender)?;
+
+    *world_state = new_world_state;
+    reopen_files(world_state, loop_state, open_files);
+    loop_state.start_loading(world_state);
+    Ok(())
+}
+
+/// Loads the projects of the workspace folders `ws_roots` on the thread pool.
+/// The projects which are already loaded are reused, so only the added folders
+/// need `cargo metadata` to run. The result comes back as a
+/// `Task::WorkspaceFoldersLoaded`.
+fn spawn_workspace_folders_change(
+    world_state: &WorldState,
+    loop_state: &mut LoopState,
+    pool: &ThreadPool,
+    task_sender: &Sender<Task>,
+    msg_sender: &Sender<Message>,
+    ws_roots: Vec<PathBuf>,
+) {
+    loop_state.pending_ws_roots = Some(ws_roots.clone());
+    let reusable = loop_state.loaded_workspaces.clone();
+    let config = world_state.config.clone();
+    let task_sender = task_sender.clone();
+    let msg_sender = msg_sender.clone();
+    pool.execute(move || {
+        let workspaces = load_project_workspaces(&ws_roots, &config, reusable, &msg_sender);
+        task_sender.send(Task::WorkspaceFoldersLoaded { roots: ws_roots, workspaces }).unwrap();
+    });
+}
+
+/// Switches the `WorldState` to the workspace folders `roots`. Unlike
+/// `reload_workspace`, the analysis is kept. The files of removed folders are
+/// unloaded, and their diagnostics are cleared in the client.
+fn change_workspace_roots(
+    world_state: &mut WorldState,
+    loop_state: &mut LoopState,
+    sender: &Sender<Message>,
+    roots: Vec<PathBuf>,
+    workspaces: Vec<(ProjectRoot, ProjectWorkspace)>,
+) -> Result<()> {
+    if loop_state.pending_ws_roots.as_ref() != Some(&roots) {
+        // The workspace folders changed again while we were loading.
+        log::info!("discarding the workspaces loaded for {:?}", roots);
+        return Ok(());
+    }
+    loop_state.pending_ws_roots = None;
+    loop_state.loaded_workspaces = workspaces.iter().cloned().collect();
+    let workspaces = workspaces.into_iter().map(|(_, workspace)| workspace).collect::<Vec<_>>();
+
+    let config = world_state.config.clone();
+    let globs = exclude_globs(&config)?;
+    unregister_file_watchers(&config, loop_state, sender);
+    register_file_watchers(&roots, &workspaces, &config, loop_state, sender);
+
+    let open_files = open_files(world_state, loop_state)?;
+    let diagnosed_files = diagnosed_files(world_state);
+    let watch = Watch(matches!(config.files.watcher, FilesWatcher::Notify));
+    if world_state.switch_workspaces(roots, workspaces, &globs, watch) {
+        // The diagnostics are recomputed for the files which are still there.
+        for path in diagnosed_files {
+            clear_diagnostics(&path, sender);
+        }
+        reopen_files(world_state, loop_state, open_files);
+        loop_state.start_loading(world_state);
+    } else {
+        loop_state.workspace_changed = true;
+        if let Some(flycheck) = &world_state.flycheck {
+            flycheck.update();
+        }
+    }
+    Ok(())
+}
+
+/// The paths and the contents of the files opened by the client.
+fn open_files(world_state: &WorldState, loop_state: &LoopState) -> Result<Vec<(PathBuf, String)>> {
+    let analysis = world_state.analysis_host.analysis();
+    let vfs = world_state.vfs.read();
+    loop_state
+        .subscriptions
+        .subscriptions()
+        .into_iter()
+        .map(|file_id| {
+            let text = analysis.file_text(file_id)?;
+            Ok((vfs.file2path(VfsFile(file_id.0)), String::clone(&text)))
+        })
+        .collect()
+}
+
+/// The paths of the files with diagnostics, which the client shows.
+fn diagnosed_files(world_state: &WorldState) -> Vec<PathBuf> {
+    let vfs = world_state.vfs.read();
+    let diagnostics = &world_state.diagnostics;
+    let file_ids: FxHashSet<FileId> =
+        diagnostics.native.keys().chain(diagnostics.check.keys()).copied().collect();
+    file_ids.into_iter().map(|file_id| vfs.file2path(VfsFile(file_id.0))).collect()
+}
+
+fn clear_diagnostics(path: &Path, sender: &Sender<Message>) {
+    let uri = match url_from_path_with_drive_lowercasing(path) {
+        Ok(uri) => uri,
+        Err(err) => {
+            log::error!("Couldn't convert path to url ({}): {}", err, path.display());
+            return;
+        }
+    };
+    let params = req::PublishDiagnosticsParams { uri, diagnostics: Vec::new(), version: None };
+    let not = notification_new::<req::PublishDiagnostics>(params);
+    sender.send(not.into()).unwrap();
+}
+
+/// Opens the files again after the `Vfs` was replaced.
+fn reopen_files(
+    world_state: &WorldState,
+    loop_state: &mut LoopState,
+    open_files: Vec<(PathBuf, String)>,
+) {
+    loop_state.subscriptions = Subscriptions::default();
+    for (path, text) in open_files {
+        if let Some(file_id) = world_state.vfs.write().add_file_overlay(&path, text) {
+            loop_state.subscriptions.add_sub(FileId(file_id.0));
+        }
+    }
+}
+
 #[derive(Debug)]
 enum Task {
     Respond(Response),
     Notify(Notification),
     Diagnostic(DiagnosticTask),
+    ApplyEdit(req::ApplyWorkspaceEditParams),
+    /// The projects of `roots` were loaded anew, see `spawn_workspace_reload`.
+    WorkspacesLoaded {
+        id: Option<RequestId>,
+        roots: Vec<PathBuf>,
+        workspaces: Vec<(ProjectRoot, ProjectWorkspace)>,
+    },
+    /// The projects of the changed workspace folders `roots` were loaded, see
+    /// `spawn_workspace_folders_change`.
+    WorkspaceFoldersLoaded {
+        roots: Vec<PathBuf>,
+        workspaces: Vec<(ProjectRoot, ProjectWorkspace)>,
+    },
 }
 
 enum Event {
     Msg(Message),
     Task(Task),
     Vfs(VfsTask),
-    Lib(LibraryData),
+    /// The watcher went quiet, or we've waited long enough, so the pending
+    /// file system changes should be applied.
+    FlushVfs,
+    Lib(u32, LibraryData),
     CheckWatcher(CheckTask),
 }
 
@@ -279,7 +590,8 @@ impl fmt::Debug for Event {
             Event::Msg(it) => fmt::Debug::fmt(it, f),
             Event::Task(it) => fmt::Debug::fmt(it, f),
             Event::Vfs(it) => fmt::Debug::fmt(it, f),
-            Event::Lib(it) => fmt::Debug::fmt(it, f),
+            Event::FlushVfs => f.write_str("FlushVfs"),
+            Event::Lib(_, it) => fmt::Debug::fmt(it, f),
             Event::CheckWatcher(it) => fmt::Debug::fmt(it, f),
         }
     }
@@ -300,8 +612,30 @@ struct LoopState {
     roots_scanned: usize,
     roots_total: usize,
     configuration_request_id: Option<RequestId>,
+    /// Incremented each time the workspace is (re)loaded, so that libraries
+    /// indexed for a previous workspace can be discarded.
+    workspace_generation: u32,
+    /// The projects of the current world, by the root they were discovered
+    /// from, for reuse when workspace folders are added or removed.
+    loaded_workspaces: FxHashMap<ProjectRoot, ProjectWorkspace>,
+    /// The workspace folders whose projects are being loaded on the thread
+    /// pool, after the client added or removed some.
+    pending_ws_roots: Option<Vec<PathBuf>>,
+    /// The first and the last of the watcher events which are not yet applied
+    /// to the analysis.
+    pending_vfs_events: Option<(Instant, Instant)>,
+    /// The crate graph changed without a rescan of the files, so the
+    /// diagnostics of the open files are out of date.
+    workspace_changed: bool,
 }
 
+/// Watcher events come in bursts, for example on `git checkout` or when a
+/// build script regenerates files. We wait until the watcher has been quiet
+/// for `VFS_DEBOUNCE`, but no longer than `VFS_MAX_DELAY`, and apply the whole
+/// burst as a single change.
+const VFS_DEBOUNCE: Duration = Duration::from_millis(50);
+const VFS_MAX_DELAY: Duration = Duration::from_millis(500);
+
 impl LoopState {
     fn next_request_id(&mut self) -> RequestId {
         self.next_request_id += 1;
@@ -310,12 +644,52 @@ impl LoopState {
         assert!(inserted);
         res
     }
+
+    fn start_loading(&mut self, world_state: &WorldState) {
+        self.workspace_generation += 1;
+        self.workspace_loaded = false;
+        self.roots_progress_reported = None;
+        self.roots_scanned = 0;
+        self.roots_total = world_state.vfs.read().n_roots();
+        self.pending_libraries.clear();
+    }
+
+    fn record_vfs_event(&mut self, now: Instant) {
+        let first = self.pending_vfs_events.map_or(now, |(first, _)| first);
+        self.pending_vfs_events = Some((first, now));
+    }
+
+    fn vfs_flush_deadline(&self) -> Option<Instant> {
+        let (first, last) = self.pending_vfs_events?;
+        Some((last + VFS_DEBOUNCE).min(first + VFS_MAX_DELAY))
+    }
+}
+
+/// Pending changes are applied once the debounce timer fires. Requests are
+/// the exception: edits made by the user must be visible to the requests
+/// which follow them, so a request flushes the changes right away.
+fn should_flush_vfs(event: &Event, deadline: Option<Instant>, now: Instant) -> bool {
+    match event {
+        Event::Vfs(_) | Event::Msg(Message::Notification(_)) | Event::Msg(Message::Response(_)) => {
+            false
+        }
+        Event::Msg(Message::Request(_)) | Event::FlushVfs => true,
+        _ => deadline.map_or(true, |it| it <= now),
+    }
+}
+
+/// Document notifications update the overlays of the VFS, which, just like
+/// the watcher events, are applied on the next flush.
+fn changes_documents(not: &Notification) -> bool {
+    notification_is::<req::DidOpenTextDocument>(not)
+        || notification_is::<req::DidChangeTextDocument>(not)
+        || notification_is::<req::DidCloseTextDocument>(not)
 }
 
 fn loop_turn(
     pool: &ThreadPool,
     task_sender: &Sender<Task>,
-    libdata_sender: &Sender<LibraryData>,
+    libdata_sender: &Sender<(u32, LibraryData)>,
     connection: &Connection,
     world_state: &mut WorldState,
     loop_state: &mut LoopState,
@@ -331,25 +705,31 @@ fn loop_turn(
         log::info!("queued count = {}", queue_count);
     }
 
+    let flush_vfs = should_flush_vfs(&event, loop_state.vfs_flush_deadline(), loop_start);
+
     match event {
         Event::Task(task) => {
-            on_task(task, &connection.sender, &mut loop_state.pending_requests, world_state);
+            on_task(task, &connection.sender, loop_state, world_state);
             world_state.maybe_collect_garbage();
         }
         Event::Vfs(task) => {
             world_state.vfs.write().handle_task(task);
+            loop_state.record_vfs_event(loop_start);
         }
-        Event::Lib(lib) => {
-            world_state.add_lib(lib);
-            world_state.maybe_collect_garbage();
+        Event::FlushVfs => (),
+        Event::Lib(generation, lib) => {
             loop_state.in_flight_libraries -= 1;
-            loop_state.roots_scanned += 1;
+            if generation == loop_state.workspace_generation {
+                world_state.add_lib(lib);
+                world_state.maybe_collect_garbage();
+                loop_state.roots_scanned += 1;
+            }
         }
         Event::CheckWatcher(task) => on_check_task(task, world_state, task_sender)?,
         Event::Msg(msg) => match msg {
             Message::Request(req) => on_request(
                 world_state,
-                &mut loop_state.pending_requests,
+                loop_state,
                 pool,
                 task_sender,
                 &connection.sender,
@@ -357,7 +737,17 @@ fn loop_turn(
                 req,
             )?,
             Message::Notification(not) => {
-                on_notification(&connection.sender, world_state, loop_state, not)?;
+                if changes_documents(&not) {
+                    loop_state.record_vfs_event(loop_start);
+                }
+                on_notification(
+                    &connection.sender,
+                    world_state,
+                    loop_state,
+                    pool,
+                    task_sender,
+                    not,
+                )?;
             }
             Message::Response(resp) => {
                 let removed = loop_state.pending_responses.remove(&resp.id);
@@ -377,8 +767,30 @@ fn loop_turn(
                         (None, Some(configs)) => {
                             if let Some(new_config) = configs.get(0) {
                                 let mut config = world_state.config.clone();
-                                config.update(&new_config);
+                                let errors = config.update(&new_config);
+                                show_config_errors(&errors, &connection.sender);
+                                let requires_reload = world_state.config.requires_reload(&config);
+                                let range_formatting = config.range_formatting_enabled();
+                                if config.client_caps.range_formatting_dynamic_registration
+                                    && range_formatting
+                                        != world_state.config.range_formatting_enabled()
+                                {
+                                    if range_formatting {
+                                        register_range_formatting(loop_state, &connection.sender);
+                                    } else {
+                                        unregister_range_formatting(loop_state, &connection.sender);
+                                    }
+                                }
                                 world_state.update_configuration(config);
+                                if requires_reload {
+                                    spawn_workspace_reload(
+                                        world_state,
+                                        pool,
+                                        task_sender,
+                                        &connection.sender,
+                                        None,
+                                    );
+                                }
                             }
                         }
                         (None, None) => {
@@ -390,10 +802,13 @@ fn loop_turn(
         },
     };
 
-    let mut state_changed = false;
-    if let Some(changes) = world_state.process_changes(&mut loop_state.roots_scanned) {
-        state_changed = true;
-        loop_state.pending_libraries.extend(changes);
+    let mut state_changed = std::mem::take(&mut loop_state.workspace_changed);
+    if flush_vfs {
+        loop_state.pending_vfs_events = None;
+        if let Some(changes) = world_state.process_changes(&mut loop_state.roots_scanned) {
+            state_changed = true;
+            loop_state.pending_libraries.extend(changes);
+        }
     }
 
     let max_in_flight_libs = pool.max_count().saturating_sub(2).max(1);
@@ -403,10 +818,11 @@ fn loop_turn(
         let (root, files) = loop_state.pending_libraries.pop().unwrap();
         loop_state.in_flight_libraries += 1;
         let sender = libdata_sender.clone();
+        let generation = loop_state.workspace_generation;
         pool.execute(move || {
             log::info!("indexing {:?} ... ", root);
             let data = LibraryData::prepare(root, files);
-            sender.send(data).unwrap();
+            sender.send((generation, data)).unwrap();
         });
     }
 
@@ -461,12 +877,12 @@ fn loop_turn(
 fn on_task(
     task: Task,
     msg_sender: &Sender<Message>,
-    pending_requests: &mut PendingRequests,
+    loop_state: &mut LoopState,
     state: &mut WorldState,
 ) {
     match task {
         Task::Respond(response) => {
-            if let Some(completed) = pending_requests.finish(&response.id) {
+            if let Some(completed) = loop_state.pending_requests.finish(&response.id) {
                 log::info!("handled req#{} in {:?}", completed.id, completed.duration);
                 state.complete_request(completed);
                 msg_sender.send(response.into()).unwrap();
@@ -476,12 +892,34 @@ fn on_task(
             msg_sender.send(n.into()).unwrap();
         }
         Task::Diagnostic(task) => on_diagnostic_task(task, msg_sender, state),
+        Task::ApplyEdit(params) => {
+            let request =
+                request_new::<req::ApplyWorkspaceEdit>(loop_state.next_request_id(), params);
+            msg_sender.send(request.into()).unwrap();
+        }
+        Task::WorkspacesLoaded { id, roots, workspaces } => {
+            let result = reload_workspace(state, loop_state, msg_sender, roots, workspaces);
+            if let Err(err) = &result {
+                log::error!("failed to reload the workspace: {}", err);
+            }
+            if let Some(id) = id {
+                let task = result_to_task::<req::ExecuteCommand>(id, result.map(|()| None));
+                on_task(task, msg_sender, loop_state, state);
+            }
+        }
+        Task::WorkspaceFoldersLoaded { roots, workspaces } => {
+            if let Err(err) =
+                change_workspace_roots(state, loop_state, msg_sender, roots, workspaces)
+            {
+                log::error!("failed to change the workspace folders: {}", err);
+            }
+        }
     }
 }
 
 fn on_request(
     world: &mut WorldState,
-    pending_requests: &mut PendingRequests,
+    loop_state: &mut LoopState,
     pool: &ThreadPool,
     task_sender: &Sender<Task>,
     msg_sender: &Sender<Message>,
@@ -494,7 +932,7 @@ fn on_request(
         world,
         task_sender,
         msg_sender,
-        pending_requests,
+        loop_state,
         request_received,
     };
     pool_dispatcher
@@ -509,34 +947,47 @@ fn on_request(
         })?
         .on::<req::AnalyzerStatus>(handlers::handle_analyzer_status)?
         .on::<req::SyntaxTree>(handlers::handle_syntax_tree)?
+        .on::<req::ViewHir>(handlers::handle_view_hir)?
+        .on::<req::ViewCrateGraph>(handlers::handle_view_crate_graph)?
         .on::<req::ExpandMacro>(handlers::handle_expand_macro)?
+        .on::<req::ExpandAllMacros>(handlers::handle_expand_all_macros)?
         .on::<req::OnTypeFormatting>(handlers::handle_on_type_formatting)?
         .on::<req::DocumentSymbolRequest>(handlers::handle_document_symbol)?
         .on::<req::WorkspaceSymbol>(handlers::handle_workspace_symbol)?
         .on::<req::GotoDefinition>(handlers::handle_goto_definition)?
+        .on::<req::GotoDeclaration>(handlers::handle_goto_declaration)?
         .on::<req::GotoImplementation>(handlers::handle_goto_implementation)?
         .on::<req::GotoTypeDefinition>(handlers::handle_goto_type_definition)?
         .on::<req::ParentModule>(handlers::handle_parent_module)?
         .on::<req::Runnables>(handlers::handle_runnables)?
+        .on::<req::DiscoverTests>(handlers::handle_discover_tests)?
         .on::<req::Completion>(handlers::handle_completion)?
+        .on::<req::ResolveCompletionItem>(handlers::handle_completion_resolve)?
         .on::<req::CodeActionRequest>(handlers::handle_code_action)?
         .on::<req::CodeLensRequest>(handlers::handle_code_lens)?
         .on::<req::CodeLensResolve>(handlers::handle_code_lens_resolve)?
         .on::<req::FoldingRangeRequest>(handlers::handle_folding_range)?
+        .on::<req::DocumentLinkRequest>(handlers::handle_document_link)?
         .on::<req::SignatureHelpRequest>(handlers::handle_signature_help)?
         .on::<req::HoverRequest>(handlers::handle_hover)?
         .on::<req::PrepareRenameRequest>(handlers::handle_prepare_rename)?
         .on::<req::Rename>(handlers::handle_rename)?
+        .on::<req::WillRenameFiles>(handlers::handle_will_rename_files)?
         .on::<req::References>(handlers::handle_references)?
         .on::<req::Formatting>(handlers::handle_formatting)?
+        .on::<req::RangeFormatting>(handlers::handle_range_formatting)?
         .on::<req::DocumentHighlightRequest>(handlers::handle_document_highlight)?
         .on::<req::InlayHints>(handlers::handle_inlay_hints)?
         .on::<req::CallHierarchyPrepare>(handlers::handle_call_hierarchy_prepare)?
         .on::<req::CallHierarchyIncomingCalls>(handlers::handle_call_hierarchy_incoming)?
         .on::<req::CallHierarchyOutgoingCalls>(handlers::handle_call_hierarchy_outgoing)?
+        .on::<req::PrepareTypeHierarchy>(handlers::handle_prepare_type_hierarchy)?
+        .on::<req::TypeHierarchySupertypes>(handlers::handle_type_hierarchy_supertypes)?
+        .on::<req::TypeHierarchySubtypes>(handlers::handle_type_hierarchy_subtypes)?
         .on::<req::SemanticTokensRequest>(handlers::handle_semantic_tokens)?
         .on::<req::SemanticTokensRangeRequest>(handlers::handle_semantic_tokens_range)?
         .on::<req::Ssr>(handlers::handle_ssr)?
+        .on_execute_command()?
         .finish();
     Ok(())
 }
@@ -545,6 +996,8 @@ fn on_notification(
     msg_sender: &Sender<Message>,
     state: &mut WorldState,
     loop_state: &mut LoopState,
+    pool: &ThreadPool,
+    task_sender: &Sender<Task>,
     not: Notification,
 ) -> Result<()> {
     let not = match notification_cast::<req::Cancel>(not) {
@@ -579,12 +1032,42 @@ fn on_notification(
         Err(not) => not,
     };
     let not = match notification_cast::<req::DidChangeTextDocument>(not) {
-        Ok(mut params) => {
-            let uri = params.text_document.uri;
+        Ok(params) => {
+            let DidChangeTextDocumentParams { text_document, content_changes } = params;
+            let uri = text_document.uri;
             let path = uri.to_file_path().map_err(|()| format!("invalid uri: {}", uri))?;
-            let text =
-                params.content_changes.pop().ok_or_else(|| "empty changes".to_string())?.text;
+            let file = state.vfs.read().path2file(&path);
+            let file_id = match file {
+                Some(file) => FileId(file.0),
+                None => {
+                    // We don't know the current text of the file, so we can
+                    // only use the changes from the last full text onwards.
+                    let mut changes = content_changes;
+                    match changes.iter().rposition(|it| it.range.is_none()) {
+                        Some(idx) => {
+                            let mut text = String::new();
+                            apply_document_changes(&mut text, changes.split_off(idx));
+                            match state.vfs.write().add_file_overlay(&path, text) {
+                                Some(file_id) => {
+                                    loop_state.subscriptions.add_sub(FileId(file_id.0))
+                                }
+                                None => log::warn!(
+                                    "change to a file outside of the workspace: {}",
+                                    path.display()
+                                ),
+                            }
+                        }
+                        None => {
+                            log::error!("can't apply changes to unknown file: {}", path.display())
+                        }
+                    }
+                    return Ok(());
+                }
+            };
+            let mut text = String::clone(&state.analysis_host.analysis().file_text(file_id)?);
+            let edits = apply_document_changes(&mut text, content_changes);
             state.vfs.write().change_file_overlay(path.as_path(), text);
+            state.record_edits(file_id, edits);
             return Ok(());
         }
         Err(not) => not,
@@ -634,6 +1117,35 @@ fn on_notification(
         }
         Err(not) => not,
     };
+    let not = match notification_cast::<req::DidChangeWorkspaceFolders>(not) {
+        Ok(params) => {
+            let to_path = |folder: req::WorkspaceFolder| {
+                folder.uri.to_file_path().map_err(|()| format!("invalid uri: {}", folder.uri))
+            };
+            let mut ws_roots =
+                loop_state.pending_ws_roots.clone().unwrap_or_else(|| state.roots.clone());
+            for folder in params.event.removed {
+                let path = to_path(folder)?;
+                ws_roots.retain(|it| *it != path);
+            }
+            for folder in params.event.added {
+                let path = to_path(folder)?;
+                if !ws_roots.contains(&path) {
+                    ws_roots.push(path);
+                }
+            }
+            spawn_workspace_folders_change(
+                state,
+                loop_state,
+                pool,
+                task_sender,
+                msg_sender,
+                ws_roots,
+            );
+            return Ok(());
+        }
+        Err(not) => not,
+    };
     let not = match notification_cast::<req::DidChangeWatchedFiles>(not) {
         Ok(params) => {
             let mut vfs = state.vfs.write();
@@ -653,6 +1165,56 @@ fn on_notification(
     Ok(())
 }
 
+/// Applies the changes to the text, returning them as edits for incremental
+/// reparsing, or `None` if one of them replaced the whole text.
+fn apply_document_changes(
+    old_text: &mut String,
+    content_changes: Vec<TextDocumentContentChangeEvent>,
+) -> Option<Vec<AtomTextEdit>> {
+    let mut line_index = LineIndex::new(old_text);
+    // The changes we got must be applied sequentially, but can cross lines so we
+    // have to keep our line index updated.
+    // Some clients (e.g. Code) sort the ranges in reverse. As an optimization, we
+    // remember the last valid line in the index and only rebuild it if needed.
+    enum IndexValid {
+        All,
+        UpToLineExclusive(u64),
+    }
+
+    impl IndexValid {
+        fn covers(&self, line: u64) -> bool {
+            match *self {
+                IndexValid::UpToLineExclusive(to) => to > line,
+                _ => true,
+            }
+        }
+    }
+
+    let mut index_valid = IndexValid::All;
+    let mut edits = Some(Vec::new());
+    for change in content_changes {
+        match change.range {
+            Some(range) => {
+                if !index_valid.covers(range.end.line) {
+                    line_index = LineIndex::new(old_text);
+                }
+                index_valid = IndexValid::UpToLineExclusive(range.start.line);
+                let range = range.conv_with(&line_index);
+                old_text.replace_range(std::ops::Range::<usize>::from(range), &change.text);
+                if let Some(edits) = &mut edits {
+                    edits.push(AtomTextEdit::replace(range, change.text));
+                }
+            }
+            None => {
+                *old_text = change.text;
+                index_valid = IndexValid::UpToLineExclusive(0);
+                edits = None;
+            }
+        }
+    }
+    edits
+}
+
 fn on_check_task(
     task: CheckTask,
     world_state: &mut WorldState,
@@ -765,7 +1327,7 @@ struct PoolDispatcher<'a> {
     req: Option<Request>,
     pool: &'a ThreadPool,
     world: &'a mut WorldState,
-    pending_requests: &'a mut PendingRequests,
+    loop_state: &'a mut LoopState,
     msg_sender: &'a Sender<Message>,
     task_sender: &'a Sender<Task>,
     request_received: Instant,
@@ -794,7 +1356,7 @@ impl<'a> PoolDispatcher<'a> {
             result_to_task::<R>(id, result)
         })
         .map_err(|_| format!("sync task {:?} panicked", R::METHOD))?;
-        on_task(task, self.msg_sender, self.pending_requests, self.world);
+        on_task(task, self.msg_sender, self.loop_state, self.world);
         Ok(self)
     }
 
@@ -825,6 +1387,54 @@ impl<'a> PoolDispatcher<'a> {
         Ok(self)
     }
 
+    /// Dispatches `workspace/executeCommand`. Collecting garbage runs on the
+    /// current thread, reloading the workspace is answered once the projects
+    /// are loaded, and commands which compute an edit run on the thread pool
+    /// and ask the client to apply it.
+    fn on_execute_command(&mut self) -> Result<&mut Self> {
+        let (id, params) = match self.parse::<req::ExecuteCommand>() {
+            Some(it) => it,
+            None => {
+                return Ok(self);
+            }
+        };
+
+        let result = match params.command.as_str() {
+            req::COLLECT_GARBAGE_COMMAND => {
+                self.world.collect_garbage();
+                Ok(None)
+            }
+            req::RELOAD_WORKSPACE_COMMAND => {
+                spawn_workspace_reload(
+                    self.world,
+                    self.pool,
+                    self.task_sender,
+                    self.msg_sender,
+                    Some(id),
+                );
+                return Ok(self);
+            }
+            _ => {
+                self.pool.execute({
+                    let world = self.world.snapshot();
+                    let sender = self.task_sender.clone();
+                    move || {
+                        let result = handlers::handle_execute_command(world, params).map(|edit| {
+                            sender.send(Task::ApplyEdit(edit)).unwrap();
+                            None
+                        });
+                        let task = result_to_task::<req::ExecuteCommand>(id, result);
+                        sender.send(task).unwrap();
+                    }
+                });
+                return Ok(self);
+            }
+        };
+        let task = result_to_task::<req::ExecuteCommand>(id, result);
+        on_task(task, self.msg_sender, self.loop_state, self.world);
+        Ok(self)
+    }
+
     fn parse<R>(&mut self) -> Option<(RequestId, R::Params)>
     where
         R: req::Request + 'static,
@@ -838,7 +1448,7 @@ impl<'a> PoolDispatcher<'a> {
                 return None;
             }
         };
-        self.pending_requests.start(PendingRequest {
+        self.loop_state.pending_requests.start(PendingRequest {
             id: id.clone(),
             method: R::METHOD.to_string(),
             received: self.request_received,
@@ -920,6 +1530,22 @@ fn update_file_notifications_on_threadpool(
     }
 }
 
+/// Tells the user about settings which were ignored, so that typos don't go
+/// unnoticed.
+pub fn show_config_errors(errors: &[ConfigError], sender: &Sender<Message>) {
+    if errors.is_empty() {
+        return;
+    }
+    show_message(
+        req::MessageType::Error,
+        format!(
+            "rust-analyzer ignored invalid settings: {}",
+            errors.iter().format_with("; ", |it, f| f(it))
+        ),
+        sender,
+    );
+}
+
 pub fn show_message(typ: req::MessageType, message: impl Into<String>, sender: &Sender<Message>) {
     let message = message.into();
     let params = req::ShowMessageParams { typ, message };
@@ -958,3 +1584,182 @@ where
 {
     Request::new(id, R::METHOD.to_string(), params)
 }
+
+#[cfg(test)]
+mod tests {
+    use lsp_server::{Message, Notification, Request, Response};
+    use lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+    use ra_syntax::{TextRange, TextSize};
+
+    use std::{
+        path::PathBuf,
+        time::{Duration, Instant},
+    };
+
+    use super::{
+        apply_document_changes, changes_documents, should_flush_vfs, watcher_globs, Event,
+        LoopState, VFS_DEBOUNCE, VFS_MAX_DELAY,
+    };
+    use crate::{
+        config::{Config, RootConfig, RootKind},
+        req,
+    };
+
+    #[test]
+    fn apply_document_changes_test() {
+        macro_rules! c {
+            [$($sl:expr, $sc:expr; $el:expr, $ec:expr => $text:expr),+] => {
+                vec![$(TextDocumentContentChangeEvent {
+                    range: Some(Range {
+                        start: Position { line: $sl, character: $sc },
+                        end: Position { line: $el, character: $ec },
+                    }),
+                    range_length: None,
+                    text: String::from($text),
+                }),+]
+            };
+        }
+
+        let mut text = String::new();
+        apply_document_changes(&mut text, vec![]);
+        assert_eq!(text, "");
+        apply_document_changes(
+            &mut text,
+            vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: String::from("the"),
+            }],
+        );
+        assert_eq!(text, "the");
+        apply_document_changes(&mut text, c![0, 3; 0, 3 => " quick"]);
+        assert_eq!(text, "the quick");
+        apply_document_changes(&mut text, c![0, 0; 0, 4 => "", 0, 5; 0, 5 => " foxes"]);
+        assert_eq!(text, "quick foxes");
+        apply_document_changes(&mut text, c![0, 11; 0, 11 => "\ndream"]);
+        assert_eq!(text, "quick foxes\ndream");
+        apply_document_changes(&mut text, c![1, 0; 1, 0 => "have "]);
+        assert_eq!(text, "quick foxes\nhave dream");
+        apply_document_changes(
+            &mut text,
+            c![0, 0; 0, 0 => "the ", 1, 4; 1, 4 => " quiet", 1, 16; 1, 16 => "s\n"],
+        );
+        assert_eq!(text, "the quick foxes\nhave quiet dreams\n");
+        apply_document_changes(&mut text, c![0, 15; 0, 15 => "\n", 2, 17; 2, 17 => "\n"]);
+        assert_eq!(text, "the quick foxes\n\nhave quiet dreams\n\n");
+        apply_document_changes(
+            &mut text,
+            c![1, 0; 1, 0 => "DREAM", 2, 0; 2, 0 => "they ", 3, 0; 3, 0 => "DON'T THEY?"],
+        );
+        assert_eq!(text, "the quick foxes\nDREAM\nthey have quiet dreams\nDON'T THEY?\n");
+        apply_document_changes(&mut text, c![0, 10; 1, 5 => "", 2, 0; 2, 12 => ""]);
+        assert_eq!(text, "the quick \nthey have quiet dreams\n");
+
+        text = String::from("❤️");
+        apply_document_changes(&mut text, c![0, 0; 0, 0 => "a"]);
+        assert_eq!(text, "a❤️");
+
+        text = String::from("a\nb");
+        apply_document_changes(&mut text, c![0, 1; 1, 0 => "\nțc", 0, 1; 1, 1 => "d"]);
+        assert_eq!(text, "adcb");
+
+        text = String::from("a\nb");
+        apply_document_changes(&mut text, c![0, 1; 1, 0 => "ț\nc", 0, 2; 0, 2 => "c"]);
+        assert_eq!(text, "ațc\ncb");
+
+        text = String::from("fn f() {}");
+        let edits =
+            apply_document_changes(&mut text, c![0, 8; 0, 8 => "92", 0, 0; 0, 2 => "const"]);
+        assert_eq!(text, "const f() {92}");
+        let edits: Vec<_> = edits.unwrap().into_iter().map(|it| (it.delete, it.insert)).collect();
+        let range =
+            |start: u32, end: u32| TextRange::new(TextSize::from(start), TextSize::from(end));
+        assert_eq!(
+            edits,
+            vec![(range(8, 8), "92".to_string()), (range(0, 2), "const".to_string())]
+        );
+        let edits = apply_document_changes(
+            &mut text,
+            vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: String::new(),
+            }],
+        );
+        assert!(edits.is_none());
+    }
+
+    #[test]
+    fn vfs_flush_deadline_debounces_events() {
+        let mut loop_state = LoopState::default();
+        assert_eq!(loop_state.vfs_flush_deadline(), None);
+
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        loop_state.record_vfs_event(start);
+        assert_eq!(loop_state.vfs_flush_deadline(), Some(start + VFS_DEBOUNCE));
+
+        // Each event pushes the flush back...
+        loop_state.record_vfs_event(at(30));
+        assert_eq!(loop_state.vfs_flush_deadline(), Some(at(30) + VFS_DEBOUNCE));
+
+        // ...but no further than `VFS_MAX_DELAY` after the first one.
+        for millis in (60..=480).step_by(30) {
+            loop_state.record_vfs_event(at(millis));
+        }
+        assert_eq!(loop_state.vfs_flush_deadline(), Some(start + VFS_MAX_DELAY));
+
+        loop_state.pending_vfs_events = None;
+        assert_eq!(loop_state.vfs_flush_deadline(), None);
+    }
+
+    #[test]
+    fn only_requests_and_the_timer_flush_the_vfs() {
+        let now = Instant::now();
+        let pending = Some(now + VFS_DEBOUNCE);
+        let request = || Event::Msg(Message::Request(Request::new(1u64.into(), "test".into(), ())));
+        let did_change = || {
+            Event::Msg(Message::Notification(Notification::new(
+                <req::DidChangeTextDocument as lsp_types::notification::Notification>::METHOD
+                    .into(),
+                (),
+            )))
+        };
+
+        assert!(should_flush_vfs(&request(), pending, now));
+        assert!(should_flush_vfs(&Event::FlushVfs, pending, now));
+        assert!(!should_flush_vfs(&did_change(), pending, now));
+        assert!(!should_flush_vfs(&did_change(), None, now));
+
+        let response = Event::Msg(Message::Response(Response::new_ok(1u64.into(), ())));
+        assert!(!should_flush_vfs(&response, pending, now));
+
+        if let Event::Msg(Message::Notification(not)) = did_change() {
+            assert!(changes_documents(&not));
+        }
+    }
+
+    #[test]
+    fn watcher_globs_cover_custom_roots() {
+        let mut config = Config::default();
+        config.files.roots = vec![
+            RootConfig {
+                path: PathBuf::from("vendor"),
+                kind: RootKind::Library,
+                include: Vec::new(),
+                exclude: Vec::new(),
+            },
+            RootConfig {
+                path: PathBuf::from("/gen"),
+                kind: RootKind::Local,
+                include: vec!["**/*.rs.in".to_string(), "*.rs".to_string()],
+                exclude: Vec::new(),
+            },
+        ];
+        let globs = watcher_globs(&[PathBuf::from("/ws")], &[], &config);
+        assert_eq!(
+            globs,
+            vec!["/gen/**/*.rs.in", "/gen/*.rs", "/ws/**/*.rs", "/ws/vendor/**/*.rs"]
+        );
+    }
+}
@@ -0,0 +1,43 @@
+//! Completion items whose documentation and detail are computed only when the
+//! client resolves them.
+
+use ra_ide::{CompletionItem, FileId};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// The unresolved items of the latest completion list. The client sends the
+/// list id and the item index back in `completionItem/resolve`, so we don't
+/// need to recompute the list, which might have changed in the meantime.
+#[derive(Debug, Default)]
+pub struct CompletionResolveCache {
+    list_id: u64,
+    /// The items, with the files which define them.
+    items: FxHashMap<usize, (FileId, CompletionItem)>,
+}
+
+impl CompletionResolveCache {
+    /// Replaces the stored items, returning the id of the new list.
+    pub(crate) fn store(&mut self, items: FxHashMap<usize, (FileId, CompletionItem)>) -> u64 {
+        self.list_id += 1;
+        self.items = items;
+        self.list_id
+    }
+
+    pub(crate) fn get(&self, list_id: u64, index: usize) -> Option<CompletionItem> {
+        if list_id != self.list_id {
+            return None;
+        }
+        self.items.get(&index).map(|(_, item)| item.clone())
+    }
+
+    /// Forgets the items defined in the changed files, whose definitions might
+    /// not exist anymore. The others stay resolvable while the user types.
+    pub(crate) fn invalidate_files(&mut self, changed: &FxHashSet<FileId>) {
+        self.items.retain(|_, (file_id, _)| !changed.contains(file_id));
+    }
+
+    /// Forgets all stored items, for changes which can move any definition,
+    /// like adding a file to the module tree.
+    pub(crate) fn clear(&mut self) {
+        self.items.clear();
+    }
+}
@@ -4,6 +4,7 @@
 
 use std::{
     io::Write as _,
+    path::{Path, PathBuf},
     process::{self, Stdio},
 };
 
@@ -11,21 +12,25 @@ use lsp_server::ErrorCode;
 use lsp_types::{
     CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
     CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
-    CodeAction, CodeActionResponse, CodeLens, Command, CompletionItem, Diagnostic,
-    DocumentFormattingParams, DocumentHighlight, DocumentSymbol, FoldingRange, FoldingRangeParams,
-    Hover, HoverContents, Location, MarkupContent, MarkupKind, Position, PrepareRenameResponse,
-    Range, RenameParams, SemanticTokensParams, SemanticTokensRangeParams,
+    CodeAction, CodeActionResponse, CodeLens, Command, CompletionItem, Diagnostic, DiagnosticTag,
+    DocumentFormattingParams, DocumentHighlight, DocumentLink, DocumentLinkParams,
+    DocumentRangeFormattingParams, DocumentSymbol, FoldingRange, FoldingRangeParams, Hover,
+    HoverContents, Location, MarkupContent, MarkupKind, Position, PrepareRenameResponse, Range,
+    RenameParams, SemanticTokens, SemanticTokensParams, SemanticTokensRangeParams,
     SemanticTokensRangeResult, SemanticTokensResult, SymbolInformation, TextDocumentIdentifier,
     TextEdit, Url, WorkspaceEdit,
 };
+use ra_flycheck::url_from_path_with_drive_lowercasing;
 use ra_ide::{
-    Assist, AssistId, FileId, FilePosition, FileRange, Query, RangeInfo, Runnable, RunnableKind,
-    SearchScope,
+    Assist, AssistId, CrateGraphConfig, DocumentLinkTarget, FileId, FilePosition, FileRange,
+    HighlightedRange, HoverAction, HoverGotoTypeData, LineIndex, Query, RangeInfo, Runnable,
+    RunnableKind, SearchScope, SourceChange, TestItemKind, TokenOrigin, TypeHierarchy,
 };
 use ra_prof::profile;
+use ra_project_model::TargetKind;
 use ra_syntax::{AstNode, SyntaxKind, TextRange, TextSize};
-use rustc_hash::FxHashMap;
-use serde::{Deserialize, Serialize};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::to_value;
 use stdx::format_to;
 
@@ -33,10 +38,11 @@ use crate::{
     cargo_target_spec::CargoTargetSpec,
     config::RustfmtConfig,
     conv::{
-        to_call_hierarchy_item, to_location, Conv, ConvWith, FoldConvCtx, MapConvWith, TryConvWith,
-        TryConvWithToVec,
+        to_call_hierarchy_item, to_location, to_type_hierarchy_item, Conv, ConvWith, FoldConvCtx,
+        MapConvWith, TryConvWith, TryConvWithToVec,
     },
     diagnostics::DiagnosticTask,
+    diff::diff,
     from_json,
     req::{self, InlayHint, InlayHintsParams},
     semantic_tokens::SemanticTokensBuilder,
@@ -65,6 +71,29 @@ pub fn handle_syntax_tree(world: WorldSnapshot, params: req::SyntaxTreeParams) -
     Ok(res)
 }
 
+pub fn handle_view_hir(
+    world: WorldSnapshot,
+    params: req::TextDocumentPositionParams,
+) -> Result<String> {
+    let _p = profile("handle_view_hir");
+    let position = params.try_conv_with(&world)?;
+    let res = world.analysis().view_hir(position)?;
+    Ok(res)
+}
+
+pub fn handle_view_crate_graph(
+    world: WorldSnapshot,
+    params: req::ViewCrateGraphParams,
+) -> Result<String> {
+    let _p = profile("handle_view_crate_graph");
+    let mut config = CrateGraphConfig::default();
+    if params.hide_sysroot {
+        config.hidden_roots = world.sysroot_crate_roots();
+    }
+    let res = world.analysis().view_crate_graph(&config)?;
+    Ok(res)
+}
+
 pub fn handle_expand_macro(
     world: WorldSnapshot,
     params: req::ExpandMacroParams,
@@ -83,6 +112,36 @@ pub fn handle_expand_macro(
     }
 }
 
+pub fn handle_expand_all_macros(
+    world: WorldSnapshot,
+    params: req::ExpandAllMacrosParams,
+) -> Result<Option<req::ExpandedFile>> {
+    let _p = profile("handle_expand_all_macros");
+    let file_id = params.text_document.try_conv_with(&world)?;
+    let line_index = world.analysis().file_line_index(file_id)?;
+    let offset = params.position.map(|p| p.conv_with(&line_index));
+
+    let expanded = match world.analysis().expand_all_macros(file_id, offset)? {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    let expanded_line_index = LineIndex::new(&expanded.text);
+    let mut origins = Vec::new();
+    for (range, origin) in expanded.origins {
+        let (frange, from_definition) = match origin {
+            TokenOrigin::Input(it) => (it, false),
+            TokenOrigin::Definition(it) => (it, true),
+        };
+        let origin_line_index = world.analysis().file_line_index(frange.file_id)?;
+        origins.push(req::ExpansionOrigin {
+            range: range.conv_with(&expanded_line_index),
+            location: to_location(frange.file_id, frange.range, &world, &origin_line_index)?,
+            from_definition,
+        });
+    }
+    Ok(Some(req::ExpandedFile { text: expanded.text, origins }))
+}
+
 pub fn handle_selection_range(
     world: WorldSnapshot,
     params: req::SelectionRangeParams,
@@ -338,6 +397,20 @@ pub fn handle_goto_definition(
     Ok(Some(res))
 }
 
+pub fn handle_goto_declaration(
+    world: WorldSnapshot,
+    params: req::TextDocumentPositionParams,
+) -> Result<Option<req::GotoDeclarationResponse>> {
+    let _p = profile("handle_goto_declaration");
+    let position = params.try_conv_with(&world)?;
+    let nav_info = match world.analysis().goto_declaration(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let res = (position.file_id, nav_info).try_conv_with(&world)?;
+    Ok(Some(res))
+}
+
 pub fn handle_goto_implementation(
     world: WorldSnapshot,
     params: req::TextDocumentPositionParams,
@@ -391,7 +464,7 @@ pub fn handle_runnables(
                 continue;
             }
         }
-        res.push(to_lsp_runnable(&world, file_id, runnable)?);
+        res.extend(to_lsp_runnable(&world, file_id, runnable)?);
     }
     // Add `cargo check` and `cargo test` for the whole package
     match CargoTargetSpec::for_file(&world, file_id)? {
@@ -427,6 +500,60 @@ pub fn handle_runnables(
     Ok(res)
 }
 
+pub fn handle_discover_tests(
+    world: WorldSnapshot,
+    params: req::DiscoverTestsParams,
+) -> Result<Vec<req::TestItem>> {
+    let _p = profile("handle_discover_tests");
+    let file_id = params.text_document.map(|it| it.try_conv_with(&world)).transpose()?;
+    let mut res = Vec::new();
+    for item in world.analysis().discover_tests(file_id)? {
+        // Paths are only unique within a crate, prefix them with its root file.
+        let crate_root = item.crate_root.try_conv_with(&world)?;
+        let id = |path: &str| format!("{}#{}", crate_root, path);
+        let line_index = world.analysis().file_line_index(item.file_id)?;
+        let location = to_location(item.file_id, item.range, &world, &line_index)?;
+        let runnable = match (item.kind, item.runnable) {
+            (TestItemKind::Crate, _) => crate_test_runnable(&world, item.crate_root)?,
+            (_, Some(runnable)) => to_lsp_runnable(&world, item.file_id, runnable)?,
+            (_, None) => None,
+        };
+        res.push(req::TestItem {
+            id: id(&item.id),
+            parent: item.parent.as_deref().map(id),
+            kind: match item.kind {
+                TestItemKind::Crate => req::TestItemKind::Crate,
+                TestItemKind::Module => req::TestItemKind::Module,
+                TestItemKind::Test => req::TestItemKind::Test,
+            },
+            label: item.label,
+            location,
+            runnable,
+        });
+    }
+    Ok(res)
+}
+
+/// `cargo test` for the target which has `crate_root` as its root file.
+fn crate_test_runnable(world: &WorldSnapshot, crate_root: FileId) -> Result<Option<req::Runnable>> {
+    let spec = match CargoTargetSpec::for_file(world, crate_root)? {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    let label = format!("cargo test -p {}", spec.package);
+    let mut args = vec!["test".to_string()];
+    spec.push_to(&mut args);
+    Ok(Some(req::Runnable {
+        range: Default::default(),
+        label,
+        bin: "cargo".to_string(),
+        args,
+        extra_args: Vec::new(),
+        env: FxHashMap::default(),
+        cwd: world.workspace_root_for(crate_root).map(|root| root.to_owned()),
+    }))
+}
+
 pub fn handle_completion(
     world: WorldSnapshot,
     params: req::CompletionParams,
@@ -461,12 +588,106 @@ pub fn handle_completion(
     };
     let line_index = world.analysis().file_line_index(position.file_id)?;
     let line_endings = world.file_line_endings(position.file_id);
-    let items: Vec<CompletionItem> =
-        items.into_iter().map(|item| item.conv_with((&line_index, line_endings))).collect();
+    let mut unresolved = FxHashMap::default();
+    for (index, item) in items.iter().enumerate() {
+        if !item.needs_resolve() {
+            continue;
+        }
+        if let Some(file_id) = world.analysis().completion_resolve_file(item)? {
+            unresolved.insert(index, (file_id, item.clone()));
+        }
+    }
+    let unresolved_indices: FxHashSet<usize> = unresolved.keys().copied().collect();
+    let list_id = if unresolved.is_empty() {
+        None
+    } else {
+        Some(world.completion_resolve.write().store(unresolved))
+    };
+    let items: Vec<CompletionItem> = items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let needs_resolve = unresolved_indices.contains(&index);
+            let mut res = item.conv_with((&line_index, line_endings));
+            if let (true, Some(list_id)) = (needs_resolve, list_id) {
+                let data = CompletionResolveData { list_id, index };
+                res.data = Some(to_value(data).unwrap());
+            }
+            res
+        })
+        .collect();
 
     Ok(Some(items.into()))
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletionResolveData {
+    list_id: u64,
+    index: usize,
+}
+
+pub fn handle_completion_resolve(
+    world: WorldSnapshot,
+    mut original_item: CompletionItem,
+) -> Result<CompletionItem> {
+    let _p = profile("handle_completion_resolve");
+    let data = match original_item.data.take() {
+        Some(it) => it,
+        None => return Ok(original_item),
+    };
+    let data = from_json::<CompletionResolveData>("CompletionResolveData", data)?;
+    // The item is gone if another completion list was requested or the file
+    // defining it changed since then; the client keeps the item as it is.
+    let item = match world.completion_resolve.read().get(data.list_id, data.index) {
+        Some(it) => it,
+        None => return Ok(original_item),
+    };
+    let item = world.analysis().resolve_completion(item)?;
+    if let Some(docs) = item.documentation() {
+        original_item.documentation = Some(docs.conv());
+    }
+    if let Some(detail) = item.detail() {
+        original_item.detail = Some(detail.to_string());
+    }
+    Ok(original_item)
+}
+
+pub fn handle_document_link(
+    world: WorldSnapshot,
+    params: DocumentLinkParams,
+) -> Result<Option<Vec<DocumentLink>>> {
+    let _p = profile("handle_document_link");
+    let file_id = params.text_document.try_conv_with(&world)?;
+    let line_index = world.analysis().file_line_index(file_id)?;
+
+    let mut res = Vec::new();
+    for link in world.analysis().document_links(file_id)? {
+        let target = match link.target {
+            DocumentLinkTarget::File(file_id) => world.file_id_to_uri(file_id)?,
+            DocumentLinkTarget::Path { anchor, path } => {
+                let anchor = world.file_id_to_path(anchor);
+                let dir = match anchor.parent() {
+                    Some(it) => it,
+                    None => continue,
+                };
+                url_from_path_with_drive_lowercasing(path.to_path(dir))?
+            }
+            DocumentLinkTarget::Url(url) => match Url::parse(&url) {
+                Ok(it) => it,
+                Err(_) => continue,
+            },
+        };
+        res.push(DocumentLink {
+            range: link.range.conv_with(&line_index),
+            target,
+            tooltip: None,
+            data: None,
+        });
+    }
+    Ok(Some(res))
+}
+
 pub fn handle_folding_range(
     world: WorldSnapshot,
     params: FoldingRangeParams,
@@ -512,7 +733,7 @@ pub fn handle_signature_help(
 pub fn handle_hover(
     world: WorldSnapshot,
     params: req::TextDocumentPositionParams,
-) -> Result<Option<Hover>> {
+) -> Result<Option<req::Hover>> {
     let _p = profile("handle_hover");
     let position = params.try_conv_with(&world)?;
     let info = match world.analysis().hover(position)? {
@@ -521,14 +742,122 @@ pub fn handle_hover(
     };
     let line_index = world.analysis.file_line_index(position.file_id)?;
     let range = info.range.conv_with(&line_index);
-    let res = Hover {
+    let hover = Hover {
         contents: HoverContents::Markup(MarkupContent {
             kind: MarkupKind::Markdown,
             value: crate::markdown::format_docs(&info.info.to_markup()),
         }),
         range: Some(range),
     };
-    Ok(Some(res))
+    // The actions are links to commands of our own client, which other
+    // clients don't have.
+    let actions = if world.config.client_caps.hover_actions {
+        to_hover_actions(&world, position.file_id, info.info.actions())?
+    } else {
+        Vec::new()
+    };
+    Ok(Some(req::Hover { hover, actions }))
+}
+
+fn to_hover_actions(
+    world: &WorldSnapshot,
+    file_id: FileId,
+    actions: &[HoverAction],
+) -> Result<Vec<req::CommandLinkGroup>> {
+    let config = world.config.hover_actions;
+    let mut res = Vec::new();
+    for action in actions {
+        match action {
+            HoverAction::Implementation(position) if config.implementations => {
+                res.extend(show_impls_link(world, *position)?);
+            }
+            HoverAction::Runnable(runnable) if config.run || config.debug => {
+                res.extend(runnable_links(world, file_id, runnable.clone())?);
+            }
+            HoverAction::GoToType(targets) if config.goto_type_def => {
+                res.push(goto_type_links(world, targets)?);
+            }
+            _ => {}
+        }
+    }
+    Ok(res)
+}
+
+fn show_impls_link(
+    world: &WorldSnapshot,
+    position: FilePosition,
+) -> Result<Option<req::CommandLinkGroup>> {
+    let navs = match world.analysis().goto_implementation(position)? {
+        Some(it) if !it.info.is_empty() => it.info,
+        _ => return Ok(None),
+    };
+    let locations =
+        navs.iter().map(|nav| nav.try_conv_with(world)).collect::<Result<Vec<Location>>>()?;
+    let line_index = world.analysis().file_line_index(position.file_id)?;
+    let uri = position.file_id.try_conv_with(world)?;
+    let command = Command {
+        title: format!("Go to implementations ({})", locations.len()),
+        command: "rust-analyzer.showReferences".into(),
+        arguments: Some(vec![
+            to_value(uri).unwrap(),
+            to_value(position.offset.conv_with(&line_index)).unwrap(),
+            to_value(locations).unwrap(),
+        ]),
+    };
+    Ok(Some(req::CommandLinkGroup {
+        title: None,
+        commands: vec![req::CommandLink { command, tooltip: None }],
+    }))
+}
+
+fn runnable_links(
+    world: &WorldSnapshot,
+    file_id: FileId,
+    runnable: Runnable,
+) -> Result<Option<req::CommandLinkGroup>> {
+    let title = run_title(&runnable.kind);
+    let debuggable = is_debuggable(&runnable.kind);
+    let mut r = match to_lsp_runnable(world, file_id, runnable)? {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    let mut commands = Vec::new();
+    if world.config.hover_actions.run {
+        let command = Command {
+            title: title.to_string(),
+            command: "rust-analyzer.runSingle".into(),
+            arguments: Some(vec![to_value(&r).unwrap()]),
+        };
+        commands.push(req::CommandLink { command, tooltip: Some(r.label.clone()) });
+    }
+    if world.config.hover_actions.debug && debuggable {
+        make_debuggable(&mut r);
+        let command = Command {
+            title: "Debug".into(),
+            command: "rust-analyzer.debugSingle".into(),
+            arguments: Some(vec![to_value(&r).unwrap()]),
+        };
+        commands.push(req::CommandLink { command, tooltip: Some(r.label.clone()) });
+    }
+    Ok(Some(req::CommandLinkGroup { title: None, commands }))
+}
+
+fn goto_type_links(
+    world: &WorldSnapshot,
+    targets: &[HoverGotoTypeData],
+) -> Result<req::CommandLinkGroup> {
+    let mut commands = Vec::new();
+    for target in targets {
+        let location: Location = (&target.nav).try_conv_with(world)?;
+        let command = Command {
+            title: target.mod_path.clone(),
+            command: "rust-analyzer.gotoLocation".into(),
+            arguments: Some(vec![to_value(location).unwrap()]),
+        };
+        let tooltip = target.nav.description().map(|it| it.to_string());
+        commands.push(req::CommandLink { command, tooltip });
+    }
+    Ok(req::CommandLinkGroup { title: Some("Go to".into()), commands })
 }
 
 pub fn handle_prepare_rename(
@@ -573,6 +902,112 @@ pub fn handle_rename(world: WorldSnapshot, params: RenameParams) -> Result<Optio
     Ok(Some(source_change_req.workspace_edit))
 }
 
+pub fn handle_will_rename_files(
+    world: WorldSnapshot,
+    params: req::RenameFilesParams,
+) -> Result<Option<WorkspaceEdit>> {
+    let _p = profile("handle_will_rename_files");
+    let path_to_file_id = |path: &Path| world.vfs.read().path2file(path).map(|it| FileId(it.0));
+    let exists = |path: &Path| path_to_file_id(path).is_some();
+    let mut renamed = FxHashSet::default();
+    let mut source_file_edits = Vec::new();
+    for rename in params.files {
+        let (old_path, new_path) =
+            match (rename.old_uri.to_file_path(), rename.new_uri.to_file_path()) {
+                (Ok(old_path), Ok(new_path)) => (old_path, new_path),
+                _ => continue,
+            };
+        let module = match renamed_module(&old_path, &new_path, &exists) {
+            Some(it) => it,
+            None => continue,
+        };
+        let file_id = match path_to_file_id(&module.file) {
+            Some(it) => it,
+            None => continue,
+        };
+        // A `foo/mod.rs` and its `foo` directory may be renamed together.
+        if !renamed.insert(file_id) {
+            continue;
+        }
+        let change = match &module.new_parent_dir {
+            None => world.analysis().will_rename_file(file_id, &module.new_name)?,
+            Some(dir) => {
+                let new_parent = match parent_module_file(dir, &exists)
+                    .and_then(|file| path_to_file_id(&file))
+                {
+                    Some(it) => it,
+                    None => continue,
+                };
+                world.analysis().will_move_file(file_id, new_parent, &module.new_name)?
+            }
+        };
+        if let Some(change) = change {
+            source_file_edits.extend(change.source_file_edits);
+        }
+    }
+    if source_file_edits.is_empty() {
+        return Ok(None);
+    }
+    let change = SourceChange::source_file_edits("rename", source_file_edits);
+    Ok(Some(change.try_conv_with(&world)?.workspace_edit))
+}
+
+/// A module whose file or directory is renamed by the client.
+#[derive(Debug, PartialEq, Eq)]
+struct RenamedModule {
+    /// The file which defines the module.
+    file: PathBuf,
+    /// The directory of the parent module after the rename, if it changes.
+    new_parent_dir: Option<PathBuf>,
+    new_name: String,
+}
+
+/// Figures out which module is affected by renaming `old_path` to `new_path`.
+/// A directory stands for its `mod.rs`.
+fn renamed_module(
+    old_path: &Path,
+    new_path: &Path,
+    exists: impl Fn(&Path) -> bool,
+) -> Option<RenamedModule> {
+    let is_rs = |path: &Path| path.extension() == Some("rs".as_ref());
+    let is_mod_rs = |file: &Path| file.file_stem() == Some("mod".as_ref());
+    let (file, old_parent_dir, new_parent_dir, new_name) = if is_rs(old_path) {
+        if !is_rs(new_path) {
+            return None;
+        }
+        let module_dir = |file: &Path| {
+            if is_mod_rs(file) {
+                let dir = file.parent()?;
+                Some((dir.parent()?.to_path_buf(), dir.file_name()?))
+            } else {
+                Some((file.parent()?.to_path_buf(), file.file_stem()?))
+            }
+        };
+        let (old_parent_dir, _) = module_dir(old_path)?;
+        let (new_parent_dir, new_name) = module_dir(new_path)?;
+        (old_path.to_path_buf(), old_parent_dir, new_parent_dir, new_name)
+    } else {
+        // Renaming the directory of a `<dir>.rs` module only moves its
+        // children, the module itself stays where it is.
+        let file = old_path.join("mod.rs");
+        if !exists(&file) {
+            return None;
+        }
+        let old_parent_dir = old_path.parent()?.to_path_buf();
+        (file, old_parent_dir, new_path.parent()?.to_path_buf(), new_path.file_name()?)
+    };
+    let new_name = new_name.to_str()?.to_string();
+    let new_parent_dir = if old_parent_dir != new_parent_dir { Some(new_parent_dir) } else { None };
+    Some(RenamedModule { file, new_parent_dir, new_name })
+}
+
+/// Finds the file of the module whose children live in `dir`.
+fn parent_module_file(dir: &Path, exists: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+    let candidates =
+        [dir.join("mod.rs"), dir.with_extension("rs"), dir.join("lib.rs"), dir.join("main.rs")];
+    candidates.iter().find(|it| exists(it)).cloned()
+}
+
 pub fn handle_references(
     world: WorldSnapshot,
     params: req::ReferenceParams,
@@ -625,15 +1060,30 @@ pub fn handle_formatting(
     params: DocumentFormattingParams,
 ) -> Result<Option<Vec<TextEdit>>> {
     let _p = profile("handle_formatting");
-    let file_id = params.text_document.try_conv_with(&world)?;
+    run_rustfmt(&world, &params.text_document, None)
+}
+
+pub fn handle_range_formatting(
+    world: WorldSnapshot,
+    params: DocumentRangeFormattingParams,
+) -> Result<Option<Vec<TextEdit>>> {
+    let _p = profile("handle_range_formatting");
+    run_rustfmt(&world, &params.text_document, Some(params.range))
+}
+
+fn run_rustfmt(
+    world: &WorldSnapshot,
+    text_document: &TextDocumentIdentifier,
+    range: Option<Range>,
+) -> Result<Option<Vec<TextEdit>>> {
+    let file_id = text_document.try_conv_with(world)?;
     let file = world.analysis().file_text(file_id)?;
     let crate_ids = world.analysis().crate_for(file_id)?;
 
     let file_line_index = world.analysis().file_line_index(file_id)?;
-    let end_position = TextSize::of(file.as_str()).conv_with(&file_line_index);
 
     let mut rustfmt = match &world.config.rustfmt {
-        RustfmtConfig::Rustfmt { extra_args } => {
+        RustfmtConfig::Rustfmt { extra_args, enable_range_formatting } => {
             let mut cmd = process::Command::new("rustfmt");
             cmd.args(extra_args);
             if let Some(&crate_id) = crate_ids.first() {
@@ -642,16 +1092,44 @@ pub fn handle_formatting(
                 cmd.arg("--edition");
                 cmd.arg(edition.to_string());
             }
+            if let Some(range) = range {
+                if !*enable_range_formatting {
+                    return Err(LspError::new(
+                        ErrorCode::InvalidRequest as i32,
+                        String::from(
+                            "rustfmt range formatting is unstable. \
+                            Opt-in by using a nightly build of rustfmt and setting \
+                            `rustfmt.enableRangeFormatting` to true in your LSP configuration",
+                        ),
+                    )
+                    .into());
+                }
+                let (start_line, end_line) =
+                    file_lines_range(&file_line_index, range.conv_with(&file_line_index));
+                cmd.arg("--unstable-features");
+                cmd.arg("--file-lines");
+                cmd.arg(
+                    serde_json::json!([{ "file": "stdin", "range": [start_line, end_line] }])
+                        .to_string(),
+                );
+            }
             cmd
         }
         RustfmtConfig::CustomCommand { command, args } => {
+            if range.is_some() {
+                return Err(LspError::new(
+                    ErrorCode::InvalidRequest as i32,
+                    String::from("range formatting is not supported with a custom rustfmt command"),
+                )
+                .into());
+            }
             let mut cmd = process::Command::new(command);
             cmd.args(args);
             cmd
         }
     };
 
-    if let Ok(path) = params.text_document.uri.to_file_path() {
+    if let Ok(path) = text_document.uri.to_file_path() {
         if let Some(parent) = path.parent() {
             rustfmt.current_dir(parent);
         }
@@ -690,10 +1168,22 @@ pub fn handle_formatting(
         }
     }
 
-    Ok(Some(vec![TextEdit {
-        range: Range::new(Position::new(0, 0), end_position),
-        new_text: captured_stdout,
-    }]))
+    // `file` has normalized line endings, so the formatted text must be
+    // normalized as well before diffing. Conversion to LSP edits restores them.
+    let formatted = captured_stdout.replace("\r\n", "\n");
+    let line_endings = world.file_line_endings(file_id);
+    Ok(Some(diff(&file, &formatted).conv_with((&file_line_index, line_endings))))
+}
+
+/// The lines `range` touches, as 1-based, inclusive line numbers for
+/// `--file-lines`. A selection of whole lines usually ends at the start of the
+/// next line, which is then left out.
+fn file_lines_range(line_index: &LineIndex, range: TextRange) -> (u32, u32) {
+    let start = line_index.line_col(range.start());
+    let end = line_index.line_col(range.end());
+    let end_line =
+        if end.col_utf16 == 0 && end.line > start.line { end.line } else { end.line + 1 };
+    (start.line + 1, end_line)
 }
 
 fn create_single_code_action(assist: Assist, world: &WorldSnapshot) -> Result<CodeAction> {
@@ -830,13 +1320,12 @@ pub fn handle_code_lens(
 
     // Gather runnables
     for runnable in world.analysis().runnables(file_id)? {
-        let title = match &runnable.kind {
-            RunnableKind::Test { .. } | RunnableKind::TestMod { .. } => "▶️\u{fe0e}Run Test",
-            RunnableKind::Bench { .. } => "Run Bench",
-            RunnableKind::Bin => "Run",
-        }
-        .to_string();
-        let mut r = to_lsp_runnable(&world, file_id, runnable)?;
+        let title = run_title(&runnable.kind).to_string();
+        let debuggable = is_debuggable(&runnable.kind);
+        let mut r = match to_lsp_runnable(&world, file_id, runnable)? {
+            Some(it) => it,
+            None => continue,
+        };
         let lens = CodeLens {
             range: r.range,
             command: Some(Command {
@@ -848,11 +1337,10 @@ pub fn handle_code_lens(
         };
         lenses.push(lens);
 
-        if r.args[0] == "run" {
-            r.args[0] = "build".into();
-        } else {
-            r.args.push("--no-run".into());
+        if !debuggable {
+            continue;
         }
+        make_debuggable(&mut r);
         let debug_lens = CodeLens {
             range: r.range,
             command: Some(Command {
@@ -891,6 +1379,29 @@ pub fn handle_code_lens(
     Ok(Some(lenses))
 }
 
+fn run_title(kind: &RunnableKind) -> &'static str {
+    match kind {
+        RunnableKind::Test { .. } | RunnableKind::TestMod { .. } => "▶️\u{fe0e}Run Test",
+        RunnableKind::Bench { .. } => "Run Bench",
+        RunnableKind::DocTest { .. } => "▶️\u{fe0e}Run Doctest",
+        RunnableKind::Bin => "Run",
+    }
+}
+
+/// Doctests are compiled and run by rustdoc, there's no binary to attach to.
+fn is_debuggable(kind: &RunnableKind) -> bool {
+    !matches!(kind, RunnableKind::DocTest { .. })
+}
+
+/// Debuggers launch the binary themselves, so cargo only needs to build it.
+fn make_debuggable(runnable: &mut req::Runnable) {
+    if runnable.args[0] == "run" {
+        runnable.args[0] = "build".into();
+    } else {
+        runnable.args.push("--no-run".into());
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 enum CodeLensResolveData {
@@ -950,13 +1461,20 @@ pub fn handle_document_highlight(
     let file_id = params.text_document.try_conv_with(&world)?;
     let line_index = world.analysis().file_line_index(file_id)?;
 
-    let refs = match world
-        .analysis()
-        .find_all_refs(params.try_conv_with(&world)?, Some(SearchScope::single_file(file_id)))?
-    {
-        None => return Ok(None),
-        Some(refs) => refs,
-    };
+    let position = params.try_conv_with(&world)?;
+    if let Some(ranges) = world.analysis().highlight_related(position)? {
+        let res = ranges
+            .into_iter()
+            .map(|range| DocumentHighlight { range: range.conv_with(&line_index), kind: None })
+            .collect();
+        return Ok(Some(res));
+    }
+
+    let refs =
+        match world.analysis().find_all_refs(position, Some(SearchScope::single_file(file_id)))? {
+            None => return Ok(None),
+            Some(refs) => refs,
+        };
 
     Ok(Some(
         refs.into_iter()
@@ -977,41 +1495,102 @@ pub fn handle_ssr(world: WorldSnapshot, params: req::SsrParams) -> Result<req::S
         .try_conv_with(&world)
 }
 
+pub fn handle_execute_command(
+    world: WorldSnapshot,
+    params: req::ExecuteCommandParams,
+) -> Result<req::ApplyWorkspaceEditParams> {
+    let _p = profile("handle_execute_command");
+    let source_change = match params.command.as_str() {
+        req::SSR_COMMAND => {
+            let query: String = command_argument(&params)?;
+            handle_ssr(world, req::SsrParams { query, parse_only: false })?
+        }
+        req::APPLY_ASSIST_COMMAND => {
+            let assist_params: req::ApplyAssistParams = command_argument(&params)?;
+            let frange =
+                (&assist_params.text_document, assist_params.range).try_conv_with(&world)?;
+            let assist = world
+                .analysis()
+                .assists(frange)?
+                .into_iter()
+                .find(|it| it.id.0 == assist_params.id)
+                .ok_or_else(|| {
+                    LspError::new(
+                        ErrorCode::InvalidParams as i32,
+                        format!("assist {} is not applicable here", assist_params.id),
+                    )
+                })?;
+            assist.source_change.try_conv_with(&world)?
+        }
+        command => {
+            return Err(LspError::new(
+                ErrorCode::InvalidParams as i32,
+                format!("unknown command: {}", command),
+            )
+            .into())
+        }
+    };
+    Ok(req::ApplyWorkspaceEditParams {
+        label: Some(source_change.label),
+        edit: source_change.workspace_edit,
+    })
+}
+
+fn command_argument<T: DeserializeOwned>(params: &req::ExecuteCommandParams) -> Result<T> {
+    match params.arguments.as_slice() {
+        [arg] => from_json("command argument", arg.clone()),
+        _ => Err(LspError::new(
+            ErrorCode::InvalidParams as i32,
+            format!("{} expects exactly one argument", params.command),
+        )
+        .into()),
+    }
+}
+
 pub fn publish_diagnostics(world: &WorldSnapshot, file_id: FileId) -> Result<DiagnosticTask> {
     let _p = profile("publish_diagnostics");
     let line_index = world.analysis().file_line_index(file_id)?;
-    let diagnostics: Vec<Diagnostic> = world
+    let to_lsp_diagnostic = |d: ra_ide::Diagnostic, tags: Option<Vec<DiagnosticTag>>| Diagnostic {
+        range: d.range.conv_with(&line_index),
+        severity: Some(d.severity.conv()),
+        code: None,
+        source: Some("rust-analyzer".to_string()),
+        message: d.message,
+        related_information: None,
+        tags,
+    };
+    let mut diagnostics: Vec<Diagnostic> = world
         .analysis()
         .diagnostics(file_id)?
         .into_iter()
-        .map(|d| Diagnostic {
-            range: d.range.conv_with(&line_index),
-            severity: Some(d.severity.conv()),
-            code: None,
-            source: Some("rust-analyzer".to_string()),
-            message: d.message,
-            related_information: None,
-            tags: None,
-        })
+        .map(|d| to_lsp_diagnostic(d, None))
         .collect();
+    if world.config.inactive_code_diagnostics {
+        diagnostics.extend(
+            world
+                .analysis()
+                .inactive_code_diagnostics(file_id)?
+                .into_iter()
+                .map(|d| to_lsp_diagnostic(d, Some(vec![DiagnosticTag::Unnecessary]))),
+        );
+    }
     Ok(DiagnosticTask::SetNative(file_id, diagnostics))
 }
 
+/// Converts the runnable, unless cargo can't run it in the target of the file.
 fn to_lsp_runnable(
     world: &WorldSnapshot,
     file_id: FileId,
     runnable: Runnable,
-) -> Result<req::Runnable> {
+) -> Result<Option<req::Runnable>> {
     let spec = CargoTargetSpec::for_file(world, file_id)?;
+    if !CargoTargetSpec::can_run(spec.as_ref(), &runnable.kind) {
+        return Ok(None);
+    }
+    let label = runnable_label(spec.as_ref(), &runnable.kind);
     let (args, extra_args) = CargoTargetSpec::runnable_args(spec, &runnable.kind)?;
     let line_index = world.analysis().file_line_index(file_id)?;
-    let label = match &runnable.kind {
-        RunnableKind::Test { test_id, .. } => format!("test {}", test_id),
-        RunnableKind::TestMod { path } => format!("test-mod {}", path),
-        RunnableKind::Bench { test_id } => format!("bench {}", test_id),
-        RunnableKind::Bin => "run binary".to_string(),
-    };
-    Ok(req::Runnable {
+    Ok(Some(req::Runnable {
         range: runnable.range.conv_with(&line_index),
         label,
         bin: "cargo".to_string(),
@@ -1023,7 +1602,22 @@ fn to_lsp_runnable(
             m
         },
         cwd: world.workspace_root_for(file_id).map(|root| root.to_owned()),
-    })
+    }))
+}
+
+fn runnable_label(spec: Option<&CargoTargetSpec>, kind: &RunnableKind) -> String {
+    match kind {
+        RunnableKind::Test { test_id, .. } => format!("test {}", test_id),
+        RunnableKind::TestMod { path } => format!("test-mod {}", path),
+        RunnableKind::Bench { test_id } => format!("bench {}", test_id),
+        RunnableKind::DocTest { test_id } => format!("doctest {}", test_id),
+        RunnableKind::Bin => match spec {
+            Some(spec) if spec.target_kind == TargetKind::Example => {
+                format!("run example {}", spec.target)
+            }
+            _ => "run binary".to_string(),
+        },
+    }
 }
 
 pub fn handle_inlay_hints(
@@ -1076,7 +1670,7 @@ pub fn handle_call_hierarchy_incoming(
     let frange: FileRange = (&doc, item.range).try_conv_with(&world)?;
     let fpos = FilePosition { file_id: frange.file_id, offset: frange.range.start() };
 
-    let call_items = match world.analysis().incoming_calls(fpos)? {
+    let call_items = match world.analysis().incoming_calls(fpos, &world.config.call_hierarchy)? {
         None => return Ok(None),
         Some(it) => it,
     };
@@ -1108,7 +1702,7 @@ pub fn handle_call_hierarchy_outgoing(
     let frange: FileRange = (&doc, item.range).try_conv_with(&world)?;
     let fpos = FilePosition { file_id: frange.file_id, offset: frange.range.start() };
 
-    let call_items = match world.analysis().outgoing_calls(fpos)? {
+    let call_items = match world.analysis().outgoing_calls(fpos, &world.config.call_hierarchy)? {
         None => return Ok(None),
         Some(it) => it,
     };
@@ -1129,6 +1723,57 @@ pub fn handle_call_hierarchy_outgoing(
     Ok(Some(res))
 }
 
+pub fn handle_prepare_type_hierarchy(
+    world: WorldSnapshot,
+    params: req::TextDocumentPositionParams,
+) -> Result<Option<Vec<req::TypeHierarchyItem>>> {
+    let _p = profile("handle_prepare_type_hierarchy");
+    let position = params.try_conv_with(&world)?;
+    let hierarchy = match world.analysis().type_hierarchy(position)? {
+        None => return Ok(None),
+        Some(it) => it.info,
+    };
+    Ok(Some(vec![to_type_hierarchy_item(&world, hierarchy.target)?]))
+}
+
+pub fn handle_type_hierarchy_supertypes(
+    world: WorldSnapshot,
+    params: req::TypeHierarchyParams,
+) -> Result<Option<Vec<req::TypeHierarchyItem>>> {
+    let _p = profile("handle_type_hierarchy_supertypes");
+    let hierarchy = match type_hierarchy_of_item(&world, params.item)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let navs = hierarchy.supertraits.into_iter().chain(hierarchy.implemented_traits);
+    let res = navs.map(|nav| to_type_hierarchy_item(&world, nav)).collect::<Result<_>>()?;
+    Ok(Some(res))
+}
+
+pub fn handle_type_hierarchy_subtypes(
+    world: WorldSnapshot,
+    params: req::TypeHierarchyParams,
+) -> Result<Option<Vec<req::TypeHierarchyItem>>> {
+    let _p = profile("handle_type_hierarchy_subtypes");
+    let hierarchy = match type_hierarchy_of_item(&world, params.item)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let navs = hierarchy.subtraits.into_iter().chain(hierarchy.implementors);
+    let res = navs.map(|nav| to_type_hierarchy_item(&world, nav)).collect::<Result<_>>()?;
+    Ok(Some(res))
+}
+
+fn type_hierarchy_of_item(
+    world: &WorldSnapshot,
+    item: req::TypeHierarchyItem,
+) -> Result<Option<TypeHierarchy>> {
+    let doc = TextDocumentIdentifier::new(item.uri);
+    let frange: FileRange = (&doc, item.selection_range).try_conv_with(&world)?;
+    let position = FilePosition { file_id: frange.file_id, offset: frange.range.start() };
+    Ok(world.analysis().type_hierarchy(position)?.map(|it| it.info))
+}
+
 pub fn handle_semantic_tokens(
     world: WorldSnapshot,
     params: SemanticTokensParams,
@@ -1139,41 +1784,142 @@ pub fn handle_semantic_tokens(
     let text = world.analysis().file_text(file_id)?;
     let line_index = world.analysis().file_line_index(file_id)?;
 
+    let highlights = world.analysis().highlight(file_id)?;
+    let tokens = semantic_tokens(&text, &line_index, highlights);
+
+    Ok(Some(tokens.into()))
+}
+
+pub fn handle_semantic_tokens_range(
+    world: WorldSnapshot,
+    params: SemanticTokensRangeParams,
+) -> Result<Option<SemanticTokensRangeResult>> {
+    let _p = profile("handle_semantic_tokens_range");
+
+    let frange = (&params.text_document, params.range).try_conv_with(&world)?;
+    let text = world.analysis().file_text(frange.file_id)?;
+    let line_index = world.analysis().file_line_index(frange.file_id)?;
+
+    let highlights = world.analysis().highlight_range(frange)?;
+    let tokens = semantic_tokens(&text, &line_index, highlights);
+
+    Ok(Some(tokens.into()))
+}
+
+/// Semantic tokens can't span several lines, so highlights which do, like
+/// inactive code, are split into a token per line.
+fn semantic_tokens(
+    text: &str,
+    line_index: &LineIndex,
+    highlights: Vec<HighlightedRange>,
+) -> SemanticTokens {
     let mut builder = SemanticTokensBuilder::default();
 
-    for highlight_range in world.analysis().highlight(file_id)?.into_iter() {
+    for highlight_range in highlights {
         let (token_index, modifier_bitset) = highlight_range.highlight.conv();
         for mut range in line_index.lines(highlight_range.range) {
             if text[range].ends_with('\n') {
                 range = TextRange::new(range.start(), range.end() - TextSize::of('\n'));
             }
-            let range = range.conv_with(&line_index);
+            let range = range.conv_with(line_index);
             builder.push(range, token_index, modifier_bitset);
         }
     }
 
-    let tokens = builder.build();
-
-    Ok(Some(tokens.into()))
+    builder.build()
 }
 
-pub fn handle_semantic_tokens_range(
-    world: WorldSnapshot,
-    params: SemanticTokensRangeParams,
-) -> Result<Option<SemanticTokensRangeResult>> {
-    let _p = profile("handle_semantic_tokens_range");
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
 
-    let frange = (&params.text_document, params.range).try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(frange.file_id)?;
+    use ra_ide::{LineIndex, RunnableKind, TestId};
+    use ra_project_model::TargetKind;
+    use ra_syntax::TextRange;
 
-    let mut builder = SemanticTokensBuilder::default();
+    use super::{
+        file_lines_range, parent_module_file, renamed_module, runnable_label, RenamedModule,
+    };
+    use crate::cargo_target_spec::CargoTargetSpec;
+
+    const FILES: &[&str] =
+        &["/src/lib.rs", "/src/foo.rs", "/src/foo/baz.rs", "/src/bar/mod.rs", "/src/quux.rs"];
 
-    for highlight_range in world.analysis().highlight_range(frange)?.into_iter() {
-        let (token_type, token_modifiers) = highlight_range.highlight.conv();
-        builder.push(highlight_range.range.conv_with(&line_index), token_type, token_modifiers);
+    fn exists(path: &Path) -> bool {
+        FILES.iter().any(|it| Path::new(it) == path)
     }
 
-    let tokens = builder.build();
+    fn check(old_path: &str, new_path: &str, expected: Option<(&str, Option<&str>, &str)>) {
+        let actual = renamed_module(Path::new(old_path), Path::new(new_path), exists);
+        let expected = expected.map(|(file, new_parent_dir, new_name)| RenamedModule {
+            file: PathBuf::from(file),
+            new_parent_dir: new_parent_dir.map(PathBuf::from),
+            new_name: new_name.to_string(),
+        });
+        assert_eq!(actual, expected);
+    }
 
-    Ok(Some(tokens.into()))
+    #[test]
+    fn renamed_module_works() {
+        check("/src/foo.rs", "/src/bar.rs", Some(("/src/foo.rs", None, "bar")));
+        check("/src/foo.rs", "/src/foo/mod.rs", Some(("/src/foo.rs", None, "foo")));
+        check("/src/bar/mod.rs", "/src/baz/mod.rs", Some(("/src/bar/mod.rs", None, "baz")));
+        check("/src/bar", "/src/baz", Some(("/src/bar/mod.rs", None, "baz")));
+        // `/src/foo.rs` stays, so `mod foo;` must not change.
+        check("/src/foo", "/src/quux", None);
+        check("/src/foo", "/src/bar/foo", None);
+        check("/src/baz", "/src/quux", None);
+        check("/src/foo.rs", "/src/foo.txt", None);
+    }
+
+    #[test]
+    fn renamed_module_across_directories() {
+        check("/src/quux.rs", "/src/foo/bar.rs", Some(("/src/quux.rs", Some("/src/foo"), "bar")));
+        check(
+            "/src/foo/baz.rs",
+            "/src/bar/baz/mod.rs",
+            Some(("/src/foo/baz.rs", Some("/src/bar"), "baz")),
+        );
+        check("/src/bar", "/src/foo/bar", Some(("/src/bar/mod.rs", Some("/src/foo"), "bar")));
+    }
+
+    #[test]
+    fn parent_module_file_works() {
+        let check = |dir: &str, expected: Option<&str>| {
+            assert_eq!(parent_module_file(Path::new(dir), exists), expected.map(PathBuf::from))
+        };
+        check("/src", Some("/src/lib.rs"));
+        check("/src/foo", Some("/src/foo.rs"));
+        check("/src/bar", Some("/src/bar/mod.rs"));
+        check("/src/baz", None);
+    }
+
+    #[test]
+    fn file_lines_range_leaves_out_the_next_line() {
+        let line_index = LineIndex::new("fn a() {}\nfn b() {}\nfn c() {}\n");
+        let range = |start: u32, end: u32| {
+            file_lines_range(&line_index, TextRange::new(start.into(), end.into()))
+        };
+        assert_eq!(range(0, 5), (1, 1));
+        assert_eq!(range(0, 10), (1, 1));
+        assert_eq!(range(0, 12), (1, 2));
+        assert_eq!(range(10, 20), (2, 2));
+        assert_eq!(range(10, 10), (2, 2));
+    }
+
+    #[test]
+    fn runnable_labels() {
+        let spec = |target_kind| CargoTargetSpec {
+            package: "foo".to_string(),
+            target: "quux".to_string(),
+            target_kind,
+        };
+        let doctest = RunnableKind::DocTest { test_id: TestId::Path("Bar::baz".to_string()) };
+        assert_eq!(runnable_label(Some(&spec(TargetKind::Lib)), &doctest), "doctest Bar::baz");
+        assert_eq!(
+            runnable_label(Some(&spec(TargetKind::Example)), &RunnableKind::Bin),
+            "run example quux"
+        );
+        assert_eq!(runnable_label(Some(&spec(TargetKind::Bin)), &RunnableKind::Bin), "run binary");
+    }
 }
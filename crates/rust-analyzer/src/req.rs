@@ -1,6 +1,6 @@
 //! Defines `rust-analyzer` specific custom messages.
 
-use lsp_types::{Location, Position, Range, TextDocumentIdentifier};
+use lsp_types::{Command, Location, Position, Range, TextDocumentIdentifier, Url};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 
@@ -9,13 +9,14 @@ pub use lsp_types::{
     CodeLensParams, CompletionParams, CompletionResponse, ConfigurationItem, ConfigurationParams,
     DiagnosticTag, DidChangeConfigurationParams, DidChangeWatchedFilesParams,
     DidChangeWatchedFilesRegistrationOptions, DocumentOnTypeFormattingParams, DocumentSymbolParams,
-    DocumentSymbolResponse, FileSystemWatcher, Hover, InitializeResult, MessageType,
+    DocumentSymbolResponse, ExecuteCommandParams, FileSystemWatcher, InitializeResult, MessageType,
     PartialResultParams, ProgressParams, ProgressParamsValue, ProgressToken,
     PublishDiagnosticsParams, ReferenceParams, Registration, RegistrationParams, SelectionRange,
     SelectionRangeParams, SemanticTokensParams, SemanticTokensRangeParams,
     SemanticTokensRangeResult, SemanticTokensResult, ServerCapabilities, ShowMessageParams,
     SignatureHelp, SymbolKind, TextDocumentEdit, TextDocumentPositionParams, TextEdit,
-    WorkDoneProgressParams, WorkspaceEdit, WorkspaceSymbolParams,
+    Unregistration, UnregistrationParams, WorkDoneProgressParams, WorkspaceEdit, WorkspaceFolder,
+    WorkspaceSymbolParams,
 };
 use std::path::PathBuf;
 
@@ -50,6 +51,29 @@ pub struct SyntaxTreeParams {
     pub range: Option<Range>,
 }
 
+pub enum ViewHir {}
+
+impl Request for ViewHir {
+    type Params = TextDocumentPositionParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewHir";
+}
+
+pub enum ViewCrateGraph {}
+
+impl Request for ViewCrateGraph {
+    type Params = ViewCrateGraphParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewCrateGraph";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewCrateGraphParams {
+    /// Leave out the crates of the sysroot, like `core` and `std`.
+    pub hide_sysroot: bool,
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ExpandedMacro {
@@ -72,6 +96,39 @@ pub struct ExpandMacroParams {
     pub position: Option<Position>,
 }
 
+pub enum ExpandAllMacros {}
+
+impl Request for ExpandAllMacros {
+    type Params = ExpandAllMacrosParams;
+    type Result = Option<ExpandedFile>;
+    const METHOD: &'static str = "rust-analyzer/expandAllMacros";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpandAllMacrosParams {
+    pub text_document: TextDocumentIdentifier,
+    /// Only expand the top-level item at this position.
+    pub position: Option<Position>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpandedFile {
+    pub text: String,
+    pub origins: Vec<ExpansionOrigin>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpansionOrigin {
+    /// The range of the token in `ExpandedFile::text`.
+    pub range: Range,
+    pub location: Location,
+    /// Whether the token comes from the macro definition rather than the call.
+    pub from_definition: bool,
+}
+
 pub enum FindMatchingBrace {}
 
 impl Request for FindMatchingBrace {
@@ -145,6 +202,40 @@ pub struct Runnable {
     pub cwd: Option<PathBuf>,
 }
 
+pub enum DiscoverTests {}
+
+impl Request for DiscoverTests {
+    type Params = DiscoverTestsParams;
+    type Result = Vec<TestItem>;
+    const METHOD: &'static str = "rust-analyzer/discoverTests";
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverTestsParams {
+    /// Only return the tests defined in this document, and their ancestors.
+    pub text_document: Option<TextDocumentIdentifier>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestItem {
+    pub id: String,
+    pub parent: Option<String>,
+    pub kind: TestItemKind,
+    pub label: String,
+    pub location: Location,
+    pub runnable: Option<Runnable>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum TestItemKind {
+    Crate,
+    Module,
+    Test,
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SourceChange {
@@ -172,6 +263,10 @@ pub enum InlayKind {
     TypeHint,
     ParameterHint,
     ChainingHint,
+    LifetimeHint,
+    ClosureReturnTypeHint,
+    ClosureCaptureHint,
+    AdjustmentHint,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -195,3 +290,131 @@ pub struct SsrParams {
     pub query: String,
     pub parse_only: bool,
 }
+
+/// Commands the server executes itself via `workspace/executeCommand`.
+///
+/// These live in a separate namespace from the client-side `rust-analyzer.*`
+/// commands, so that clients which register all server commands don't clash
+/// with the VS Code extension.
+pub const COLLECT_GARBAGE_COMMAND: &str = "rust-analyzer.server.collectGarbage";
+pub const RELOAD_WORKSPACE_COMMAND: &str = "rust-analyzer.server.reloadWorkspace";
+/// Takes the SSR query string as the only argument.
+pub const SSR_COMMAND: &str = "rust-analyzer.server.ssr";
+/// Takes `ApplyAssistParams` as the only argument.
+pub const APPLY_ASSIST_COMMAND: &str = "rust-analyzer.server.applyAssist";
+
+pub const SERVER_COMMANDS: &[&str] =
+    &[COLLECT_GARBAGE_COMMAND, RELOAD_WORKSPACE_COMMAND, SSR_COMMAND, APPLY_ASSIST_COMMAND];
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyAssistParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+    pub id: String,
+}
+
+/// `workspace/willRenameFiles`, from LSP 3.16, which `lsp-types` doesn't
+/// support yet.
+pub enum WillRenameFiles {}
+
+impl Request for WillRenameFiles {
+    type Params = RenameFilesParams;
+    type Result = Option<WorkspaceEdit>;
+    const METHOD: &'static str = "workspace/willRenameFiles";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameFilesParams {
+    pub files: Vec<FileRename>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRename {
+    pub old_uri: Url,
+    pub new_uri: Url,
+}
+
+/// `textDocument/prepareTypeHierarchy`, from LSP 3.17, which `lsp-types`
+/// doesn't support yet.
+pub enum PrepareTypeHierarchy {}
+
+impl Request for PrepareTypeHierarchy {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "textDocument/prepareTypeHierarchy";
+}
+
+/// For a trait, its supertraits. For a type, the traits it implements.
+pub enum TypeHierarchySupertypes {}
+
+impl Request for TypeHierarchySupertypes {
+    type Params = TypeHierarchyParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "typeHierarchy/supertypes";
+}
+
+/// For a trait, its subtraits and the types implementing it.
+pub enum TypeHierarchySubtypes {}
+
+impl Request for TypeHierarchySubtypes {
+    type Params = TypeHierarchyParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "typeHierarchy/subtypes";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyParams {
+    pub item: TypeHierarchyItem,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub uri: Url,
+    pub range: Range,
+    pub selection_range: Range,
+}
+
+/// `textDocument/hover`, with links to commands below the hover.
+pub enum HoverRequest {}
+
+impl Request for HoverRequest {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<Hover>;
+    const METHOD: &'static str = "textDocument/hover";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hover {
+    #[serde(flatten)]
+    pub hover: lsp_types::Hover,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub actions: Vec<CommandLinkGroup>,
+}
+
+/// Links shown together, like the types in a signature.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandLinkGroup {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub commands: Vec<CommandLink>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandLink {
+    #[serde(flatten)]
+    pub command: Command,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tooltip: Option<String>,
+}
@@ -13,11 +13,17 @@ pub(crate) const UNION: SemanticTokenType = SemanticTokenType::new("union");
 pub(crate) const UNRESOLVED_REFERENCE: SemanticTokenType =
     SemanticTokenType::new("unresolvedReference");
 pub(crate) const FORMAT_SPECIFIER: SemanticTokenType = SemanticTokenType::new("formatSpecifier");
+pub(crate) const INACTIVE_CODE: SemanticTokenType = SemanticTokenType::new("inactiveCode");
 
 pub(crate) const CONSTANT: SemanticTokenModifier = SemanticTokenModifier::new("constant");
 pub(crate) const CONTROL_FLOW: SemanticTokenModifier = SemanticTokenModifier::new("controlFlow");
 pub(crate) const MUTABLE: SemanticTokenModifier = SemanticTokenModifier::new("mutable");
 pub(crate) const UNSAFE: SemanticTokenModifier = SemanticTokenModifier::new("unsafe");
+pub(crate) const CONSUMING: SemanticTokenModifier = SemanticTokenModifier::new("consuming");
+pub(crate) const CALLABLE: SemanticTokenModifier = SemanticTokenModifier::new("callable");
+pub(crate) const LIBRARY: SemanticTokenModifier = SemanticTokenModifier::new("library");
+pub(crate) const ATTRIBUTE_MODIFIER: SemanticTokenModifier =
+    SemanticTokenModifier::new("attribute");
 
 pub(crate) const SUPPORTED_TYPES: &[SemanticTokenType] = &[
     SemanticTokenType::COMMENT,
@@ -48,6 +54,7 @@ pub(crate) const SUPPORTED_TYPES: &[SemanticTokenType] = &[
     UNION,
     UNRESOLVED_REFERENCE,
     FORMAT_SPECIFIER,
+    INACTIVE_CODE,
 ];
 
 pub(crate) const SUPPORTED_MODIFIERS: &[SemanticTokenModifier] = &[
@@ -62,6 +69,10 @@ pub(crate) const SUPPORTED_MODIFIERS: &[SemanticTokenModifier] = &[
     MUTABLE,
     UNSAFE,
     CONTROL_FLOW,
+    CONSUMING,
+    CALLABLE,
+    LIBRARY,
+    ATTRIBUTE_MODIFIER,
 ];
 
 #[derive(Default)]
@@ -3,7 +3,8 @@
 //! By default, we include only `.rs` files, and skip some know offenders like
 //! `/target` or `/node_modules` altogether.
 //!
-//! It's also possible to add custom exclusion globs.
+//! It's also possible to add custom exclusion globs, and to restrict a root to
+//! the files matching a set of inclusion globs.
 
 use globset::{GlobSet, GlobSetBuilder};
 use ra_vfs::{Filter, RelativePath};
@@ -15,12 +16,13 @@ const IGNORED_FOR_NON_MEMBERS: &[&str] = &["examples/**", "tests/**", "benches/*
 
 pub struct RustPackageFilterBuilder {
     is_member: bool,
+    include: Option<GlobSetBuilder>,
     exclude: GlobSetBuilder,
 }
 
 impl Default for RustPackageFilterBuilder {
     fn default() -> RustPackageFilterBuilder {
-        RustPackageFilterBuilder { is_member: false, exclude: GlobSetBuilder::new() }
+        RustPackageFilterBuilder { is_member: false, include: None, exclude: GlobSetBuilder::new() }
     }
 }
 
@@ -30,6 +32,16 @@ impl RustPackageFilterBuilder {
         self
     }
 
+    /// Only files matching one of the `globs` are included. Directories are
+    /// still traversed, as files deeper down might match.
+    pub fn include(mut self, globs: impl IntoIterator<Item = Glob>) -> RustPackageFilterBuilder {
+        let include = self.include.get_or_insert_with(GlobSetBuilder::new);
+        for glob in globs.into_iter() {
+            include.add(glob);
+        }
+        self
+    }
+
     pub fn exclude(mut self, globs: impl IntoIterator<Item = Glob>) -> RustPackageFilterBuilder {
         for glob in globs.into_iter() {
             self.exclude.add(glob);
@@ -38,7 +50,7 @@ impl RustPackageFilterBuilder {
     }
 
     pub fn into_vfs_filter(self) -> Box<dyn Filter> {
-        let RustPackageFilterBuilder { is_member, mut exclude } = self;
+        let RustPackageFilterBuilder { is_member, include, mut exclude } = self;
         for &glob in ALWAYS_IGNORED {
             exclude.add(Glob::new(glob).unwrap());
         }
@@ -47,11 +59,15 @@ impl RustPackageFilterBuilder {
                 exclude.add(Glob::new(glob).unwrap());
             }
         }
-        Box::new(RustPackageFilter { exclude: exclude.build().unwrap() })
+        Box::new(RustPackageFilter {
+            include: include.map(|it| it.build().unwrap()),
+            exclude: exclude.build().unwrap(),
+        })
     }
 }
 
 struct RustPackageFilter {
+    include: Option<GlobSet>,
     exclude: GlobSet,
 }
 
@@ -62,6 +78,8 @@ impl Filter for RustPackageFilter {
 
     fn include_file(&self, file_path: &RelativePath) -> bool {
         file_path.extension() == Some("rs")
+            && !self.exclude.is_match(file_path.as_str())
+            && self.include.as_ref().map_or(true, |it| it.is_match(file_path.as_str()))
     }
 }
 
@@ -96,3 +114,21 @@ fn test_globs() {
 
     assert!(!filter.include_dir(RelativePath::new("src/llvm-project/clang")));
 }
+
+#[test]
+fn test_include_globs() {
+    let filter = RustPackageFilterBuilder::default()
+        .set_member(true)
+        .include(std::iter::once(Glob::new("**/*_gen.rs").unwrap()))
+        .exclude(std::iter::once(Glob::new("old/**").unwrap()))
+        .into_vfs_filter();
+
+    assert!(filter.include_dir(RelativePath::new("src/foo")));
+    assert!(filter.include_file(RelativePath::new("src/foo/bar_gen.rs")));
+    assert!(!filter.include_file(RelativePath::new("src/foo/bar.rs")));
+    assert!(!filter.include_file(RelativePath::new("old/bar_gen.rs")));
+
+    let filter = RustPackageFilterBuilder::default().set_member(true).into_vfs_filter();
+    assert!(filter.include_file(RelativePath::new("src/foo/bar.rs")));
+    assert!(!filter.include_file(RelativePath::new("src/foo/bar.txt")));
+}
@@ -16,14 +16,18 @@ use ra_ide::{
     Analysis, AnalysisChange, AnalysisHost, CrateGraph, FileId, LibraryData, SourceRootId,
 };
 use ra_project_model::{get_rustc_cfg_options, ProcMacroClient, ProjectWorkspace};
+use ra_text_edit::AtomTextEdit;
 use ra_vfs::{LineEndings, RootEntry, Vfs, VfsChange, VfsFile, VfsRoot, VfsTask, Watch};
 use relative_path::RelativePathBuf;
 use stdx::format_to;
 
 use crate::{
-    config::Config,
+    config::{Config, RootConfig, RootKind},
     diagnostics::{CheckFixes, DiagnosticCollection},
-    main_loop::pending_requests::{CompletedRequest, LatestRequests},
+    main_loop::{
+        completion_resolve::CompletionResolveCache,
+        pending_requests::{CompletedRequest, LatestRequests},
+    },
     vfs_glob::{Glob, RustPackageFilterBuilder},
     LspError, Result,
 };
@@ -48,6 +52,158 @@ fn create_flycheck(workspaces: &[ProjectWorkspace], config: &FlycheckConfig) ->
         })
 }
 
+/// Roots configured by the user, with relative paths resolved against each
+/// workspace folder.
+pub(crate) fn custom_roots<'a>(
+    folder_roots: &[PathBuf],
+    config: &'a Config,
+) -> Vec<(PathBuf, &'a RootConfig)> {
+    config
+        .files
+        .roots
+        .iter()
+        .flat_map(|root| {
+            let paths = if root.path.is_absolute() {
+                vec![root.path.clone()]
+            } else {
+                folder_roots.iter().map(|it| it.join(&root.path)).collect()
+            };
+            paths.into_iter().map(move |path| (path, root))
+        })
+        .collect()
+}
+
+/// The roots of the `Vfs` for the workspace folders and their projects.
+fn root_entries(
+    folder_roots: &[PathBuf],
+    workspaces: &[ProjectWorkspace],
+    exclude_globs: &[Glob],
+    config: &Config,
+) -> Vec<(PathBuf, RootEntry)> {
+    let extern_dirs: FxHashSet<_> =
+        workspaces.iter().flat_map(ProjectWorkspace::out_dirs).collect();
+    let create_filter = |is_member| {
+        RustPackageFilterBuilder::default()
+            .set_member(is_member)
+            .exclude(exclude_globs.iter().cloned())
+    };
+    let custom_filter = |root: &RootConfig| {
+        let globs = |globs: &[String]| -> Vec<Glob> {
+            globs.iter().filter_map(|it| Glob::new(it).ok()).collect()
+        };
+        let mut filter = create_filter(root.kind == RootKind::Local).exclude(globs(&root.exclude));
+        if !root.include.is_empty() {
+            filter = filter.include(globs(&root.include));
+        }
+        filter.into_vfs_filter()
+    };
+    folder_roots
+        .iter()
+        .map(|path| (path.clone(), create_filter(true).into_vfs_filter()))
+        .chain(workspaces.iter().flat_map(ProjectWorkspace::to_roots).map(|pkg_root| {
+            (pkg_root.path().to_owned(), create_filter(pkg_root.is_member()).into_vfs_filter())
+        }))
+        .chain(extern_dirs.into_iter().map(|path| (path, create_filter(false).into_vfs_filter())))
+        .chain(
+            custom_roots(folder_roots, config)
+                .into_iter()
+                .map(|(path, root)| (path, custom_filter(root))),
+        )
+        .map(|(path, filter)| (path.clone(), RootEntry::new(path, filter)))
+        .collect()
+}
+
+/// Creates a `Vfs` with the given roots, and adds them to `change`. Returns
+/// the `Vfs`, its task receiver, and the roots which are local.
+fn load_vfs(
+    roots: Vec<(PathBuf, RootEntry)>,
+    folder_roots: &[PathBuf],
+    watch: Watch,
+    config: &Config,
+    change: &mut AnalysisChange,
+) -> (Vfs, Receiver<VfsTask>, FxHashSet<SourceRootId>) {
+    let custom_roots = custom_roots(folder_roots, config);
+    let (task_sender, task_receiver) = unbounded();
+    let task_sender = Box::new(move |t| task_sender.send(t).unwrap());
+    let roots = roots.into_iter().map(|(_, entry)| entry).collect();
+    let (vfs, vfs_roots) = Vfs::new(roots, task_sender, watch);
+
+    let mut local_roots = FxHashSet::default();
+    for r in vfs_roots {
+        let vfs_root_path = vfs.root2path(r);
+        // An explicitly configured root knows its kind, the rest are local if
+        // they are inside one of the workspace folders.
+        let is_local = match custom_roots.iter().find(|(path, _)| *path == vfs_root_path) {
+            Some((_, root)) => root.kind == RootKind::Local,
+            None => folder_roots.iter().any(|it| vfs_root_path.starts_with(it)),
+        };
+        if is_local {
+            local_roots.insert(SourceRootId(r.0));
+        }
+        change.add_root(SourceRootId(r.0), is_local);
+        change.set_debug_root_path(SourceRootId(r.0), vfs_root_path.display().to_string());
+    }
+    (vfs, task_receiver, local_roots)
+}
+
+/// Creates the crate graph from all the workspaces, loading the crate roots
+/// into `vfs`.
+fn crate_graph(
+    workspaces: &[ProjectWorkspace],
+    vfs: &mut Vfs,
+    proc_macro_client: &ProcMacroClient,
+) -> CrateGraph {
+    let extern_dirs: FxHashSet<_> =
+        workspaces.iter().flat_map(ProjectWorkspace::out_dirs).collect();
+    // FIXME: add path2root in vfs to simpily this logic
+    let extern_source_roots: FxHashMap<PathBuf, ExternSourceId> = (0..vfs.n_roots())
+        .map(|it| VfsRoot(it as u32))
+        .map(|root| (vfs.root2path(root), ExternSourceId(root.0)))
+        .filter(|(path, _)| extern_dirs.contains(path))
+        .collect();
+
+    // FIXME: Read default cfgs from config
+    let default_cfg_options = {
+        let mut opts = get_rustc_cfg_options();
+        opts.insert_atom("test".into());
+        opts.insert_atom("debug_assertion".into());
+        opts
+    };
+
+    let mut crate_graph = CrateGraph::default();
+    let mut load = |path: &Path| {
+        // Some path from metadata will be non canonicalized, e.g. /foo/../bar/lib.rs
+        let path = path.canonicalize().ok()?;
+        let vfs_file = vfs.load(&path);
+        vfs_file.map(|f| FileId(f.0))
+    };
+    workspaces
+        .iter()
+        .map(|ws| {
+            ws.to_crate_graph(
+                &default_cfg_options,
+                &extern_source_roots,
+                proc_macro_client,
+                &mut load,
+            )
+        })
+        .for_each(|graph| {
+            crate_graph.extend(graph);
+        });
+    crate_graph
+}
+
+/// The root files of the sysroot crates of all the workspaces, looked up in
+/// `vfs` the same way `crate_graph` loads them.
+pub(crate) fn sysroot_crate_roots(workspaces: &[ProjectWorkspace], vfs: &Vfs) -> FxHashSet<FileId> {
+    workspaces
+        .iter()
+        .flat_map(ProjectWorkspace::sysroot_crate_roots)
+        .filter_map(|path| vfs.path2file(&path.canonicalize().ok()?))
+        .map(|it| FileId(it.0))
+        .collect()
+}
+
 /// `WorldState` is the primary mutable state of the language server
 ///
 /// The most interesting components are `vfs`, which stores a consistent
@@ -57,14 +213,19 @@ fn create_flycheck(workspaces: &[ProjectWorkspace], config: &FlycheckConfig) ->
 pub struct WorldState {
     pub config: Config,
     pub roots: Vec<PathBuf>,
+    local_roots: FxHashSet<SourceRootId>,
     pub workspaces: Arc<Vec<ProjectWorkspace>>,
     pub analysis_host: AnalysisHost,
     pub vfs: Arc<RwLock<Vfs>>,
     pub task_receiver: Receiver<VfsTask>,
     pub latest_requests: Arc<RwLock<LatestRequests>>,
+    pub completion_resolve: Arc<RwLock<CompletionResolveCache>>,
     pub flycheck: Option<Flycheck>,
     pub diagnostics: DiagnosticCollection,
     pub proc_macro_client: ProcMacroClient,
+    /// Edits of open files since the last `process_changes`, for incremental
+    /// reparsing. `None` if the whole text of the file was replaced.
+    pending_edits: FxHashMap<FileId, Option<Vec<AtomTextEdit>>>,
 }
 
 /// An immutable snapshot of the world's state at a point in time.
@@ -73,6 +234,7 @@ pub struct WorldSnapshot {
     pub workspaces: Arc<Vec<ProjectWorkspace>>,
     pub analysis: Analysis,
     pub latest_requests: Arc<RwLock<LatestRequests>>,
+    pub completion_resolve: Arc<RwLock<CompletionResolveCache>>,
     pub check_fixes: CheckFixes,
     vfs: Arc<RwLock<Vfs>>,
 }
@@ -88,63 +250,9 @@ impl WorldState {
     ) -> WorldState {
         let mut change = AnalysisChange::new();
 
-        let extern_dirs: FxHashSet<_> =
-            workspaces.iter().flat_map(ProjectWorkspace::out_dirs).collect();
-
-        let roots: Vec<_> = {
-            let create_filter = |is_member| {
-                RustPackageFilterBuilder::default()
-                    .set_member(is_member)
-                    .exclude(exclude_globs.iter().cloned())
-                    .into_vfs_filter()
-            };
-            folder_roots
-                .iter()
-                .map(|path| RootEntry::new(path.clone(), create_filter(true)))
-                .chain(workspaces.iter().flat_map(ProjectWorkspace::to_roots).map(|pkg_root| {
-                    RootEntry::new(pkg_root.path().to_owned(), create_filter(pkg_root.is_member()))
-                }))
-                .chain(
-                    extern_dirs
-                        .iter()
-                        .map(|path| RootEntry::new(path.to_owned(), create_filter(false))),
-                )
-                .collect()
-        };
-
-        let (task_sender, task_receiver) = unbounded();
-        let task_sender = Box::new(move |t| task_sender.send(t).unwrap());
-        let (mut vfs, vfs_roots) = Vfs::new(roots, task_sender, watch);
-
-        let mut extern_source_roots = FxHashMap::default();
-        for r in vfs_roots {
-            let vfs_root_path = vfs.root2path(r);
-            let is_local = folder_roots.iter().any(|it| vfs_root_path.starts_with(it));
-            change.add_root(SourceRootId(r.0), is_local);
-            change.set_debug_root_path(SourceRootId(r.0), vfs_root_path.display().to_string());
-
-            // FIXME: add path2root in vfs to simpily this logic
-            if extern_dirs.contains(&vfs_root_path) {
-                extern_source_roots.insert(vfs_root_path, ExternSourceId(r.0));
-            }
-        }
-
-        // FIXME: Read default cfgs from config
-        let default_cfg_options = {
-            let mut opts = get_rustc_cfg_options();
-            opts.insert_atom("test".into());
-            opts.insert_atom("debug_assertion".into());
-            opts
-        };
-
-        // Create crate graph from all the workspaces
-        let mut crate_graph = CrateGraph::default();
-        let mut load = |path: &std::path::Path| {
-            // Some path from metadata will be non canonicalized, e.g. /foo/../bar/lib.rs
-            let path = path.canonicalize().ok()?;
-            let vfs_file = vfs.load(&path);
-            vfs_file.map(|f| FileId(f.0))
-        };
+        let roots = root_entries(&folder_roots, &workspaces, exclude_globs, &config);
+        let (mut vfs, task_receiver, local_roots) =
+            load_vfs(roots, &folder_roots, watch, &config, &mut change);
 
         let proc_macro_client = match &config.proc_macro_srv {
             None => ProcMacroClient::dummy(),
@@ -161,20 +269,7 @@ impl WorldState {
             },
         };
 
-        workspaces
-            .iter()
-            .map(|ws| {
-                ws.to_crate_graph(
-                    &default_cfg_options,
-                    &extern_source_roots,
-                    &proc_macro_client,
-                    &mut load,
-                )
-            })
-            .for_each(|graph| {
-                crate_graph.extend(graph);
-            });
-        change.set_crate_graph(crate_graph);
+        change.set_crate_graph(crate_graph(&workspaces, &mut vfs, &proc_macro_client));
 
         let flycheck = config.check.as_ref().and_then(|c| create_flycheck(&workspaces, c));
 
@@ -183,15 +278,68 @@ impl WorldState {
         WorldState {
             config,
             roots: folder_roots,
+            local_roots,
             workspaces: Arc::new(workspaces),
             analysis_host,
             vfs: Arc::new(RwLock::new(vfs)),
             task_receiver,
             latest_requests: Default::default(),
+            completion_resolve: Default::default(),
             flycheck,
             diagnostics: Default::default(),
             proc_macro_client,
+            pending_edits: FxHashMap::default(),
+        }
+    }
+
+    /// Switches to other workspace folders and projects. The analysis keeps
+    /// its caches, and only learns about the new crate graph and roots.
+    ///
+    /// The `Vfs` can't add or remove roots, so when the projects need a root
+    /// which it doesn't have, or don't need one which it has, it is replaced
+    /// and all files are scanned anew. This unloads the files of removed
+    /// folders. Returns whether that happened, as the file ids are then
+    /// different.
+    pub fn switch_workspaces(
+        &mut self,
+        folder_roots: Vec<PathBuf>,
+        workspaces: Vec<ProjectWorkspace>,
+        exclude_globs: &[Glob],
+        watch: Watch,
+    ) -> bool {
+        let mut change = AnalysisChange::new();
+
+        let old_roots: FxHashSet<PathBuf> = {
+            let vfs = self.vfs.read();
+            (0..vfs.n_roots()).map(|it| vfs.root2path(VfsRoot(it as u32))).collect()
+        };
+        let roots = root_entries(&folder_roots, &workspaces, exclude_globs, &self.config);
+        let new_roots: FxHashSet<&PathBuf> = roots.iter().map(|(path, _)| path).collect();
+        let rescan = new_roots.len() != old_roots.len()
+            || new_roots.iter().any(|path| !old_roots.contains(*path));
+        if rescan {
+            let (vfs, task_receiver, local_roots) =
+                load_vfs(roots, &folder_roots, watch, &self.config, &mut change);
+            // The roots which the new `Vfs` doesn't have become empty.
+            for root in vfs.n_roots()..old_roots.len() {
+                change.add_root(SourceRootId(root as u32), true);
+            }
+            self.vfs = Arc::new(RwLock::new(vfs));
+            self.task_receiver = task_receiver;
+            self.local_roots = local_roots;
+            self.diagnostics = Default::default();
+            self.pending_edits.clear();
         }
+
+        let crate_graph = crate_graph(&workspaces, &mut self.vfs.write(), &self.proc_macro_client);
+        change.set_crate_graph(crate_graph);
+        self.analysis_host.apply_change(change);
+        self.completion_resolve.write().clear();
+
+        self.flycheck = self.config.check.as_ref().and_then(|it| create_flycheck(&workspaces, it));
+        self.roots = folder_roots;
+        self.workspaces = Arc::new(workspaces);
+        rescan
     }
 
     pub fn update_configuration(&mut self, config: Config) {
@@ -216,12 +364,13 @@ impl WorldState {
         }
         let mut libs = Vec::new();
         let mut change = AnalysisChange::new();
+        let mut changed_files = FxHashSet::default();
+        let mut files_moved = false;
         for c in changes {
             match c {
                 VfsChange::AddRoot { root, files } => {
-                    let root_path = self.vfs.read().root2path(root);
-                    let is_local = self.roots.iter().any(|r| root_path.starts_with(r));
-                    if is_local {
+                    files_moved = true;
+                    if self.local_roots.contains(&SourceRootId(root.0)) {
                         *roots_scanned += 1;
                         for (file, path, text) in files {
                             change.add_file(SourceRootId(root.0), FileId(file.0), path, text);
@@ -235,20 +384,42 @@ impl WorldState {
                     }
                 }
                 VfsChange::AddFile { root, file, path, text } => {
+                    files_moved = true;
                     change.add_file(SourceRootId(root.0), FileId(file.0), path, text);
                 }
                 VfsChange::RemoveFile { root, file, path } => {
+                    files_moved = true;
                     change.remove_file(SourceRootId(root.0), FileId(file.0), path)
                 }
                 VfsChange::ChangeFile { file, text } => {
-                    change.change_file(FileId(file.0), text);
+                    let file_id = FileId(file.0);
+                    changed_files.insert(file_id);
+                    match self.pending_edits.remove(&file_id) {
+                        Some(Some(edits)) => change.change_file_incrementally(file_id, text, edits),
+                        _ => change.change_file(file_id, text),
+                    }
                 }
             }
         }
+        self.pending_edits.clear();
         self.analysis_host.apply_change(change);
+        if files_moved {
+            self.completion_resolve.write().clear();
+        } else {
+            self.completion_resolve.write().invalidate_files(&changed_files);
+        }
         Some(libs)
     }
 
+    /// Records edits made to an open file, see `apply_document_changes`.
+    pub fn record_edits(&mut self, file_id: FileId, edits: Option<Vec<AtomTextEdit>>) {
+        let pending = self.pending_edits.entry(file_id).or_insert_with(|| Some(Vec::new()));
+        match (pending, edits) {
+            (Some(pending), Some(edits)) => pending.extend(edits),
+            (pending, _) => *pending = None,
+        }
+    }
+
     pub fn add_lib(&mut self, data: LibraryData) {
         let mut change = AnalysisChange::new();
         change.add_library(data);
@@ -262,6 +433,7 @@ impl WorldState {
             analysis: self.analysis_host.analysis(),
             vfs: Arc::clone(&self.vfs),
             latest_requests: Arc::clone(&self.latest_requests),
+            completion_resolve: Arc::clone(&self.completion_resolve),
             check_fixes: Arc::clone(&self.diagnostics.check_fixes),
         }
     }
@@ -308,6 +480,11 @@ impl WorldSnapshot {
         self.vfs.read().file2path(VfsFile(id.0))
     }
 
+    /// The root files of the sysroot crates of all workspaces.
+    pub fn sysroot_crate_roots(&self) -> FxHashSet<FileId> {
+        sysroot_crate_roots(&self.workspaces, &self.vfs.read())
+    }
+
     pub fn file_line_endings(&self, id: FileId) -> LineEndings {
         self.vfs.read().file_line_endings(VfsFile(id.0))
     }
@@ -3,13 +3,15 @@ mod support;
 use std::{collections::HashMap, path::PathBuf, time::Instant};
 
 use lsp_types::{
-    CodeActionContext, DidOpenTextDocumentParams, DocumentFormattingParams, FormattingOptions,
-    PartialResultParams, Position, Range, TextDocumentItem, TextDocumentPositionParams,
-    WorkDoneProgressParams,
+    CodeActionContext, DidChangeWorkspaceFoldersParams, DidOpenTextDocumentParams,
+    DocumentFormattingParams, FormattingOptions, PartialResultParams, Position, Range,
+    SemanticTokensRangeParams, TextDocumentItem, TextDocumentPositionParams, Url,
+    WorkDoneProgressParams, WorkspaceFolder, WorkspaceFoldersChangeEvent,
 };
 use rust_analyzer::req::{
-    CodeActionParams, CodeActionRequest, Completion, CompletionParams, DidOpenTextDocument,
-    Formatting, GotoDefinition, HoverRequest, OnEnter, Runnables, RunnablesParams,
+    CodeActionParams, CodeActionRequest, Completion, CompletionParams, DidChangeWorkspaceFolders,
+    DidOpenTextDocument, Formatting, GotoDefinition, HoverRequest, OnEnter, Runnables,
+    RunnablesParams, SemanticTokensRangeRequest, WorkspaceSymbol, WorkspaceSymbolParams,
 };
 use serde_json::json;
 use tempfile::TempDir;
@@ -700,3 +702,206 @@ pub fn foo(_input: TokenStream) -> TokenStream {
     let value = res.get("contents").unwrap().get("value").unwrap().to_string();
     assert_eq!(value, r#""```rust\nfoo::Bar\nfn bar()\n```""#)
 }
+
+#[test]
+fn semantic_tokens_range_splits_inactive_code_into_lines() {
+    if skip_slow_tests() {
+        return;
+    }
+    let server = project(
+        r#"
+//- Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- src/lib.rs
+#[cfg(never)]
+fn foo() {
+    let x = 92;
+}
+fn bar() {}
+"#,
+    );
+    server.wait_until_workspace_is_loaded();
+
+    let res = server.send_request::<SemanticTokensRangeRequest>(SemanticTokensRangeParams {
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+        text_document: server.doc_id("src/lib.rs"),
+        range: Range::new(Position::new(0, 0), Position::new(5, 0)),
+    });
+    let data: Vec<u64> =
+        res["data"].as_array().unwrap().iter().map(|it| it.as_u64().unwrap()).collect();
+    let mut line = 0;
+    let mut lines_with_tokens = Vec::new();
+    for token in data.chunks(5) {
+        let (delta_line, length) = (token[0], token[2]);
+        line += delta_line;
+        assert!(length <= "    let x = 92;".len() as u64, "token too long: {:?}", token);
+        lines_with_tokens.push(line);
+    }
+    lines_with_tokens.dedup();
+    assert_eq!(lines_with_tokens, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn adding_workspace_folder_loads_its_project() {
+    if skip_slow_tests() {
+        return;
+    }
+    let server = Project::with_fixture(
+        r#"
+//- foo/Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- foo/src/lib.rs
+pub fn foo() {}
+
+//- bar/Cargo.toml
+[package]
+name = "bar"
+version = "0.0.0"
+
+//- bar/src/lib.rs
+pub struct Bar;
+pub fn bar() -> Bar { Bar }
+"#,
+    )
+    .root("foo")
+    .server();
+    server.wait_until_workspace_is_loaded();
+
+    server.notification::<DidChangeWorkspaceFolders>(DidChangeWorkspaceFoldersParams {
+        event: WorkspaceFoldersChangeEvent {
+            added: vec![WorkspaceFolder {
+                uri: Url::from_file_path(server.path().join("bar")).unwrap(),
+                name: "bar".to_string(),
+            }],
+            removed: vec![],
+        },
+    });
+    server.wait_until_workspace_is_loaded_n_times(2);
+
+    let res = server.send_request::<GotoDefinition>(TextDocumentPositionParams::new(
+        server.doc_id("bar/src/lib.rs"),
+        Position::new(1, 17),
+    ));
+    assert!(format!("{}", res).contains("bar/src/lib.rs"));
+}
+
+#[test]
+fn removing_workspace_folder_unloads_its_project() {
+    if skip_slow_tests() {
+        return;
+    }
+    let server = Project::with_fixture(
+        r#"
+//- foo/Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- foo/src/lib.rs
+pub fn foo() {}
+
+//- bar/Cargo.toml
+[package]
+name = "bar"
+version = "0.0.0"
+
+//- bar/src/lib.rs
+pub struct Bar;
+"#,
+    )
+    .root("foo")
+    .root("bar")
+    .server();
+    server.wait_until_workspace_is_loaded();
+
+    let find_bar = || {
+        server.send_request::<WorkspaceSymbol>(WorkspaceSymbolParams {
+            query: "Bar".to_string(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        })
+    };
+    assert!(format!("{}", find_bar()).contains("bar/src/lib.rs"));
+
+    server.notification::<DidChangeWorkspaceFolders>(DidChangeWorkspaceFoldersParams {
+        event: WorkspaceFoldersChangeEvent {
+            added: vec![],
+            removed: vec![WorkspaceFolder {
+                uri: Url::from_file_path(server.path().join("bar")).unwrap(),
+                name: "bar".to_string(),
+            }],
+        },
+    });
+    server.wait_until_workspace_is_loaded_n_times(2);
+
+    assert!(!format!("{}", find_bar()).contains("bar/src/lib.rs"));
+}
+
+#[test]
+fn execute_command_reloads_workspace() {
+    if skip_slow_tests() {
+        return;
+    }
+    let server = project(
+        r#"
+//- Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- src/lib.rs
+pub fn foo() {}
+fn bar() { foo() }
+"#,
+    );
+    server.wait_until_workspace_is_loaded();
+
+    let (res, edits) = server.execute_command("rust-analyzer.server.reloadWorkspace", vec![]);
+    assert_eq!(res, json!(null));
+    assert!(edits.is_empty());
+    server.wait_until_workspace_is_loaded_n_times(2);
+
+    let res = server.send_request::<GotoDefinition>(TextDocumentPositionParams::new(
+        server.doc_id("src/lib.rs"),
+        Position::new(1, 12),
+    ));
+    assert!(format!("{}", res).contains("src/lib.rs"));
+}
+
+#[test]
+fn execute_command_applies_ssr() {
+    if skip_slow_tests() {
+        return;
+    }
+    let server = project(
+        r#"
+//- Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- src/lib.rs
+fn foo(x: u32) {}
+fn bar(x: u32) {}
+fn main() { foo(92); }
+"#,
+    );
+    server.wait_until_workspace_is_loaded();
+
+    let (res, edits) = server
+        .execute_command("rust-analyzer.server.ssr", vec![json!("foo($a:expr) ==>> bar($a)")]);
+    assert_eq!(res, json!(null));
+    assert_eq!(edits.len(), 1);
+    assert!(format!("{}", edits[0]).contains("bar(92)"));
+
+    let (res, edits) = server.execute_command("rust-analyzer.server.collectGarbage", vec![]);
+    assert_eq!(res, json!(null));
+    assert!(edits.is_empty());
+}
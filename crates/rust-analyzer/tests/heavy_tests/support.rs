@@ -10,8 +10,9 @@ use crossbeam_channel::{after, select, Receiver};
 use lsp_server::{Connection, Message, Notification, Request};
 use lsp_types::{
     notification::{DidOpenTextDocument, Exit},
-    request::Shutdown,
-    DidOpenTextDocumentParams, TextDocumentIdentifier, TextDocumentItem, Url, WorkDoneProgress,
+    request::{ApplyWorkspaceEdit, ExecuteCommand, Request as _, Shutdown},
+    DidOpenTextDocumentParams, ExecuteCommandParams, TextDocumentIdentifier, TextDocumentItem, Url,
+    WorkDoneProgress,
 };
 use serde::Serialize;
 use serde_json::{to_string_pretty, Value};
@@ -179,11 +180,34 @@ impl Server {
         self.send_request_(r)
     }
     fn send_request_(&self, r: Request) -> Value {
+        let (res, edits) = self.send_request_with_edits(r);
+        assert!(edits.is_empty(), "unexpected edits: {:?}", edits);
+        res
+    }
+    /// Sends `workspace/executeCommand`. Returns the result, and the edits
+    /// the server asked the client to apply while executing the command.
+    pub fn execute_command(&self, command: &str, arguments: Vec<Value>) -> (Value, Vec<Value>) {
+        let id = self.req_id.get();
+        self.req_id.set(id + 1);
+
+        let params = ExecuteCommandParams {
+            command: command.to_string(),
+            arguments,
+            work_done_progress_params: Default::default(),
+        };
+        let r = Request::new(id.into(), ExecuteCommand::METHOD.to_string(), params);
+        self.send_request_with_edits(r)
+    }
+    fn send_request_with_edits(&self, r: Request) -> (Value, Vec<Value>) {
         let id = r.id.clone();
         self.client.sender.send(r.into()).unwrap();
+        let mut edits = Vec::new();
         while let Some(msg) = self.recv() {
             match msg {
                 Message::Request(req) if req.method == "window/workDoneProgress/create" => (),
+                Message::Request(req) if req.method == ApplyWorkspaceEdit::METHOD => {
+                    edits.push(req.params)
+                }
                 Message::Request(req) => panic!("unexpected request: {:?}", req),
                 Message::Notification(_) => (),
                 Message::Response(res) => {
@@ -191,14 +215,19 @@ impl Server {
                     if let Some(err) = res.error {
                         panic!("error response: {:#?}", err);
                     }
-                    return res.result.unwrap();
+                    return (res.result.unwrap(), edits);
                 }
             }
         }
         panic!("no response");
     }
     pub fn wait_until_workspace_is_loaded(&self) {
-        self.wait_for_message_cond(1, &|msg: &Message| match msg {
+        self.wait_until_workspace_is_loaded_n_times(1)
+    }
+    /// Like `wait_until_workspace_is_loaded`, for a workspace which is loaded
+    /// again, for example after the workspace folders changed.
+    pub fn wait_until_workspace_is_loaded_n_times(&self, n: usize) {
+        self.wait_for_message_cond(n, &|msg: &Message| match msg {
             Message::Notification(n) if n.method == "$/progress" => {
                 match n.clone().extract::<ProgressParams>("$/progress").unwrap() {
                     ProgressParams {
@@ -26,6 +26,16 @@ export function createClient(serverPath: string, cwd: string): lc.LanguageClient
         initializationOptions: vscode.workspace.getConfiguration("rust-analyzer"),
         traceOutputChannel,
         middleware: {
+            async provideHover(document: vscode.TextDocument, position: vscode.Position, token: vscode.CancellationToken, _next: lc.ProvideHoverSignature) {
+                const params = res.code2ProtocolConverter.asTextDocumentPositionParams(document, position);
+                const result = await res.sendRequest(lc.HoverRequest.type, params, token);
+                const hover = res.protocol2CodeConverter.asHover(result);
+                const actions = (result as any)?.actions as CommandLinkGroup[] | undefined;
+                if (hover && actions && actions.length > 0) {
+                    hover.contents.push(renderHoverActions(actions));
+                }
+                return hover;
+            },
             // Workaround for https://github.com/microsoft/vscode-languageserver-node/issues/576
             async provideDocumentSemanticTokens(document: vscode.TextDocument, token: vscode.CancellationToken, next: DocumentSemanticsTokensSignature) {
                 const res = await next(document, token);
@@ -49,6 +59,43 @@ export function createClient(serverPath: string, cwd: string): lc.LanguageClient
     // implementations are still in the "proposed" category for 3.16.
     res.registerFeature(new CallHierarchyFeature(res));
     res.registerFeature(new SemanticTokensFeature(res));
+    res.registerFeature(new ExperimentalFeatures());
 
     return res;
 }
+
+// Tells the server which of its extensions to the protocol we support.
+class ExperimentalFeatures implements lc.StaticFeature {
+    fillClientCapabilities(capabilities: lc.ClientCapabilities): void {
+        const caps: any = capabilities.experimental ?? {};
+        caps.hoverActions = true;
+        capabilities.experimental = caps;
+    }
+    initialize(_capabilities: lc.ServerCapabilities, _documentSelector: lc.DocumentSelector | undefined): void {
+    }
+}
+
+interface CommandLink extends lc.Command {
+    tooltip?: string;
+}
+
+interface CommandLinkGroup {
+    title?: string;
+    commands: CommandLink[];
+}
+
+function renderCommand(cmd: CommandLink) {
+    const args = encodeURIComponent(JSON.stringify(cmd.arguments ?? []));
+    const tooltip = cmd.tooltip ? ` '${cmd.tooltip}'` : '';
+    return `[${cmd.title}](command:${cmd.command}?${args}${tooltip})`;
+}
+
+function renderHoverActions(actions: CommandLinkGroup[]): vscode.MarkdownString {
+    const text = actions
+        .map(group => (group.title ? `${group.title} ` : '') + group.commands.map(renderCommand).join(' | '))
+        .join('\n\n');
+    const result = new vscode.MarkdownString(text);
+    // Command links only work in trusted markdown.
+    result.isTrusted = true;
+    return result;
+}
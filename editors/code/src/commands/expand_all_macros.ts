@@ -0,0 +1,99 @@
+import * as vscode from 'vscode';
+import * as ra from '../rust-analyzer-api';
+
+import { Ctx, Cmd } from '../ctx';
+
+const EXPANSION_SCHEME = 'rust-analyzer-expansion';
+
+// Opens the virtual file that shows the current file (or the item under the
+// cursor, if the selection is not empty) with all macros expanded.
+//
+// Go to definition in the virtual file jumps to where the token came from:
+// the macro call or the macro definition.
+export function expandAllMacros(ctx: Ctx): Cmd {
+    const tdcp = new TextDocumentContentProvider(ctx);
+    ctx.pushCleanup(
+        vscode.workspace.registerTextDocumentContentProvider(
+            EXPANSION_SCHEME,
+            tdcp,
+        ),
+    );
+    ctx.pushCleanup(
+        vscode.languages.registerDefinitionProvider(
+            { scheme: EXPANSION_SCHEME },
+            new OriginProvider(ctx, tdcp),
+        ),
+    );
+
+    return async () => {
+        const editor = vscode.window.activeTextEditor;
+        const itemOnly = !!editor && !editor.selection.isEmpty;
+        const uri = itemOnly
+            ? vscode.Uri.parse(`${tdcp.uri.toString()}?item=true`)
+            : tdcp.uri;
+
+        const document = await vscode.workspace.openTextDocument(uri);
+        tdcp.eventEmitter.fire(uri);
+        return vscode.window.showTextDocument(
+            document,
+            vscode.ViewColumn.Two,
+            true,
+        );
+    };
+}
+
+class TextDocumentContentProvider
+    implements vscode.TextDocumentContentProvider {
+    uri = vscode.Uri.parse(`${EXPANSION_SCHEME}://expandAllMacros/[EXPANSION].rs`);
+    eventEmitter = new vscode.EventEmitter<vscode.Uri>();
+    // The last expansion shown for each virtual document.
+    expansions = new Map<string, ra.ExpandedFile>();
+
+    constructor(private readonly ctx: Ctx) {
+    }
+
+    async provideTextDocumentContent(uri: vscode.Uri): Promise<string> {
+        const editor = vscode.window.activeTextEditor;
+        const client = this.ctx.client;
+        if (!editor || !client) return '';
+
+        const itemOnly = uri.query === 'item=true';
+        const expanded = await client.sendRequest(ra.expandAllMacros, {
+            textDocument: { uri: editor.document.uri.toString() },
+            position: itemOnly ? editor.selection.active : null,
+        });
+
+        if (expanded == null) {
+            this.expansions.delete(uri.toString());
+            return 'Not available';
+        }
+
+        this.expansions.set(uri.toString(), expanded);
+        return expanded.text;
+    }
+
+    get onDidChange(): vscode.Event<vscode.Uri> {
+        return this.eventEmitter.event;
+    }
+}
+
+class OriginProvider implements vscode.DefinitionProvider {
+    constructor(
+        private readonly ctx: Ctx,
+        private readonly tdcp: TextDocumentContentProvider,
+    ) {
+    }
+
+    provideDefinition(doc: vscode.TextDocument, pos: vscode.Position): vscode.ProviderResult<vscode.Location> {
+        const expanded = this.tdcp.expansions.get(doc.uri.toString());
+        const client = this.ctx.client;
+        if (!expanded || !client) return;
+
+        const origin = expanded.origins.find(it =>
+            client.protocol2CodeConverter.asRange(it.range).contains(pos)
+        );
+        if (!origin) return;
+
+        return client.protocol2CodeConverter.asLocation(origin.location);
+    }
+}
@@ -11,7 +11,10 @@ export * from './join_lines';
 export * from './on_enter';
 export * from './parent_module';
 export * from './syntax_tree';
+export * from './view_hir';
+export * from './view_crate_graph';
 export * from './expand_macro';
+export * from './expand_all_macros';
 export * from './runnables';
 export * from './ssr';
 export * from './server_version';
@@ -34,6 +37,19 @@ export function showReferences(ctx: Ctx): Cmd {
     };
 }
 
+export function gotoLocation(ctx: Ctx): Cmd {
+    return async (location: lc.Location) => {
+        const client = ctx.client;
+        if (client) {
+            const uri = client.protocol2CodeConverter.asUri(location.uri);
+            let range = client.protocol2CodeConverter.asRange(location.range);
+            // Put the cursor at the start of the target rather than selecting all of it.
+            range = range.with({ end: range.start });
+            await vscode.window.showTextDocument(uri, { selection: range });
+        }
+    };
+}
+
 export function applySourceChange(ctx: Ctx): Cmd {
     return async (change: ra.SourceChange) => {
         await sourceChange.applySourceChange(ctx, change);
@@ -0,0 +1,57 @@
+import * as vscode from 'vscode';
+import * as ra from '../rust-analyzer-api';
+
+import { Ctx, Cmd } from '../ctx';
+
+// Opens the virtual file that shows the crate graph in the Graphviz DOT
+// format. It can be rendered with `dot -Tsvg` or any DOT viewer extension.
+export function viewCrateGraph(ctx: Ctx): Cmd {
+    const tdcp = new TextDocumentContentProvider(ctx);
+    ctx.pushCleanup(
+        vscode.workspace.registerTextDocumentContentProvider(
+            'rust-analyzer-crate-graph',
+            tdcp,
+        ),
+    );
+
+    return async () => {
+        const choice = await vscode.window.showQuickPick(
+            ['Hide sysroot crates', 'Show all crates'],
+            { placeHolder: 'Crates to include in the graph' },
+        );
+        if (!choice) return;
+
+        const uri = choice === 'Hide sysroot crates'
+            ? vscode.Uri.parse(`${tdcp.uri.toString()}?hideSysroot=true`)
+            : tdcp.uri;
+        const document = await vscode.workspace.openTextDocument(uri);
+        tdcp.eventEmitter.fire(uri);
+        return vscode.window.showTextDocument(
+            document,
+            vscode.ViewColumn.Two,
+            true,
+        );
+    };
+}
+
+class TextDocumentContentProvider
+    implements vscode.TextDocumentContentProvider {
+    uri = vscode.Uri.parse('rust-analyzer-crate-graph://viewCrateGraph/crates.dot');
+    eventEmitter = new vscode.EventEmitter<vscode.Uri>();
+
+    constructor(private readonly ctx: Ctx) {
+    }
+
+    async provideTextDocumentContent(uri: vscode.Uri): Promise<string> {
+        const client = this.ctx.client;
+        if (!client) return '';
+
+        return client.sendRequest(ra.viewCrateGraph, {
+            hideSysroot: uri.query === 'hideSysroot=true',
+        });
+    }
+
+    get onDidChange(): vscode.Event<vscode.Uri> {
+        return this.eventEmitter.event;
+    }
+}
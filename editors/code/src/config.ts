@@ -98,6 +98,10 @@ export class Config {
             typeHints: this.get<boolean>("inlayHints.typeHints"),
             parameterHints: this.get<boolean>("inlayHints.parameterHints"),
             chainingHints: this.get<boolean>("inlayHints.chainingHints"),
+            lifetimeElisionHints: this.get<boolean>("inlayHints.lifetimeElisionHints"),
+            closureReturnTypeHints: this.get<boolean>("inlayHints.closureReturnTypeHints"),
+            closureCaptureHints: this.get<boolean>("inlayHints.closureCaptureHints"),
+            adjustmentHints: this.get<boolean>("inlayHints.adjustmentHints"),
             maxLength: this.get<null | number>("inlayHints.maxLength"),
         };
     }
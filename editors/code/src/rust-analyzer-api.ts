@@ -29,6 +29,15 @@ export interface SyntaxTreeParams {
 export const syntaxTree = request<SyntaxTreeParams, string>("syntaxTree");
 
 
+export const viewHir = request<lc.TextDocumentPositionParams, string>("viewHir");
+
+
+export interface ViewCrateGraphParams {
+    hideSysroot: boolean;
+}
+export const viewCrateGraph = request<ViewCrateGraphParams, string>("viewCrateGraph");
+
+
 export interface ExpandMacroParams {
     textDocument: lc.TextDocumentIdentifier;
     position: Option<lc.Position>;
@@ -39,6 +48,21 @@ export interface ExpandedMacro {
 }
 export const expandMacro = request<ExpandMacroParams, Option<ExpandedMacro>>("expandMacro");
 
+export interface ExpandAllMacrosParams {
+    textDocument: lc.TextDocumentIdentifier;
+    position: Option<lc.Position>;
+}
+export interface ExpansionOrigin {
+    range: lc.Range;
+    location: lc.Location;
+    fromDefinition: boolean;
+}
+export interface ExpandedFile {
+    text: string;
+    origins: Vec<ExpansionOrigin>;
+}
+export const expandAllMacros = request<ExpandAllMacrosParams, Option<ExpandedFile>>("expandAllMacros");
+
 
 export interface FindMatchingBraceParams {
     textDocument: lc.TextDocumentIdentifier;
@@ -86,13 +110,37 @@ export interface Runnable {
 }
 export const runnables = request<RunnablesParams, Vec<Runnable>>("runnables");
 
-export type InlayHint = InlayHint.TypeHint | InlayHint.ParamHint | InlayHint.ChainingHint;
+export interface DiscoverTestsParams {
+    textDocument: Option<lc.TextDocumentIdentifier>;
+}
+export interface TestItem {
+    id: string;
+    parent: Option<string>;
+    kind: "crate" | "module" | "test";
+    label: string;
+    location: lc.Location;
+    runnable: Option<Runnable>;
+}
+export const discoverTests = request<DiscoverTestsParams, Vec<TestItem>>("discoverTests");
+
+export type InlayHint =
+    | InlayHint.TypeHint
+    | InlayHint.ParamHint
+    | InlayHint.ChainingHint
+    | InlayHint.LifetimeHint
+    | InlayHint.ClosureReturnTypeHint
+    | InlayHint.ClosureCaptureHint
+    | InlayHint.AdjustmentHint;
 
 export namespace InlayHint {
     export const enum Kind {
         TypeHint = "TypeHint",
         ParamHint = "ParameterHint",
         ChainingHint = "ChainingHint",
+        LifetimeHint = "LifetimeHint",
+        ClosureReturnTypeHint = "ClosureReturnTypeHint",
+        ClosureCaptureHint = "ClosureCaptureHint",
+        AdjustmentHint = "AdjustmentHint",
     }
     interface Common {
         range: lc.Range;
@@ -101,6 +149,10 @@ export namespace InlayHint {
     export type TypeHint = Common & { kind: Kind.TypeHint };
     export type ParamHint = Common & { kind: Kind.ParamHint };
     export type ChainingHint = Common & { kind: Kind.ChainingHint };
+    export type LifetimeHint = Common & { kind: Kind.LifetimeHint };
+    export type ClosureReturnTypeHint = Common & { kind: Kind.ClosureReturnTypeHint };
+    export type ClosureCaptureHint = Common & { kind: Kind.ClosureCaptureHint };
+    export type AdjustmentHint = Common & { kind: Kind.AdjustmentHint };
 }
 export interface InlayHintsParams {
     textDocument: lc.TextDocumentIdentifier;